@@ -0,0 +1,25 @@
+use ruddis::errors::{ApplicationError, Fallible};
+use ruddis::server::serve;
+use std::env;
+use std::net::TcpListener;
+
+const DEFAULT_PORT: u16 = 6379;
+
+fn port() -> Result<u16, ApplicationError> {
+    let mut args = env::args().skip(1);
+    match args.next().as_deref() {
+        None => Ok(DEFAULT_PORT),
+        Some("--port") => args
+            .next()
+            .fail_to("No port provided")?
+            .parse()
+            .map_err(|_| String::from("Port must be a number between 0 and 65535").into()),
+        Some(unknown) => Err(format!("Unknown argument: {}", unknown).into()),
+    }
+}
+
+fn main() -> Result<(), ApplicationError> {
+    let listener = TcpListener::bind(("127.0.0.1", port()?))?;
+    println!("ruddis-server listening on {}", listener.local_addr()?);
+    serve(listener)
+}
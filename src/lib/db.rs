@@ -0,0 +1,219 @@
+use super::domain::Data;
+use super::random::Random;
+use std::collections::HashMap;
+
+/// The keyspace's data along with the deadlines of the keys that expire,
+/// in milliseconds since the Unix epoch. A deadline only counts while its
+/// key is there: a command that empties a key leaves its deadline behind,
+/// to be dropped the next time the key is touched or the active expiry
+/// cycle comes across it.
+pub struct Db {
+    store: HashMap<Vec<u8>, Data>,
+    /// Where each key with a deadline is in `volatile`.
+    deadlines: HashMap<Vec<u8>, usize>,
+    /// The keys with a deadline, in no order, so that the active expiry
+    /// cycle can pick them at random.
+    volatile: Vec<(Vec<u8>, u64)>,
+    random: Random,
+}
+
+impl Db {
+    pub fn new() -> Self {
+        Db {
+            store: HashMap::new(),
+            deadlines: HashMap::new(),
+            volatile: Vec::new(),
+            random: Random::with_seed(0x9e37_79b9_7f4a_7c15),
+        }
+    }
+
+    pub fn store(&self) -> &HashMap<Vec<u8>, Data> {
+        &self.store
+    }
+
+    pub fn store_mut(&mut self) -> &mut HashMap<Vec<u8>, Data> {
+        &mut self.store
+    }
+
+    /// When a key expires, if it is there and has a deadline.
+    pub fn deadline(&self, key: &[u8]) -> Option<u64> {
+        if !self.store.contains_key(key) {
+            return None;
+        }
+        self.deadlines.get(key).map(|slot| self.volatile[*slot].1)
+    }
+
+    /// Gives a key a deadline, replacing any it had.
+    pub fn set_deadline(&mut self, key: &[u8], at: u64) {
+        match self.deadlines.get(key) {
+            Some(slot) => self.volatile[*slot].1 = at,
+            None => {
+                self.deadlines.insert(key.to_vec(), self.volatile.len());
+                self.volatile.push((key.to_vec(), at));
+            }
+        }
+    }
+
+    /// Takes a key's deadline away, returning whether it had one.
+    pub fn persist(&mut self, key: &[u8]) -> bool {
+        let had = self.deadline(key).is_some();
+        self.forget(key);
+        had
+    }
+
+    fn forget(&mut self, key: &[u8]) {
+        if let Some(slot) = self.deadlines.remove(key) {
+            self.volatile.swap_remove(slot);
+            if let Some((moved, _)) = self.volatile.get(slot) {
+                self.deadlines.insert(moved.clone(), slot);
+            }
+        }
+    }
+
+    /// Deletes a key whose deadline has passed, returning whether it did.
+    /// Every command runs this on its keys before it touches them, which is
+    /// all it takes for expired keys to look gone.
+    pub fn expire_if_due(&mut self, key: &[u8], now: u64) -> bool {
+        let at = match self.deadlines.get(key) {
+            Some(slot) => self.volatile[*slot].1,
+            None => return false,
+        };
+        let expired = at <= now && self.store.remove(key).is_some();
+        if expired || !self.store.contains_key(key) {
+            self.forget(key);
+        }
+        expired
+    }
+
+    /// Checks up to `samples` keys with deadlines, picked at random, and
+    /// deletes the ones that are due. Returns how many it checked and how
+    /// many of those it deleted, or found already gone.
+    pub fn expire_sample(&mut self, now: u64, samples: usize) -> (usize, usize) {
+        let (mut checked, mut expired) = (0, 0);
+        while checked < samples && !self.volatile.is_empty() {
+            let slot = self.random.below(self.volatile.len());
+            let (key, at) = &self.volatile[slot];
+            checked += 1;
+            if *at <= now || !self.store.contains_key(key) {
+                let key = key.clone();
+                self.store.remove(&key);
+                self.forget(&key);
+                expired += 1;
+            }
+        }
+        (checked, expired)
+    }
+}
+
+impl Default for Db {
+    fn default() -> Self {
+        Db::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Data, Db};
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+    use std::collections::HashMap;
+
+    #[derive(Debug, Clone)]
+    enum Op {
+        Insert(u8),
+        Remove(u8),
+        Expire(u8, u64),
+        Persist(u8),
+        Touch(u8),
+        Wait(u64),
+        Sample,
+    }
+
+    fn ops() -> impl Strategy<Value = Vec<Op>> {
+        vec(
+            prop_oneof![
+                (0u8..8).prop_map(Op::Insert),
+                (0u8..8).prop_map(Op::Remove),
+                (0u8..8, 0u64..50).prop_map(|(key, ttl)| Op::Expire(key, ttl)),
+                (0u8..8).prop_map(Op::Persist),
+                (0u8..8).prop_map(Op::Touch),
+                (0u64..20).prop_map(Op::Wait),
+                Just(Op::Sample),
+            ],
+            0..200,
+        )
+    }
+
+    proptest! {
+        #[test]
+        fn keys_live_until_their_deadlines(ops in ops()) {
+            let mut db = Db::new();
+            let mut model: HashMap<u8, Option<u64>> = HashMap::new();
+            let mut now = 0;
+            let alive = |model: &HashMap<u8, Option<u64>>, key: u8, now: u64| {
+                model.get(&key).is_some_and(|deadline| deadline.is_none_or(|at| at > now))
+            };
+            for op in ops {
+                match op {
+                    Op::Insert(key) => {
+                        db.expire_if_due(&[key], now);
+                        db.store_mut().insert(vec![key], Data::from(vec![key]));
+                        if !alive(&model, key, now) {
+                            model.insert(key, None);
+                        }
+                    }
+                    Op::Remove(key) => {
+                        db.expire_if_due(&[key], now);
+                        db.store_mut().remove(&[key][..]);
+                        model.remove(&key);
+                    }
+                    Op::Expire(key, ttl) => {
+                        db.expire_if_due(&[key], now);
+                        if db.store().contains_key(&[key][..]) {
+                            db.set_deadline(&[key], now + ttl);
+                            model.insert(key, Some(now + ttl));
+                        }
+                    }
+                    Op::Persist(key) => {
+                        db.expire_if_due(&[key], now);
+                        let had = alive(&model, key, now) && model[&key].is_some();
+                        assert_eq!(db.persist(&[key]), had);
+                        if had {
+                            model.insert(key, None);
+                        }
+                    }
+                    Op::Touch(key) => {
+                        db.expire_if_due(&[key], now);
+                        assert_eq!(db.store().contains_key(&[key][..]), alive(&model, key, now));
+                    }
+                    Op::Wait(ms) => now += ms,
+                    Op::Sample => {
+                        db.expire_sample(now, 4);
+                    }
+                }
+            }
+            (0u8..8).try_for_each(|key| {
+                db.expire_if_due(&[key], now);
+                prop_assert_eq!(db.store().contains_key(&[key][..]), alive(&model, key, now));
+                if alive(&model, key, now) {
+                    prop_assert_eq!(db.deadline(&[key]), model[&key]);
+                }
+                Ok(())
+            })?;
+        }
+    }
+
+    #[test]
+    fn sampling_finds_every_expired_key() {
+        let mut db = Db::new();
+        for key in 0..200u8 {
+            db.store_mut().insert(vec![key], Data::from(vec![key]));
+            db.set_deadline(&[key], if key % 2 == 0 { 10 } else { 1000 });
+        }
+        for _ in 0..1000 {
+            db.expire_sample(100, 20);
+        }
+        assert_eq!(db.store().len(), 100);
+        assert!(db.store().keys().all(|key| key[0] % 2 == 1));
+    }
+}
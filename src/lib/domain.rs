@@ -1,33 +1,197 @@
-use std::collections::HashSet;
+use super::expire::{self, Lifetime, When};
+use super::geo;
+use super::hash::Hash;
+use super::incr::parse_float;
+use super::list::insert::Position;
+use super::list::{pos, End};
+use super::stream::{self, Stream, StreamId};
+use super::string;
+use super::zset::{self, SortedSet};
+use std::borrow::Cow;
+use std::collections::{HashSet, VecDeque};
+use std::time::Duration;
 
-#[cfg(test)]
-use proptest_derive::Arbitrary;
+/// A string value. Like Redis, strings that are the canonical rendering of
+/// a 64-bit integer are stored as that integer, which is only an encoding:
+/// a primitive always behaves as the string it was built from. Because the
+/// encoding is canonical, `5` and `"5"` compare and hash the same.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct Primitive(Encoding);
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
-#[cfg_attr(test, derive(Arbitrary))]
-pub enum Primitive {
-    String(String),
-    Number(i64),
+enum Encoding {
+    Raw(Vec<u8>),
+    Int(i64),
+}
+
+impl Primitive {
+    /// The integer this string holds, if it is one in the strict sense
+    /// Redis uses for INCR: no sign but `-`, no leading zeros or spaces.
+    pub fn as_integer(&self) -> Option<i64> {
+        match self.0 {
+            Encoding::Int(n) => Some(n),
+            Encoding::Raw(_) => None,
+        }
+    }
+
+    /// The float this string holds, read the way Redis reads one for
+    /// INCRBYFLOAT, which leaves out infinities and NaN.
+    pub fn as_float(&self) -> Option<f64> {
+        match &self.0 {
+            Encoding::Int(n) => Some(*n as f64),
+            Encoding::Raw(bytes) => parse_float(bytes),
+        }
+    }
+
+    /// The length of the string, without rendering an integer to find it.
+    pub fn len(&self) -> usize {
+        match &self.0 {
+            Encoding::Raw(bytes) => bytes.len(),
+            Encoding::Int(n) => {
+                let sign = if *n < 0 { 1 } else { 0 };
+                sign + n
+                    .unsigned_abs()
+                    .checked_ilog10()
+                    .map_or(1, |digits| digits as usize + 1)
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The string's bytes, borrowed unless it has to be rendered.
+    pub fn as_bytes(&self) -> Cow<'_, [u8]> {
+        match &self.0 {
+            Encoding::Raw(bytes) => Cow::Borrowed(bytes),
+            Encoding::Int(n) => Cow::Owned(format!("{}", n).into_bytes()),
+        }
+    }
+
+    /// Edits the string's bytes in place, encoding the result afresh.
+    pub fn edit<T, F: FnOnce(&mut Vec<u8>) -> T>(&mut self, edit: F) -> T {
+        let mut bytes = std::mem::replace(self, Primitive(Encoding::Int(0))).into_bytes();
+        let edited = edit(&mut bytes);
+        *self = bytes.into();
+        edited
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match &self.0 {
+            Encoding::Raw(bytes) => bytes.clone(),
+            Encoding::Int(n) => format!("{}", n).into_bytes(),
+        }
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        match self.0 {
+            Encoding::Raw(bytes) => bytes,
+            Encoding::Int(n) => format!("{}", n).into_bytes(),
+        }
+    }
+}
+
+/// Renders arbitrary bytes the way redis-cli does: printable ASCII as is and
+/// everything else escaped, so that nothing is lost on the way to a terminal.
+pub struct Escaped<'a>(pub &'a [u8]);
+
+impl std::fmt::Display for Escaped<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.iter().try_for_each(|byte| match byte {
+            b'\\' => write!(f, "\\\\"),
+            b'"' => write!(f, "\\\""),
+            b'\n' => write!(f, "\\n"),
+            b'\r' => write!(f, "\\r"),
+            b'\t' => write!(f, "\\t"),
+            0x07 => write!(f, "\\a"),
+            0x08 => write!(f, "\\b"),
+            byte if byte.is_ascii_graphic() || *byte == b' ' => write!(f, "{}", char::from(*byte)),
+            byte => write!(f, "\\x{:02x}", byte),
+        })
+    }
 }
 
 impl std::fmt::Display for Primitive {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Primitive::String(s) => write!(f, "\"{}\"", s),
-            Primitive::Number(n) => write!(f, "{}", n),
+        match &self.0 {
+            Encoding::Raw(bytes) => write!(f, "\"{}\"", Escaped(bytes)),
+            Encoding::Int(n) => write!(f, "\"{}\"", n),
+        }
+    }
+}
+
+impl From<Vec<u8>> for Primitive {
+    fn from(bytes: Vec<u8>) -> Self {
+        // No 64-bit integer takes more than 20 bytes, and long strings
+        // shouldn't have to be scanned to find that out.
+        let canonical = if bytes.len() > 20 {
+            None
+        } else {
+            match std::str::from_utf8(&bytes).map(str::parse::<i64>) {
+                Ok(Ok(n)) if format!("{}", n).as_bytes() == &bytes[..] => Some(n),
+                _ => None,
+            }
+        };
+        match canonical {
+            Some(n) => Primitive(Encoding::Int(n)),
+            None => Primitive(Encoding::Raw(bytes)),
         }
     }
 }
 
 impl From<String> for Primitive {
     fn from(primitive: String) -> Self {
-        Primitive::String(primitive)
+        primitive.into_bytes().into()
+    }
+}
+
+impl From<&str> for Primitive {
+    fn from(primitive: &str) -> Self {
+        primitive.as_bytes().to_vec().into()
     }
 }
 
 impl From<i64> for Primitive {
     fn from(primitive: i64) -> Self {
-        Primitive::Number(primitive)
+        Primitive(Encoding::Int(primitive))
+    }
+}
+
+/// Renders a finite float the way Redis renders the result of INCRBYFLOAT:
+/// in plain decimal notation, rounded to at most 17 decimal places, with no
+/// trailing zeros and no negative zero.
+impl From<f64> for Primitive {
+    fn from(n: f64) -> Self {
+        let shortest = format!("{}", n);
+        let decimals = shortest.split('.').nth(1).map_or(0, str::len);
+        let mut rendered = if decimals > 17 {
+            format!("{:.17}", n)
+        } else {
+            shortest
+        };
+        if rendered.contains('.') {
+            rendered.truncate(rendered.trim_end_matches('0').trim_end_matches('.').len());
+        }
+        if rendered == "-0" {
+            rendered.remove(0);
+        }
+        rendered.into()
+    }
+}
+
+#[cfg(test)]
+impl proptest::arbitrary::Arbitrary for Primitive {
+    type Parameters = ();
+    type Strategy = proptest::strategy::BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        use proptest::prelude::*;
+        prop_oneof![
+            any::<i64>().prop_map(Primitive::from),
+            proptest::collection::vec(any::<u8>(), 0..16).prop_map(Primitive::from),
+        ]
+        .boxed()
     }
 }
 
@@ -35,6 +199,10 @@ impl From<i64> for Primitive {
 pub enum Data {
     Primitive(Primitive),
     Set(HashSet<Primitive>),
+    List(VecDeque<Primitive>),
+    Hash(Hash),
+    SortedSet(SortedSet),
+    Stream(Stream),
 }
 
 impl std::fmt::Display for Data {
@@ -42,6 +210,10 @@ impl std::fmt::Display for Data {
         match self {
             Data::Primitive(p) => p.fmt(f),
             Data::Set(_) => write!(f, "<Set>"),
+            Data::List(_) => write!(f, "<List>"),
+            Data::Hash(_) => write!(f, "<Hash>"),
+            Data::SortedSet(_) => write!(f, "<SortedSet>"),
+            Data::Stream(_) => write!(f, "<Stream>"),
         }
     }
 }
@@ -52,15 +224,21 @@ impl From<Primitive> for Data {
     }
 }
 
+impl From<Vec<u8>> for Data {
+    fn from(data: Vec<u8>) -> Self {
+        Data::Primitive(data.into())
+    }
+}
+
 impl From<String> for Data {
     fn from(data: String) -> Self {
-        Data::Primitive(Primitive::String(data))
+        Data::Primitive(data.into())
     }
 }
 
 impl From<i64> for Data {
     fn from(data: i64) -> Self {
-        Data::Primitive(Primitive::Number(data))
+        Data::Primitive(data.into())
     }
 }
 
@@ -70,18 +248,466 @@ impl From<HashSet<Primitive>> for Data {
     }
 }
 
+#[derive(Debug, PartialEq, Clone)]
+pub enum Reply {
+    Simple(String),
+    Error(String),
+    Integer(i64),
+    Bulk(Vec<u8>),
+    Nil,
+    /// The null Redis replies with where it would otherwise send an array,
+    /// which RESP2 tells apart from a null bulk string.
+    NullArray,
+    Array(Vec<Reply>),
+    Set(Vec<Reply>),
+    Map(Vec<(Reply, Reply)>),
+    Double(f64),
+    Boolean(bool),
+}
+
+impl std::fmt::Display for Reply {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Reply::Simple(s) => write!(f, "{}", s),
+            Reply::Error(e) => write!(f, "(error) {}", e),
+            Reply::Integer(n) => write!(f, "(integer) {}", n),
+            Reply::Bulk(s) => write!(f, "\"{}\"", Escaped(s)),
+            Reply::Nil | Reply::NullArray => write!(f, "(nil)"),
+            Reply::Array(items) | Reply::Set(items) if items.is_empty() => {
+                write!(f, "(empty array)")
+            }
+            Reply::Array(items) | Reply::Set(items) => items
+                .iter()
+                .enumerate()
+                .try_for_each(|(i, item)| writeln!(f, "{}) {}", i + 1, item)),
+            Reply::Map(entries) if entries.is_empty() => write!(f, "(empty hash)"),
+            Reply::Map(entries) => entries
+                .iter()
+                .enumerate()
+                .try_for_each(|(i, (k, v))| writeln!(f, "{}# {} => {}", i + 1, k, v)),
+            Reply::Double(d) => write!(f, "(double) {}", d),
+            Reply::Boolean(b) => write!(f, "({})", b),
+        }
+    }
+}
+
+impl From<Primitive> for Reply {
+    fn from(primitive: Primitive) -> Self {
+        Reply::Bulk(primitive.into_bytes())
+    }
+}
+
+impl<T: Into<Reply>> From<Option<T>> for Reply {
+    fn from(reply: Option<T>) -> Self {
+        reply.map_or(Reply::Nil, T::into)
+    }
+}
+
+impl From<Vec<Primitive>> for Reply {
+    fn from(primitives: Vec<Primitive>) -> Self {
+        Reply::Array(primitives.into_iter().map(Reply::from).collect())
+    }
+}
+
+impl From<Vec<Option<Primitive>>> for Reply {
+    fn from(primitives: Vec<Option<Primitive>>) -> Self {
+        Reply::Array(primitives.into_iter().map(Reply::from).collect())
+    }
+}
+
+impl From<Vec<(Primitive, Primitive)>> for Reply {
+    fn from(pairs: Vec<(Primitive, Primitive)>) -> Self {
+        Reply::Map(
+            pairs
+                .into_iter()
+                .map(|(k, v)| (k.into(), v.into()))
+                .collect(),
+        )
+    }
+}
+
+impl From<i64> for Reply {
+    fn from(n: i64) -> Self {
+        Reply::Integer(n)
+    }
+}
+
+impl From<usize> for Reply {
+    fn from(n: usize) -> Self {
+        Reply::Integer(n as i64)
+    }
+}
+
+impl From<f64> for Reply {
+    fn from(d: f64) -> Self {
+        Reply::Double(d)
+    }
+}
+
+impl From<HashSet<Primitive>> for Reply {
+    fn from(set: HashSet<Primitive>) -> Self {
+        Reply::Set(set.into_iter().map(Reply::from).collect())
+    }
+}
+
+impl From<bool> for Reply {
+    fn from(b: bool) -> Self {
+        Reply::Integer(if b { 1 } else { 0 })
+    }
+}
+
 pub enum Command {
-    Echo(String),
-    Set(String, Data),
-    Get(String),
-    Incr(String),
-    Sadd(String, Vec<Primitive>),
-    Scard(String),
-    Sismember(String, Primitive),
-    Sdiff(String, Vec<String>),
-    SdiffStore(String, String, Vec<String>),
-    Sinter(Vec<String>),
-    SinterStore(String, Vec<String>),
-    Sunion(Vec<String>),
-    SunionStore(String, Vec<String>),
+    Hello(Option<i64>),
+    Echo(Vec<u8>),
+    Set(Vec<u8>, Primitive, string::set::Options),
+    Setnx(Vec<u8>, Primitive),
+    Msetnx(Vec<(Vec<u8>, Primitive)>),
+    Get(Vec<u8>),
+    Getdel(Vec<u8>),
+    Getex(Vec<u8>, Lifetime),
+    Mget(Vec<Vec<u8>>),
+    Mset(Vec<(Vec<u8>, Primitive)>),
+    Append(Vec<u8>, Vec<u8>),
+    Strlen(Vec<u8>),
+    Getrange(Vec<u8>, i64, i64),
+    Setrange(Vec<u8>, usize, Vec<u8>),
+    Lcs(Vec<u8>, Vec<u8>, string::lcs::Options),
+    Setbit(Vec<u8>, u64, bool),
+    Getbit(Vec<u8>, u64),
+    Bitcount(Vec<u8>, string::bits::Range),
+    Bitpos(Vec<u8>, bool, string::bits::Range),
+    Bitop(string::bitop::Op, Vec<u8>, Vec<Vec<u8>>),
+    Bitfield(Vec<u8>, Vec<string::bitfield::Op>),
+    Pfadd(Vec<u8>, Vec<Vec<u8>>),
+    Pfcount(Vec<Vec<u8>>),
+    Pfmerge(Vec<u8>, Vec<Vec<u8>>),
+    Incrby(Vec<u8>, i64),
+    Incrbyfloat(Vec<u8>, f64),
+    Expire(Vec<u8>, When, expire::set::Options),
+    Ttl(Vec<u8>),
+    Pttl(Vec<u8>),
+    Persist(Vec<u8>),
+    Sadd(Vec<u8>, Vec<Primitive>),
+    Scard(Vec<u8>),
+    Sismember(Vec<u8>, Primitive),
+    Smismember(Vec<u8>, Vec<Primitive>),
+    Smembers(Vec<u8>),
+    Srem(Vec<u8>, Vec<Primitive>),
+    Spop(Vec<u8>, Option<usize>),
+    Srandmember(Vec<u8>, Option<i64>),
+    Smove(Vec<u8>, Vec<u8>, Primitive),
+    Sdiff(Vec<u8>, Vec<Vec<u8>>),
+    SdiffStore(Vec<u8>, Vec<u8>, Vec<Vec<u8>>),
+    Sinter(Vec<Vec<u8>>),
+    Sintercard(Vec<Vec<u8>>, usize),
+    SinterStore(Vec<u8>, Vec<Vec<u8>>),
+    Sunion(Vec<Vec<u8>>),
+    SunionStore(Vec<u8>, Vec<Vec<u8>>),
+    Lpush(Vec<u8>, Vec<Primitive>),
+    Rpush(Vec<u8>, Vec<Primitive>),
+    Lpop(Vec<u8>, Option<usize>),
+    Rpop(Vec<u8>, Option<usize>),
+    Lrange(Vec<u8>, i64, i64),
+    Llen(Vec<u8>),
+    Lindex(Vec<u8>, i64),
+    Lset(Vec<u8>, i64, Primitive),
+    Lrem(Vec<u8>, i64, Primitive),
+    Ltrim(Vec<u8>, i64, i64),
+    Linsert(Vec<u8>, Position, Primitive, Primitive),
+    Lpos(Vec<u8>, Primitive, pos::Options),
+    Lmove(Vec<u8>, Vec<u8>, End, End),
+    Blpop(Vec<Vec<u8>>, Option<Duration>),
+    Brpop(Vec<Vec<u8>>, Option<Duration>),
+    Blmove(Vec<u8>, Vec<u8>, End, End, Option<Duration>),
+    Hset(Vec<u8>, Vec<(Primitive, Primitive)>),
+    Hsetnx(Vec<u8>, Primitive, Primitive),
+    Hget(Vec<u8>, Primitive),
+    Hmget(Vec<u8>, Vec<Primitive>),
+    Hdel(Vec<u8>, Vec<Primitive>),
+    Hgetall(Vec<u8>),
+    Hkeys(Vec<u8>),
+    Hvals(Vec<u8>),
+    Hlen(Vec<u8>),
+    Hexists(Vec<u8>, Primitive),
+    Hincrby(Vec<u8>, Primitive, i64),
+    Hincrbyfloat(Vec<u8>, Primitive, f64),
+    Hstrlen(Vec<u8>, Primitive),
+    Zadd(Vec<u8>, zset::add::Options, Vec<(f64, Vec<u8>)>),
+    ZaddIncr(Vec<u8>, zset::add::Options, f64, Vec<u8>),
+    Zincrby(Vec<u8>, f64, Vec<u8>),
+    Zrange(Vec<u8>, zset::range::Range, bool),
+    Zrank(Vec<u8>, Vec<u8>),
+    Zscore(Vec<u8>, Vec<u8>),
+    Zrem(Vec<u8>, Vec<Vec<u8>>),
+    ZunionStore(Vec<u8>, Vec<Vec<u8>>, Vec<f64>, zset::Aggregate),
+    ZinterStore(Vec<u8>, Vec<Vec<u8>>, Vec<f64>, zset::Aggregate),
+    ZdiffStore(Vec<u8>, Vec<Vec<u8>>),
+    Geoadd(Vec<u8>, zset::add::Options, Vec<(f64, f64, Vec<u8>)>),
+    Geodist(Vec<u8>, Vec<u8>, Vec<u8>, geo::Unit),
+    Geopos(Vec<u8>, Vec<Vec<u8>>),
+    Geohash(Vec<u8>, Vec<Vec<u8>>),
+    Geosearch(Vec<u8>, geo::search::Query, geo::search::With),
+    GeosearchStore(Vec<u8>, Vec<u8>, geo::search::Query, bool),
+    Xadd(
+        Vec<u8>,
+        stream::add::Options,
+        stream::add::NewId,
+        Vec<(Primitive, Primitive)>,
+    ),
+    Xrange(Vec<u8>, StreamId, StreamId, Option<usize>, bool),
+    Xread(Vec<Vec<u8>>, Vec<stream::read::After>, Option<usize>),
+    XreadBlock(
+        Vec<Vec<u8>>,
+        Vec<stream::read::After>,
+        Option<usize>,
+        Option<Duration>,
+    ),
+    Xlen(Vec<u8>),
+    Xtrim(Vec<u8>, stream::trim::Trim),
+    XgroupCreate(Vec<u8>, Vec<u8>, stream::read::After, bool),
+    XgroupSetId(Vec<u8>, Vec<u8>, stream::read::After),
+    XgroupDestroy(Vec<u8>, Vec<u8>),
+    XgroupCreateConsumer(Vec<u8>, Vec<u8>, Vec<u8>),
+    XgroupDelConsumer(Vec<u8>, Vec<u8>, Vec<u8>),
+    XreadGroup(
+        stream::readgroup::Options,
+        Vec<Vec<u8>>,
+        Vec<stream::readgroup::Start>,
+    ),
+    XreadGroupBlock(
+        stream::readgroup::Options,
+        Vec<Vec<u8>>,
+        Vec<stream::readgroup::Start>,
+        Option<Duration>,
+    ),
+    Xack(Vec<u8>, Vec<u8>, Vec<StreamId>),
+    Xpending(Vec<u8>, Vec<u8>, Option<stream::pending::Filter>),
+    Xclaim(
+        Vec<u8>,
+        Vec<u8>,
+        Vec<u8>,
+        Vec<StreamId>,
+        stream::claim::Options,
+    ),
+    Xautoclaim(Vec<u8>, Vec<u8>, Vec<u8>, stream::autoclaim::Options),
+}
+
+impl Command {
+    /// Every key the command reads or writes.
+    pub fn keys(&self) -> Vec<&[u8]> {
+        match self {
+            Command::Hello(_) | Command::Echo(_) => vec![],
+            Command::Set(key, ..)
+            | Command::Setnx(key, _)
+            | Command::Get(key)
+            | Command::Getdel(key)
+            | Command::Getex(key, _)
+            | Command::Append(key, _)
+            | Command::Strlen(key)
+            | Command::Getrange(key, ..)
+            | Command::Setrange(key, ..)
+            | Command::Setbit(key, ..)
+            | Command::Getbit(key, _)
+            | Command::Bitcount(key, _)
+            | Command::Bitpos(key, ..)
+            | Command::Bitfield(key, _)
+            | Command::Incrby(key, _)
+            | Command::Incrbyfloat(key, _)
+            | Command::Expire(key, ..)
+            | Command::Ttl(key)
+            | Command::Pttl(key)
+            | Command::Persist(key)
+            | Command::Pfadd(key, _)
+            | Command::Sadd(key, _)
+            | Command::Scard(key)
+            | Command::Sismember(key, _)
+            | Command::Smismember(key, _)
+            | Command::Smembers(key)
+            | Command::Srem(key, _)
+            | Command::Spop(key, _)
+            | Command::Srandmember(key, _)
+            | Command::Lpush(key, _)
+            | Command::Rpush(key, _)
+            | Command::Lpop(key, _)
+            | Command::Rpop(key, _)
+            | Command::Lrange(key, ..)
+            | Command::Llen(key)
+            | Command::Lindex(key, _)
+            | Command::Lset(key, ..)
+            | Command::Lrem(key, ..)
+            | Command::Ltrim(key, ..)
+            | Command::Linsert(key, ..)
+            | Command::Lpos(key, ..)
+            | Command::Hset(key, _)
+            | Command::Hsetnx(key, ..)
+            | Command::Hget(key, _)
+            | Command::Hmget(key, _)
+            | Command::Hdel(key, _)
+            | Command::Hgetall(key)
+            | Command::Hkeys(key)
+            | Command::Hvals(key)
+            | Command::Hlen(key)
+            | Command::Hexists(key, _)
+            | Command::Hincrby(key, ..)
+            | Command::Hincrbyfloat(key, ..)
+            | Command::Hstrlen(key, _)
+            | Command::Zadd(key, ..)
+            | Command::ZaddIncr(key, ..)
+            | Command::Zincrby(key, ..)
+            | Command::Zrange(key, ..)
+            | Command::Zrank(key, _)
+            | Command::Zscore(key, _)
+            | Command::Zrem(key, _)
+            | Command::Geoadd(key, ..)
+            | Command::Geodist(key, ..)
+            | Command::Geopos(key, _)
+            | Command::Geohash(key, _)
+            | Command::Geosearch(key, ..)
+            | Command::Xadd(key, ..)
+            | Command::Xrange(key, ..)
+            | Command::Xlen(key)
+            | Command::Xtrim(key, _)
+            | Command::XgroupCreate(key, ..)
+            | Command::XgroupSetId(key, ..)
+            | Command::XgroupDestroy(key, _)
+            | Command::XgroupCreateConsumer(key, ..)
+            | Command::XgroupDelConsumer(key, ..)
+            | Command::Xack(key, ..)
+            | Command::Xpending(key, ..)
+            | Command::Xclaim(key, ..)
+            | Command::Xautoclaim(key, ..) => vec![key],
+            Command::Mset(pairs) | Command::Msetnx(pairs) => {
+                pairs.iter().map(|(key, _)| key.as_slice()).collect()
+            }
+            Command::Lcs(key1, key2, _) => vec![key1, key2],
+            Command::Lmove(source, destination, ..) | Command::Blmove(source, destination, ..) => {
+                vec![source, destination]
+            }
+            Command::GeosearchStore(destination, key, ..) => vec![destination, key],
+            Command::Smove(source, destination, _) => vec![source, destination],
+            Command::Mget(keys)
+            | Command::Sinter(keys)
+            | Command::Sintercard(keys, _)
+            | Command::Sunion(keys)
+            | Command::Pfcount(keys)
+            | Command::Blpop(keys, _)
+            | Command::Brpop(keys, _)
+            | Command::Xread(keys, ..)
+            | Command::XreadBlock(keys, ..)
+            | Command::XreadGroup(_, keys, _)
+            | Command::XreadGroupBlock(_, keys, ..) => keys.iter().map(Vec::as_slice).collect(),
+            Command::Sdiff(key, keys)
+            | Command::Bitop(_, key, keys)
+            | Command::Pfmerge(key, keys)
+            | Command::SinterStore(key, keys)
+            | Command::SunionStore(key, keys)
+            | Command::ZunionStore(key, keys, ..)
+            | Command::ZinterStore(key, keys, ..)
+            | Command::ZdiffStore(key, keys) => std::iter::once(key)
+                .chain(keys)
+                .map(Vec::as_slice)
+                .collect(),
+            Command::SdiffStore(destination, key, keys) => std::iter::once(destination)
+                .chain(std::iter::once(key))
+                .chain(keys)
+                .map(Vec::as_slice)
+                .collect(),
+        }
+    }
+
+    /// The keys a blocking command waits on and for how long, where no
+    /// timeout means waiting indefinitely.
+    pub fn blocks_on(&self) -> Option<(&[Vec<u8>], Option<Duration>)> {
+        match self {
+            Command::Blpop(keys, timeout) | Command::Brpop(keys, timeout) => Some((keys, *timeout)),
+            Command::Blmove(source, _, _, _, timeout) => {
+                Some((std::slice::from_ref(source), *timeout))
+            }
+            Command::XreadBlock(keys, _, _, timeout)
+            | Command::XreadGroupBlock(_, keys, _, timeout) => Some((keys, *timeout)),
+            _ => None,
+        }
+    }
+
+    /// Blocked clients that take from a key are served in the order they
+    /// blocked in. Readers leave the key as it was, so none of them has to
+    /// wait for another.
+    pub fn waits_in_line(&self) -> bool {
+        !matches!(self, Command::XreadBlock(..))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Command, Primitive};
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+    use std::collections::HashSet;
+
+    proptest! {
+        #[test]
+        fn numeric_strings_are_numbers(n in any::<i64>()) {
+            let s = Primitive::from(format!("{}", n));
+            assert_eq!(s.as_integer(), Some(n));
+            assert_eq!(s, Primitive::from(n))
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn sets_agree_on_numeric_strings(n in any::<i64>()) {
+            let set: HashSet<Primitive> = vec![Primitive::from(n)].into_iter().collect();
+            assert!(set.contains(&Primitive::from(format!("{}", n))))
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn non_canonical_numbers_are_strings(s in "(0|\\+|-0| )[0-9]+|[0-9]+ ") {
+            assert_eq!(Primitive::from(s.clone()).as_integer(), None);
+            assert_eq!(Primitive::from(s.clone()).into_bytes(), s.into_bytes())
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn lengths_match_the_bytes(primitive in any::<Primitive>()) {
+            assert_eq!(primitive.len(), primitive.to_bytes().len())
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn floats_render_in_plain_decimals(n in -1e30f64..1e30) {
+            let rendered = Primitive::from(n).into_bytes();
+            assert!(rendered.iter().all(|b| b.is_ascii_digit() || *b == b'-' || *b == b'.'));
+            let parsed = Primitive::from(rendered).as_float().unwrap();
+            assert!((parsed - n).abs() <= 1e-17f64.max(n.abs() * 1e-15))
+        }
+    }
+
+    #[test]
+    fn floats_render_like_redis() {
+        let rendered = |n: f64| String::from_utf8(Primitive::from(n).into_bytes()).unwrap();
+        assert_eq!(rendered(10.5 + 0.1), "10.6");
+        assert_eq!(rendered(5200.0), "5200");
+        assert_eq!(rendered(-0.0), "0");
+        assert_eq!(rendered(1e-20), "0");
+        assert_eq!(rendered(-1e-20), "0");
+        assert_eq!(rendered(1.5e-17), "0.00000000000000002");
+        assert_eq!(rendered(3e20), "300000000000000000000");
+    }
+
+    proptest! {
+        #[test]
+        fn bytes_round_trip(bytes in vec(any::<u8>(), 0..64)) {
+            assert_eq!(Primitive::from(bytes.clone()).to_bytes(), bytes)
+        }
+    }
+
+    #[test]
+    fn hyperloglog_elements_are_not_keys() {
+        let command = Command::Pfadd(b"hll".to_vec(), vec![b"a".to_vec(), b"b".to_vec()]);
+        assert_eq!(command.keys(), vec![&b"hll"[..]]);
+    }
 }
@@ -0,0 +1,679 @@
+use super::db::Db;
+use super::domain::{Command, Data, Primitive, Reply};
+use super::errors::ApplicationError;
+use super::expire;
+use super::geo;
+use super::list::{self, End};
+use super::stream::readgroup::{Delivered, Start};
+use super::stream::{self, Entry, Fields, StreamId};
+use super::{hash, hyperloglog, incr, set, string, zset};
+use std::collections::HashMap;
+
+/// Runs a command at `now`, in milliseconds since the Unix epoch. Any of
+/// its keys that are due to expire are deleted first, so no command ever
+/// sees an expired key.
+pub fn execute(db: &mut Db, command: Command, now: u64) -> Result<Reply, ApplicationError> {
+    expire_keys(db, &command, now);
+    let store = db.store_mut();
+    match command {
+        Command::Hello(_) => Err(String::from("HELLO is only available over a connection").into()),
+        Command::Echo(echoed) => Ok(Reply::Bulk(echoed)),
+        Command::Set(key, value, options) => {
+            let (set, old) = string::set::command(db, &key, value, &options, now)?;
+            Ok(if options.get {
+                old.into()
+            } else if set {
+                Reply::Simple("OK".into())
+            } else {
+                Reply::Nil
+            })
+        }
+        Command::Setnx(key, value) => {
+            let options = string::set::Options {
+                nx: true,
+                ..Default::default()
+            };
+            let (set, _) = string::set::command(db, &key, value, &options, now)?;
+            Ok(set.into())
+        }
+        Command::Msetnx(pairs) => Ok(string::set::multi_nx_command(db, pairs).into()),
+        Command::Get(key) => string::get::command(store, &key).map(Reply::from),
+        Command::Getdel(key) => string::get::del_command(db, &key).map(Reply::from),
+        Command::Getex(key, lifetime) => {
+            string::get::ex_command(db, &key, lifetime, now).map(Reply::from)
+        }
+        Command::Mget(keys) => Ok(string::get::multi_command(store, &keys).into()),
+        Command::Mset(pairs) => {
+            string::set::multi_command(db, pairs);
+            Ok(Reply::Simple("OK".into()))
+        }
+        Command::Append(key, value) => {
+            string::append::command(store, &key, &value).map(Reply::from)
+        }
+        Command::Strlen(key) => string::strlen::command(store, &key).map(Reply::from),
+        Command::Getrange(key, start, end) => {
+            string::range::command(store, &key, start, end).map(Reply::Bulk)
+        }
+        Command::Setrange(key, offset, value) => {
+            string::range::set_command(store, &key, offset, &value).map(Reply::from)
+        }
+        Command::Lcs(key1, key2, options) => {
+            let lcs = string::lcs::command(store, &key1, &key2)?;
+            Ok(lcs_reply(lcs, &options))
+        }
+        Command::Setbit(key, offset, bit) => {
+            let previous = string::bit::set_command(store, &key, offset, bit)?;
+            Ok(i64::from(previous).into())
+        }
+        Command::Getbit(key, offset) => {
+            let bit = string::bit::command(store, &key, offset)?;
+            Ok(i64::from(bit).into())
+        }
+        Command::Bitcount(key, range) => {
+            let count = string::bitcount::command(store, &key, range)?;
+            Ok((count as i64).into())
+        }
+        Command::Bitpos(key, bit, range) => {
+            string::bitpos::command(store, &key, bit, range).map(Reply::from)
+        }
+        Command::Bitop(op, destination, keys) => {
+            let stored = string::bitop::store_command(store, op, &destination, &keys)?;
+            db.persist(&destination);
+            Ok(stored.into())
+        }
+        Command::Bitfield(key, ops) => {
+            let replies = string::bitfield::command(store, &key, &ops)?;
+            Ok(Reply::Array(replies.into_iter().map(Reply::from).collect()))
+        }
+        Command::Pfadd(key, elements) => {
+            let changed = hyperloglog::add::command(store, &key, &elements)?;
+            Ok(i64::from(changed).into())
+        }
+        Command::Pfcount(keys) => {
+            let count = hyperloglog::count::command(store, &keys)?;
+            Ok((count as i64).into())
+        }
+        Command::Pfmerge(destination, keys) => {
+            hyperloglog::merge::store_command(store, &destination, &keys)?;
+            Ok(Reply::Simple("OK".into()))
+        }
+        Command::Incrby(key, by) => incr::command(store, &key, by).map(Reply::from),
+        Command::Incrbyfloat(key, by) => incr::float_command(store, &key, by).map(Reply::from),
+        Command::Expire(key, when, options) => {
+            expire::set::command(db, &key, when, options, now).map(Reply::from)
+        }
+        Command::Ttl(key) => Ok(expire::ttl::seconds_command(db, &key, now).into()),
+        Command::Pttl(key) => Ok(expire::ttl::command(db, &key, now).into()),
+        Command::Persist(key) => Ok(expire::persist::command(db, &key).into()),
+        Command::Sadd(key, values) => set::add::command(store, &key, values).map(Reply::from),
+        Command::Scard(key) => set::card::command(store, &key).map(Reply::from),
+        Command::Sismember(key, member) => {
+            set::ismember::command(store, &key, &member).map(Reply::from)
+        }
+        Command::Smismember(key, members) => Ok(Reply::Array(
+            set::mismember::command(store, &key, &members)?
+                .into_iter()
+                .map(Reply::from)
+                .collect(),
+        )),
+        Command::Smembers(key) => set::members::command(store, &key).map(Reply::from),
+        Command::Srem(key, members) => set::rem::command(store, &key, &members).map(Reply::from),
+        Command::Spop(key, count) => {
+            let popped = set::pop::command(store, &key, count.unwrap_or(1))?;
+            Ok(match count {
+                Some(_) => popped.into(),
+                None => popped.into_iter().next().into(),
+            })
+        }
+        Command::Srandmember(key, count) => {
+            let picked = set::randmember::command(store, &key, count.unwrap_or(1))?;
+            Ok(match count {
+                Some(_) => picked.into(),
+                None => picked.into_iter().next().into(),
+            })
+        }
+        Command::Smove(source, destination, member) => {
+            set::smove::command(store, &source, &destination, member).map(Reply::from)
+        }
+        Command::Sdiff(key, keys) => set::diff::command(store, &key, &keys).map(Reply::from),
+        Command::SdiffStore(destination, base_key, keys) => {
+            let stored = set::diff::store_command(store, &destination, &base_key, &keys)?;
+            db.persist(&destination);
+            Ok(stored.into())
+        }
+        Command::Sinter(keys) => set::inter::command(store, &keys).map(Reply::from),
+        Command::Sintercard(keys, limit) => {
+            set::intercard::command(store, &keys, limit).map(Reply::from)
+        }
+        Command::SinterStore(destination, keys) => {
+            let stored = set::inter::store_command(store, &destination, &keys)?;
+            db.persist(&destination);
+            Ok(stored.into())
+        }
+        Command::Sunion(keys) => set::union::command(store, &keys).map(Reply::from),
+        Command::SunionStore(destination, keys) => {
+            let stored = set::union::store_command(store, &destination, &keys)?;
+            db.persist(&destination);
+            Ok(stored.into())
+        }
+        Command::Lpush(key, values) => {
+            list::push::command(store, &key, values, End::Left).map(Reply::from)
+        }
+        Command::Rpush(key, values) => {
+            list::push::command(store, &key, values, End::Right).map(Reply::from)
+        }
+        Command::Lpop(key, count) => pop(store, &key, count, End::Left),
+        Command::Rpop(key, count) => pop(store, &key, count, End::Right),
+        Command::Lrange(key, start, stop) => {
+            list::range::command(store, &key, start, stop).map(Reply::from)
+        }
+        Command::Llen(key) => list::len::command(store, &key).map(Reply::from),
+        Command::Lindex(key, index) => list::index::command(store, &key, index).map(Reply::from),
+        Command::Lset(key, index, value) => {
+            list::set::command(store, &key, index, value).map(|_| Reply::Simple("OK".into()))
+        }
+        Command::Lrem(key, count, value) => {
+            list::rem::command(store, &key, count, &value).map(Reply::from)
+        }
+        Command::Ltrim(key, start, stop) => {
+            list::trim::command(store, &key, start, stop).map(|_| Reply::Simple("OK".into()))
+        }
+        Command::Linsert(key, position, pivot, value) => {
+            list::insert::command(store, &key, position, &pivot, value).map(Reply::from)
+        }
+        Command::Lpos(key, value, options) => {
+            let indexes = list::pos::command(store, &key, &value, options)?;
+            let mut replies = indexes.into_iter().map(Reply::from);
+            Ok(match options.count {
+                Some(_) => Reply::Array(replies.collect()),
+                None => replies.next().unwrap_or(Reply::Nil),
+            })
+        }
+        Command::Lmove(source, destination, from, to) => {
+            list::lmove::command(store, &source, &destination, from, to).map(Reply::from)
+        }
+        Command::Hset(key, pairs) => hash::set::command(store, &key, pairs).map(Reply::from),
+        Command::Hsetnx(key, field, value) => {
+            hash::set::nx_command(store, &key, field, value).map(Reply::from)
+        }
+        Command::Hget(key, field) => hash::get::command(store, &key, &field).map(Reply::from),
+        Command::Hmget(key, fields) => {
+            hash::get::multi_command(store, &key, &fields).map(Reply::from)
+        }
+        Command::Hdel(key, fields) => hash::del::command(store, &key, &fields).map(Reply::from),
+        Command::Hgetall(key) => hash::getall::command(store, &key).map(Reply::from),
+        Command::Hkeys(key) => hash::getall::keys_command(store, &key).map(Reply::from),
+        Command::Hvals(key) => hash::getall::vals_command(store, &key).map(Reply::from),
+        Command::Hlen(key) => hash::len::command(store, &key).map(Reply::from),
+        Command::Hexists(key, field) => hash::exists::command(store, &key, &field).map(Reply::from),
+        Command::Hincrby(key, field, by) => {
+            hash::incr::command(store, &key, field, by).map(Reply::from)
+        }
+        Command::Hincrbyfloat(key, field, by) => {
+            hash::incr::float_command(store, &key, field, by).map(Reply::from)
+        }
+        Command::Hstrlen(key, field) => hash::strlen::command(store, &key, &field).map(Reply::from),
+        Command::Zadd(key, options, pairs) => {
+            zset::add::command(store, &key, options, pairs).map(Reply::from)
+        }
+        Command::ZaddIncr(key, options, by, member) => {
+            zset::add::incr_command(store, &key, options, by, member).map(Reply::from)
+        }
+        Command::Zincrby(key, by, member) => {
+            zset::add::incr_command(store, &key, Default::default(), by, member).map(Reply::from)
+        }
+        Command::Zrange(key, query, with_scores) => {
+            let members = zset::range::command(store, &key, &query)?;
+            Ok(Reply::Array(
+                members
+                    .into_iter()
+                    .flat_map(|(member, score)| {
+                        let score = if with_scores {
+                            Some(Reply::Double(score))
+                        } else {
+                            None
+                        };
+                        std::iter::once(Reply::Bulk(member)).chain(score)
+                    })
+                    .collect(),
+            ))
+        }
+        Command::Zrank(key, member) => zset::rank::command(store, &key, &member).map(Reply::from),
+        Command::Zscore(key, member) => zset::score::command(store, &key, &member).map(Reply::from),
+        Command::Zrem(key, members) => zset::rem::command(store, &key, &members).map(Reply::from),
+        Command::ZunionStore(destination, keys, weights, aggregate) => {
+            let stored =
+                zset::union::store_command(store, &destination, &keys, &weights, aggregate)?;
+            db.persist(&destination);
+            Ok(stored.into())
+        }
+        Command::ZinterStore(destination, keys, weights, aggregate) => {
+            let stored =
+                zset::inter::store_command(store, &destination, &keys, &weights, aggregate)?;
+            db.persist(&destination);
+            Ok(stored.into())
+        }
+        Command::ZdiffStore(destination, keys) => {
+            let stored = zset::diff::store_command(store, &destination, &keys)?;
+            db.persist(&destination);
+            Ok(stored.into())
+        }
+        Command::Geoadd(key, options, points) => {
+            geo::add::command(store, &key, options, points).map(Reply::from)
+        }
+        Command::Geodist(key, a, b, unit) => {
+            let distance = geo::dist::command(store, &key, &a, &b, unit)?;
+            Ok(distance.map_or(Reply::Nil, distance_reply))
+        }
+        Command::Geopos(key, members) => {
+            let points = geo::pos::command(store, &key, &members)?;
+            Ok(Reply::Array(
+                points
+                    .into_iter()
+                    .map(|point| point.map_or(Reply::Nil, point_reply))
+                    .collect(),
+            ))
+        }
+        Command::Geohash(key, members) => {
+            let hashes = geo::hash::command(store, &key, &members)?;
+            Ok(Reply::Array(
+                hashes
+                    .into_iter()
+                    .map(|hash| hash.map_or(Reply::Nil, |hash| Reply::Bulk(hash.into_bytes())))
+                    .collect(),
+            ))
+        }
+        Command::Geosearch(key, query, with) => {
+            let found = geo::search::command(store, &key, &query)?;
+            Ok(Reply::Array(
+                found
+                    .into_iter()
+                    .map(|found| found_reply(found, with))
+                    .collect(),
+            ))
+        }
+        Command::GeosearchStore(destination, key, query, store_dist) => {
+            let stored = geo::search::store_command(store, &destination, &key, &query, store_dist)?;
+            db.persist(&destination);
+            Ok(stored.into())
+        }
+        Command::Xadd(key, options, id, fields) => {
+            let id = stream::add::command(store, &key, options, id, fields, now)?;
+            Ok(id.map_or(Reply::Nil, id_reply))
+        }
+        Command::Xrange(key, start, end, count, reverse) => {
+            let entries = stream::range::command(store, &key, start, end, count, reverse)?;
+            Ok(entries_reply(entries))
+        }
+        Command::Xread(keys, afters, count) => {
+            let read = stream::read::multi_command(store, &keys, &afters, count)?;
+            Ok(read_reply(read))
+        }
+        Command::Xlen(key) => stream::len::command(store, &key).map(Reply::from),
+        Command::Xtrim(key, trim) => stream::trim::command(store, &key, trim).map(Reply::from),
+        Command::XgroupCreate(key, group, start, mkstream) => {
+            stream::group::create_command(store, &key, &group, start, mkstream)?;
+            Ok(Reply::Simple("OK".into()))
+        }
+        Command::XgroupSetId(key, group, start) => {
+            stream::group::set_id_command(store, &key, &group, start)?;
+            Ok(Reply::Simple("OK".into()))
+        }
+        Command::XgroupDestroy(key, group) => {
+            stream::group::destroy_command(store, &key, &group).map(Reply::from)
+        }
+        Command::XgroupCreateConsumer(key, group, consumer) => {
+            stream::group::create_consumer_command(store, &key, &group, &consumer).map(Reply::from)
+        }
+        Command::XgroupDelConsumer(key, group, consumer) => {
+            stream::group::delete_consumer_command(store, &key, &group, &consumer).map(Reply::from)
+        }
+        Command::XreadGroup(options, keys, starts) => {
+            let read = stream::readgroup::multi_command(store, &keys, &starts, &options, now)?;
+            Ok(read_group_reply(read))
+        }
+        Command::Xack(key, group, ids) => {
+            stream::ack::command(store, &key, &group, &ids).map(Reply::from)
+        }
+        Command::Xpending(key, group, None) => {
+            stream::pending::summary_command(store, &key, &group).map(pending_summary_reply)
+        }
+        Command::Xpending(key, group, Some(filter)) => {
+            let pending = stream::pending::command(store, &key, &group, &filter, now)?;
+            Ok(Reply::Array(
+                pending
+                    .into_iter()
+                    .map(|(id, pending)| {
+                        Reply::Array(vec![
+                            id_reply(id),
+                            Reply::Bulk(pending.consumer.clone()),
+                            Reply::Integer(pending.idle(now) as i64),
+                            Reply::Integer(pending.deliveries as i64),
+                        ])
+                    })
+                    .collect(),
+            ))
+        }
+        Command::Xclaim(key, group, consumer, ids, options) => {
+            let claimed =
+                stream::claim::command(store, &key, &group, &consumer, &ids, &options, now)?;
+            Ok(if options.just_id {
+                ids_reply(claimed.into_iter().map(|(id, _)| id))
+            } else {
+                entries_reply(claimed)
+            })
+        }
+        Command::Xautoclaim(key, group, consumer, options) => {
+            let autoclaimed =
+                stream::autoclaim::command(store, &key, &group, &consumer, &options, now)?;
+            let claimed = if options.just_id {
+                ids_reply(autoclaimed.claimed.into_iter().map(|(id, _)| id))
+            } else {
+                entries_reply(autoclaimed.claimed)
+            };
+            Ok(Reply::Array(vec![
+                id_reply(autoclaimed.next),
+                claimed,
+                ids_reply(autoclaimed.deleted.into_iter()),
+            ]))
+        }
+        command @ Command::Blpop(..)
+        | command @ Command::Brpop(..)
+        | command @ Command::Blmove(..)
+        | command @ Command::XreadBlock(..)
+        | command @ Command::XreadGroupBlock(..) => match arrive(db, command, now)? {
+            Arrival::Served(reply) => Ok(reply),
+            Arrival::Waits(command) => serve_now(db, &command, now),
+        },
+    }
+}
+
+/// What becomes of a blocking command when it arrives.
+pub enum Arrival {
+    /// It could be served without waiting.
+    Served(Reply),
+    /// It has to wait for its keys, as this command.
+    Waits(Command),
+}
+
+/// Stream readers are served at once if there is anything to read, like
+/// their non-blocking versions, and otherwise wait for entries after the
+/// ones there are now. A consumer reading its history never waits.
+/// Everything else waits its turn.
+pub fn arrive(db: &mut Db, command: Command, now: u64) -> Result<Arrival, ApplicationError> {
+    expire_keys(db, &command, now);
+    let store = db.store_mut();
+    match command {
+        Command::XreadBlock(keys, afters, count, timeout) => {
+            let read = stream::read::multi_command(store, &keys, &afters, count)?;
+            if !read.is_empty() {
+                return Ok(Arrival::Served(read_reply(read)));
+            }
+            let afters = stream::read::resolve(store, &keys, &afters)?;
+            Ok(Arrival::Waits(Command::XreadBlock(
+                keys, afters, count, timeout,
+            )))
+        }
+        Command::XreadGroupBlock(options, keys, starts, timeout) => {
+            let read = stream::readgroup::multi_command(store, &keys, &starts, &options, now)?;
+            if !read.is_empty() || starts.iter().any(|start| *start != Start::New) {
+                return Ok(Arrival::Served(read_group_reply(read)));
+            }
+            Ok(Arrival::Waits(Command::XreadGroupBlock(
+                options, keys, starts, timeout,
+            )))
+        }
+        command => Ok(Arrival::Waits(command)),
+    }
+}
+
+/// Serves a blocking command from `key` alone, or returns `None` when it
+/// would have to wait for the key to change.
+pub fn serve_blocked(
+    db: &mut Db,
+    command: &Command,
+    key: &[u8],
+    now: u64,
+) -> Result<Option<Reply>, ApplicationError> {
+    expire_keys(db, command, now);
+    let store = db.store_mut();
+    let end = match command {
+        Command::Blpop(..) => End::Left,
+        Command::Brpop(..) => End::Right,
+        Command::Blmove(_, destination, from, to, _) => {
+            let moved = list::lmove::command(store, key, destination, *from, *to)?;
+            return Ok(moved.map(Reply::from));
+        }
+        Command::XreadBlock(keys, afters, count, _) => {
+            let after = keys
+                .iter()
+                .zip(afters)
+                .find(|(waiting_on, _)| *waiting_on == key)
+                .map(|(_, after)| *after);
+            let entries = match after {
+                Some(after) => stream::read::command(store, key, after, *count)?,
+                None => Vec::new(),
+            };
+            return Ok(if entries.is_empty() {
+                None
+            } else {
+                Some(read_reply(vec![(key.to_vec(), entries)]))
+            });
+        }
+        Command::XreadGroupBlock(options, keys, starts, _) => {
+            let start = keys
+                .iter()
+                .zip(starts)
+                .find(|(waiting_on, _)| *waiting_on == key)
+                .map(|(_, start)| *start);
+            let delivered = match start {
+                Some(start) => stream::readgroup::command(store, key, options, start, now)?,
+                None => Vec::new(),
+            };
+            return Ok(if delivered.is_empty() {
+                None
+            } else {
+                Some(read_group_reply(vec![(key.to_vec(), delivered)]))
+            });
+        }
+        _ => return Ok(None),
+    };
+    let popped = list::pop::command(store, key, 1, end)?;
+    Ok(popped
+        .and_then(|values| values.into_iter().next())
+        .map(|value| Reply::Array(vec![Reply::Bulk(key.to_vec()), value.into()])))
+}
+
+/// Deletes the command's keys that are due to expire.
+fn expire_keys(db: &mut Db, command: &Command, now: u64) {
+    command.keys().into_iter().for_each(|key| {
+        db.expire_if_due(key, now);
+    });
+}
+
+/// Without other clients there is nobody to wait for, so a blocking command
+/// serves itself from the first key it can or replies with a null array at
+/// once, as it would on timing out.
+fn serve_now(db: &mut Db, command: &Command, now: u64) -> Result<Reply, ApplicationError> {
+    let keys = command.blocks_on().map_or(&[][..], |(keys, _)| keys);
+    for key in keys {
+        if let Some(reply) = serve_blocked(db, command, key, now)? {
+            return Ok(reply);
+        }
+    }
+    Ok(Reply::NullArray)
+}
+
+/// LPOP and RPOP reply with a bare value unless they were given a count.
+fn pop(
+    store: &mut HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    count: Option<usize>,
+    end: End,
+) -> Result<Reply, ApplicationError> {
+    let popped = list::pop::command(store, key, count.unwrap_or(1), end)?;
+    Ok(match count {
+        Some(_) => popped.map_or(Reply::Nil, Reply::from),
+        None => popped
+            .and_then(|values| values.into_iter().next())
+            .map_or(Reply::Nil, Primitive::into),
+    })
+}
+
+/// LCS replies with the subsequence, its length, or with IDX a map of the
+/// runs it is made of and its length.
+fn lcs_reply(lcs: string::lcs::Lcs, options: &string::lcs::Options) -> Reply {
+    if options.idx {
+        let range = |(start, end): (usize, usize)| Reply::Array(vec![start.into(), end.into()]);
+        let matches = lcs
+            .matches
+            .into_iter()
+            .filter(|run| run.length() >= options.min_match_len)
+            .map(|run| {
+                let len = Some(run.length().into()).filter(|_| options.with_match_len);
+                Reply::Array(
+                    vec![range(run.a), range(run.b)]
+                        .into_iter()
+                        .chain(len)
+                        .collect(),
+                )
+            })
+            .collect();
+        Reply::Map(vec![
+            (Reply::Bulk(b"matches".to_vec()), Reply::Array(matches)),
+            (Reply::Bulk(b"len".to_vec()), lcs.subsequence.len().into()),
+        ])
+    } else if options.len {
+        lcs.subsequence.len().into()
+    } else {
+        Reply::Bulk(lcs.subsequence)
+    }
+}
+
+/// A stream entry as Redis replies with it: its ID followed by its fields
+/// and values, where an entry deleted while it was pending has none.
+fn entry_reply(id: StreamId, fields: Option<Fields>) -> Reply {
+    let fields = fields.map_or(Reply::Nil, |fields| {
+        Reply::Array(
+            fields
+                .into_iter()
+                .flat_map(|(field, value)| vec![field.into(), value.into()])
+                .collect(),
+        )
+    });
+    Reply::Array(vec![id_reply(id), fields])
+}
+
+/// Distances come with four decimals, as Redis gives them.
+fn distance_reply(distance: f64) -> Reply {
+    Reply::Bulk(format!("{:.4}", distance).into_bytes())
+}
+
+/// Coordinates come with 17 decimals, less any trailing zeros, as Redis
+/// gives them.
+fn point_reply((longitude, latitude): (f64, f64)) -> Reply {
+    let coordinate = |degrees: f64| {
+        let digits = format!("{:.17}", degrees);
+        let digits = digits.trim_end_matches('0').trim_end_matches('.');
+        Reply::Bulk(digits.as_bytes().to_vec())
+    };
+    Reply::Array(vec![coordinate(longitude), coordinate(latitude)])
+}
+
+/// A member GEOSEARCH found, on its own or with what `with` asks for.
+fn found_reply(found: geo::search::Found, with: geo::search::With) -> Reply {
+    if with == geo::search::With::default() {
+        return Reply::Bulk(found.member);
+    }
+    let mut reply = vec![Reply::Bulk(found.member)];
+    if with.dist {
+        reply.push(distance_reply(found.distance));
+    }
+    if with.hash {
+        reply.push(Reply::Integer(found.score as i64));
+    }
+    if with.coord {
+        reply.push(point_reply((found.longitude, found.latitude)));
+    }
+    Reply::Array(reply)
+}
+
+fn entries_reply(entries: Vec<Entry>) -> Reply {
+    Reply::Array(
+        entries
+            .into_iter()
+            .map(|(id, fields)| entry_reply(id, Some(fields)))
+            .collect(),
+    )
+}
+
+fn delivered_reply(delivered: Vec<Delivered>) -> Reply {
+    Reply::Array(
+        delivered
+            .into_iter()
+            .map(|(id, fields)| entry_reply(id, fields))
+            .collect(),
+    )
+}
+
+fn id_reply(id: StreamId) -> Reply {
+    Reply::Bulk(id.to_string().into_bytes())
+}
+
+fn ids_reply<I: Iterator<Item = StreamId>>(ids: I) -> Reply {
+    Reply::Array(ids.map(id_reply).collect())
+}
+
+/// What XREAD or XREADGROUP read, as pairs of a stream's key and what was
+/// read from it, or a null array if there was nothing to read.
+fn streams_reply(read: Vec<(Vec<u8>, Reply)>) -> Reply {
+    if read.is_empty() {
+        return Reply::NullArray;
+    }
+    Reply::Array(
+        read.into_iter()
+            .map(|(key, entries)| Reply::Array(vec![Reply::Bulk(key), entries]))
+            .collect(),
+    )
+}
+
+fn read_reply(read: Vec<stream::read::Read>) -> Reply {
+    streams_reply(
+        read.into_iter()
+            .map(|(key, entries)| (key, entries_reply(entries)))
+            .collect(),
+    )
+}
+
+fn read_group_reply(read: Vec<stream::readgroup::Read>) -> Reply {
+    streams_reply(
+        read.into_iter()
+            .map(|(key, delivered)| (key, delivered_reply(delivered)))
+            .collect(),
+    )
+}
+
+/// XPENDING without a range sums up the pending messages, with nils in
+/// place of IDs and consumers when there are none.
+fn pending_summary_reply(summary: stream::pending::Summary) -> Reply {
+    let consumers = if summary.consumers.is_empty() {
+        Reply::Nil
+    } else {
+        Reply::Array(
+            summary
+                .consumers
+                .into_iter()
+                .map(|(consumer, count)| {
+                    let count = count.to_string().into_bytes();
+                    Reply::Array(vec![Reply::Bulk(consumer), Reply::Bulk(count)])
+                })
+                .collect(),
+        )
+    };
+    Reply::Array(vec![
+        summary.count.into(),
+        summary.first.map_or(Reply::Nil, id_reply),
+        summary.last.map_or(Reply::Nil, id_reply),
+        consumers,
+    ])
+}
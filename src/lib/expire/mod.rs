@@ -0,0 +1,67 @@
+use super::db::Db;
+use super::errors::{ApplicationError, Fallible};
+
+pub mod persist;
+pub mod set;
+pub mod ttl;
+
+/// A deadline as the EXPIRE family and SET's options give it: some
+/// milliseconds from now, or a Unix time in milliseconds.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum When {
+    In(i64),
+    At(i64),
+}
+
+impl When {
+    /// The deadline in milliseconds since the Unix epoch, where one before
+    /// the epoch is as good as the epoch itself.
+    pub fn resolve(self, now: u64) -> Result<u64, ApplicationError> {
+        let at = match self {
+            When::In(ms) => (now as i64)
+                .checked_add(ms)
+                .fail_to("Invalid expire time")?,
+            When::At(ms) => ms,
+        };
+        Ok(at.max(0) as u64)
+    }
+}
+
+/// What SET and GETEX do with the deadline of the key they touch.
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub enum Lifetime {
+    #[default]
+    Persist,
+    Keep,
+    Expire(When),
+}
+
+impl Lifetime {
+    /// The lifetime with any deadline fixed as of `now`, which is where a
+    /// bad deadline is caught before anything changes.
+    pub fn resolve(self, now: u64) -> Result<Lifetime, ApplicationError> {
+        match self {
+            Lifetime::Expire(when) => Ok(Lifetime::Expire(When::At(when.resolve(now)? as i64))),
+            lifetime => Ok(lifetime),
+        }
+    }
+
+    /// Gives a key that is there this lifetime. A deadline that has already
+    /// passed deletes it at once.
+    pub fn apply(self, db: &mut Db, key: &[u8], now: u64) -> Result<(), ApplicationError> {
+        match self {
+            Lifetime::Persist => {
+                db.persist(key);
+            }
+            Lifetime::Keep => {}
+            Lifetime::Expire(when) => match when.resolve(now)? {
+                at if at <= now => {
+                    db.store_mut().remove(key);
+                    db.persist(key);
+                }
+                at => db.set_deadline(key, at),
+            },
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,6 @@
+use super::super::db::Db;
+
+/// PERSIST, which says whether the key had a deadline to take away.
+pub fn command(db: &mut Db, key: &[u8]) -> bool {
+    db.persist(key)
+}
@@ -0,0 +1,98 @@
+use super::super::db::Db;
+use super::super::errors::ApplicationError;
+use super::{Lifetime, When};
+
+/// When EXPIRE may go ahead: NX only if the key has no deadline, XX only if
+/// it has one, GT only if it pushes the deadline back and LT only if it
+/// brings it forward. A key without a deadline counts as living forever.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Options {
+    pub nx: bool,
+    pub xx: bool,
+    pub gt: bool,
+    pub lt: bool,
+}
+
+impl Options {
+    fn allow(&self, current: Option<u64>, at: u64) -> bool {
+        !(self.nx && current.is_some()
+            || self.xx && current.is_none()
+            || self.gt && current.is_none_or(|current| at <= current)
+            || self.lt && current.is_some_and(|current| at >= current))
+    }
+}
+
+/// EXPIRE, PEXPIRE, EXPIREAT and PEXPIREAT, which say whether they set the
+/// deadline. A deadline that has already passed deletes the key at once.
+pub fn command(
+    db: &mut Db,
+    key: &[u8],
+    when: When,
+    options: Options,
+    now: u64,
+) -> Result<bool, ApplicationError> {
+    let at = when.resolve(now)?;
+    if !db.store().contains_key(key) || !options.allow(db.deadline(key), at) {
+        return Ok(false);
+    }
+    Lifetime::Expire(When::At(at as i64)).apply(db, key, now)?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::domain::Data;
+    use super::{command, Db, Options, When};
+    use proptest::option;
+    use proptest::prelude::*;
+    use std::cmp::Ordering;
+
+    fn options() -> impl Strategy<Value = Options> {
+        (any::<bool>(), any::<bool>(), any::<bool>(), any::<bool>())
+            .prop_map(|(nx, xx, gt, lt)| Options { nx, xx, gt, lt })
+    }
+
+    proptest! {
+        #[test]
+        fn deadlines_move_only_as_allowed(
+            current in option::of(1u64..100),
+            ttl in -10i64..100,
+            options in options(),
+        ) {
+            let (key, now) = (b"k".as_slice(), 1000);
+            let mut db = Db::new();
+            assert!(!command(&mut db, key, When::In(ttl), options, now)?);
+            db.store_mut().insert(key.to_vec(), Data::from(1));
+            if let Some(current) = current {
+                db.set_deadline(key, now + current);
+            }
+            let at = (now as i64 + ttl) as u64;
+            let allowed = match current.map(|current| now + current) {
+                None => !options.xx && !options.gt,
+                Some(current) => {
+                    !options.nx
+                        && match at.cmp(&current) {
+                            Ordering::Greater => !options.lt,
+                            Ordering::Less => !options.gt,
+                            Ordering::Equal => !(options.gt || options.lt),
+                        }
+                }
+            };
+            assert_eq!(command(&mut db, key, When::At(at as i64), options, now)?, allowed);
+            let expected = match (allowed, current) {
+                (true, _) if at <= now => None,
+                (true, _) => Some(Some(at)),
+                (false, current) => Some(current.map(|current| now + current)),
+            };
+            assert_eq!(db.store().get(key).map(|_| db.deadline(key)), expected)
+        }
+    }
+
+    #[test]
+    fn overflowing_deadlines_are_refused() {
+        let mut db = Db::new();
+        db.store_mut().insert(b"k".to_vec(), Data::from(1));
+        command(&mut db, b"k", When::In(i64::MAX), Options::default(), 1).unwrap_err();
+        assert_eq!(db.deadline(b"k"), None);
+    }
+}
@@ -0,0 +1,40 @@
+use super::super::db::Db;
+
+/// PTTL: the milliseconds a key has left, or -1 if it has no deadline and
+/// -2 if it isn't there.
+pub fn command(db: &Db, key: &[u8], now: u64) -> i64 {
+    if !db.store().contains_key(key) {
+        return -2;
+    }
+    db.deadline(key)
+        .map_or(-1, |at| at.saturating_sub(now) as i64)
+}
+
+/// TTL, which rounds what is left to the nearest second.
+pub fn seconds_command(db: &Db, key: &[u8], now: u64) -> i64 {
+    match command(db, key, now) {
+        ms if ms < 0 => ms,
+        ms => (ms + 500) / 1000,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::domain::Data;
+    use super::{command, seconds_command, Db};
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn ttls_count_down(ttl in 1u64..100_000, elapsed in 0u64..100_000) {
+            let mut db = Db::new();
+            assert_eq!(command(&db, b"k", 0), -2);
+            db.store_mut().insert(b"k".to_vec(), Data::from(1));
+            assert_eq!(seconds_command(&db, b"k", 0), -1);
+            db.set_deadline(b"k", ttl);
+            prop_assume!(elapsed < ttl);
+            assert_eq!(command(&db, b"k", elapsed), (ttl - elapsed) as i64);
+            assert_eq!(seconds_command(&db, b"k", elapsed), ((ttl - elapsed + 500) / 1000) as i64)
+        }
+    }
+}
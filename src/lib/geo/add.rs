@@ -0,0 +1,61 @@
+use super::super::domain::Data;
+use super::super::errors::ApplicationError;
+use super::super::zset::add::{self, Options};
+use super::{check_point, geohash};
+use std::collections::HashMap;
+
+/// GEOADD, which stores each point as its geohash score. Takes the NX, XX
+/// and CH flags of ZADD.
+pub fn command(
+    store: &mut HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    options: Options,
+    points: Vec<(f64, f64, Vec<u8>)>,
+) -> Result<usize, ApplicationError> {
+    let pairs = points
+        .into_iter()
+        .map(|(longitude, latitude, member)| {
+            check_point(longitude, latitude)?;
+            Ok((geohash::score(longitude, latitude), member))
+        })
+        .collect::<Result<Vec<(f64, Vec<u8>)>, ApplicationError>>()?;
+    add::command(store, key, options, pairs)
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::pos;
+    use super::{command, Data, Options};
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+    use std::collections::HashMap;
+
+    proptest! {
+        #[test]
+        fn points_are_stored_near_where_they_were(
+            points in vec((-180.0..180.0f64, -85.0..85.0f64, vec(any::<u8>(), 1..4)), 1..20),
+        ) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            command(&mut store, b"geo", Options::default(), points.clone())?;
+            let mut latest = HashMap::new();
+            for (longitude, latitude, member) in points {
+                latest.insert(member, (longitude, latitude));
+            }
+            let members: Vec<Vec<u8>> = latest.keys().cloned().collect();
+            for (member, found) in members.iter().zip(pos::command(&store, b"geo", &members)?) {
+                let (longitude, latitude) = latest[member];
+                let (found_longitude, found_latitude) = found.unwrap();
+                assert!((found_longitude - longitude).abs() < 1e-5);
+                assert!((found_latitude - latitude).abs() < 1e-5);
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_points_off_the_map() {
+        let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+        let points = vec![(0.0, 86.0, b"pole".to_vec())];
+        assert!(command(&mut store, b"geo", Options::default(), points).is_err());
+        assert!(store.is_empty());
+    }
+}
@@ -0,0 +1,53 @@
+use super::super::domain::Data;
+use super::super::errors::ApplicationError;
+use super::super::zset::utilities::get_sorted_set;
+use super::{geohash, Unit};
+use std::collections::HashMap;
+
+/// GEODIST: how far apart two members are, unless one is missing.
+pub fn command(
+    store: &HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    a: &[u8],
+    b: &[u8],
+    unit: Unit,
+) -> Result<Option<f64>, ApplicationError> {
+    let set = match get_sorted_set(store, key)? {
+        Some(set) => set,
+        None => return Ok(None),
+    };
+    Ok(match (set.score(a), set.score(b)) {
+        (Some(a), Some(b)) => {
+            let ((long1, lat1), (long2, lat2)) = (geohash::point(a), geohash::point(b));
+            Some(geohash::distance(long1, lat1, long2, lat2) / unit.meters())
+        }
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::zset::add::Options;
+    use super::super::add;
+    use super::{command, Data, Unit};
+    use proptest::prelude::*;
+    use std::collections::HashMap;
+
+    proptest! {
+        #[test]
+        fn distances_are_symmetric_and_scale_with_units(
+            a in (-180.0..180.0f64, -85.0..85.0f64),
+            b in (-180.0..180.0f64, -85.0..85.0f64),
+        ) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            let points = vec![(a.0, a.1, b"a".to_vec()), (b.0, b.1, b"b".to_vec())];
+            add::command(&mut store, b"geo", Options::default(), points)?;
+            let meters = command(&store, b"geo", b"a", b"b", Unit::Meters)?.unwrap();
+            prop_assert_eq!(command(&store, b"geo", b"b", b"a", Unit::Meters)?, Some(meters));
+            let kilometers = command(&store, b"geo", b"a", b"b", Unit::Kilometers)?.unwrap();
+            prop_assert!((kilometers * 1000.0 - meters).abs() < 1e-6);
+            prop_assert!(meters <= 20_037_600.0);
+            prop_assert_eq!(command(&store, b"geo", b"a", b"missing", Unit::Meters)?, None);
+        }
+    }
+}
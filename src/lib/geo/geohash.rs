@@ -0,0 +1,315 @@
+//! Redis's geohashes: a longitude and latitude cut down to 26 bits each and
+//! interleaved, latitude in the even bits and longitude in the odd ones,
+//! into the 52 bits a sorted set score holds exactly. Latitudes only go as
+//! far as Web Mercator does.
+
+use std::f64::consts::PI;
+
+pub const STEP: u8 = 26;
+const LONG_MAX: f64 = 180.0;
+const LAT_MAX: f64 = 85.051_128_78;
+/// The latitude standard geohashes go up to, which GEOHASH uses.
+const STANDARD_LAT_MAX: f64 = 90.0;
+const EARTH_RADIUS: f64 = 6_372_797.560_856;
+const MERCATOR_MAX: f64 = 20_037_726.37;
+const D_R: f64 = PI / 180.0;
+const ALPHABET: &[u8; 32] = b"0123456789bcdefghjkmnpqrstuvwxyz";
+
+/// A cell of the grid that splits the world into 4 to the power of `step`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct Hash {
+    bits: u64,
+    step: u8,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Area {
+    longitude: (f64, f64),
+    latitude: (f64, f64),
+}
+
+fn deg_rad(degrees: f64) -> f64 {
+    degrees * D_R
+}
+
+fn rad_deg(radians: f64) -> f64 {
+    radians / D_R
+}
+
+pub fn is_valid(longitude: f64, latitude: f64) -> bool {
+    (-LONG_MAX..=LONG_MAX).contains(&longitude) && (-LAT_MAX..=LAT_MAX).contains(&latitude)
+}
+
+fn spread(half: u32) -> u64 {
+    let mut bits = u64::from(half);
+    bits = (bits | bits << 16) & 0x0000_ffff_0000_ffff;
+    bits = (bits | bits << 8) & 0x00ff_00ff_00ff_00ff;
+    bits = (bits | bits << 4) & 0x0f0f_0f0f_0f0f_0f0f;
+    bits = (bits | bits << 2) & 0x3333_3333_3333_3333;
+    (bits | bits << 1) & 0x5555_5555_5555_5555
+}
+
+fn squash(bits: u64) -> u32 {
+    let mut bits = bits & 0x5555_5555_5555_5555;
+    bits = (bits | bits >> 1) & 0x3333_3333_3333_3333;
+    bits = (bits | bits >> 2) & 0x0f0f_0f0f_0f0f_0f0f;
+    bits = (bits | bits >> 4) & 0x00ff_00ff_00ff_00ff;
+    bits = (bits | bits >> 8) & 0x0000_ffff_0000_ffff;
+    ((bits | bits >> 16) & 0x0000_0000_ffff_ffff) as u32
+}
+
+fn encode(longitude: f64, latitude: f64, lat_max: f64, step: u8) -> Hash {
+    let cells = (1u64 << step) as f64;
+    let lat = (latitude + lat_max) / (2.0 * lat_max) * cells;
+    let long = (longitude + LONG_MAX) / (2.0 * LONG_MAX) * cells;
+    Hash {
+        bits: spread(lat as u32) | spread(long as u32) << 1,
+        step,
+    }
+}
+
+fn decode(hash: Hash, lat_max: f64) -> Area {
+    let cells = (1u64 << hash.step) as f64;
+    let (lat, long) = (squash(hash.bits), squash(hash.bits >> 1));
+    let edge = |cell: u32, max: f64| -max + f64::from(cell) / cells * (2.0 * max);
+    Area {
+        longitude: (edge(long, LONG_MAX), edge(long + 1, LONG_MAX)),
+        latitude: (edge(lat, lat_max), edge(lat + 1, lat_max)),
+    }
+}
+
+/// The score a point is stored under.
+pub fn score(longitude: f64, latitude: f64) -> f64 {
+    encode(longitude, latitude, LAT_MAX, STEP).bits as f64
+}
+
+/// The point a score stands for: the middle of its cell.
+pub fn point(score: f64) -> (f64, f64) {
+    let area = decode(
+        Hash {
+            bits: score as u64,
+            step: STEP,
+        },
+        LAT_MAX,
+    );
+    let longitude = (area.longitude.0 + area.longitude.1) / 2.0;
+    let latitude = (area.latitude.0 + area.latitude.1) / 2.0;
+    (
+        longitude.clamp(-LONG_MAX, LONG_MAX),
+        latitude.clamp(-LAT_MAX, LAT_MAX),
+    )
+}
+
+/// The standard 11 character geohash of a point, as geohash.org has it.
+pub fn standard(longitude: f64, latitude: f64) -> String {
+    let bits = encode(longitude, latitude, STANDARD_LAT_MAX, STEP).bits;
+    (0..11)
+        .map(|at| match at {
+            // 52 bits only make ten and a bit characters.
+            10 => ALPHABET[0] as char,
+            _ => ALPHABET[((bits >> (52 - (at + 1) * 5)) & 0x1f) as usize] as char,
+        })
+        .collect()
+}
+
+fn lat_distance(lat1: f64, lat2: f64) -> f64 {
+    EARTH_RADIUS * (deg_rad(lat2) - deg_rad(lat1)).abs()
+}
+
+/// The great circle distance between two points in meters, by the
+/// haversine formula.
+pub fn distance(long1: f64, lat1: f64, long2: f64, lat2: f64) -> f64 {
+    let v = ((deg_rad(long2) - deg_rad(long1)) / 2.0).sin();
+    if v == 0.0 {
+        return lat_distance(lat1, lat2);
+    }
+    let (lat1, lat2) = (deg_rad(lat1), deg_rad(lat2));
+    let u = ((lat2 - lat1) / 2.0).sin();
+    let a = u * u + lat1.cos() * lat2.cos() * v * v;
+    2.0 * EARTH_RADIUS * a.sqrt().asin()
+}
+
+/// The distance from the middle of a box `width` by `height` meters to a
+/// point, if the point is in the box.
+pub fn distance_in_box(
+    width: f64,
+    height: f64,
+    (long1, lat1): (f64, f64),
+    (long2, lat2): (f64, f64),
+) -> Option<f64> {
+    if lat_distance(lat2, lat1) > height / 2.0 || distance(long2, lat2, long1, lat2) > width / 2.0 {
+        return None;
+    }
+    Some(distance(long1, lat1, long2, lat2))
+}
+
+/// How coarse a grid to search a radius with: cells a little bigger than
+/// it, and bigger still towards the poles.
+fn steps_for(radius: f64, latitude: f64) -> u8 {
+    if radius == 0.0 {
+        return STEP;
+    }
+    let (mut radius, mut step) = (radius, 1);
+    while radius < MERCATOR_MAX {
+        radius *= 2.0;
+        step += 1;
+    }
+    step -= 2;
+    if !(-66.0..=66.0).contains(&latitude) {
+        step -= 1;
+        if !(-80.0..=80.0).contains(&latitude) {
+            step -= 1;
+        }
+    }
+    step.clamp(1, i32::from(STEP)) as u8
+}
+
+/// Moves a cell `by` one cell east or west, wrapping around.
+fn move_x(hash: Hash, east: bool) -> Hash {
+    let x = hash.bits & 0xaaaa_aaaa_aaaa_aaaa;
+    let y = hash.bits & 0x5555_5555_5555_5555;
+    let shift = 64 - u32::from(hash.step) * 2;
+    let zz = 0x5555_5555_5555_5555u64 >> shift;
+    let x = if east {
+        x.wrapping_add(zz + 1)
+    } else {
+        (x | zz).wrapping_sub(zz + 1)
+    };
+    Hash {
+        bits: (x & (0xaaaa_aaaa_aaaa_aaaa >> shift)) | y,
+        step: hash.step,
+    }
+}
+
+/// Moves a cell one cell north or south, wrapping around.
+fn move_y(hash: Hash, north: bool) -> Hash {
+    let x = hash.bits & 0xaaaa_aaaa_aaaa_aaaa;
+    let y = hash.bits & 0x5555_5555_5555_5555;
+    let shift = 64 - u32::from(hash.step) * 2;
+    let zz = 0xaaaa_aaaa_aaaa_aaaau64 >> shift;
+    let y = if north {
+        y.wrapping_add(zz + 1)
+    } else {
+        (y | zz).wrapping_sub(zz + 1)
+    };
+    Hash {
+        bits: x | (y & (0x5555_5555_5555_5555 >> shift)),
+        step: hash.step,
+    }
+}
+
+/// The score ranges, each including its start and excluding its end, that
+/// hold every point within `half_width` and `half_height` meters of a
+/// point, and within `radius` meters of it for a circle. They are the
+/// cell the point is in and those around it, on a grid coarse enough that
+/// nothing further can be in range, in the order Redis searches them.
+pub fn ranges(
+    (longitude, latitude): (f64, f64),
+    half_width: f64,
+    half_height: f64,
+    radius: f64,
+) -> Vec<(u64, u64)> {
+    let lat_delta = rad_deg(half_height / EARTH_RADIUS);
+    let long_delta = |lat: f64| rad_deg(half_width / EARTH_RADIUS / deg_rad(lat).cos());
+    // The box is widest on the side nearest the equator.
+    let long_delta = if latitude < 0.0 {
+        long_delta(latitude - lat_delta)
+    } else {
+        long_delta(latitude + lat_delta)
+    };
+    let (min_long, max_long) = (longitude - long_delta, longitude + long_delta);
+    let (min_lat, max_lat) = (latitude - lat_delta, latitude + lat_delta);
+
+    let mut step = steps_for(radius, latitude);
+    let neighbours = |step: u8| {
+        let hash = encode(longitude, latitude, LAT_MAX, step);
+        let north = move_y(hash, true);
+        let south = move_y(hash, false);
+        [
+            hash,
+            north,
+            south,
+            move_x(hash, true),
+            move_x(hash, false),
+            move_x(north, true),
+            move_x(north, false),
+            move_x(south, true),
+            move_x(south, false),
+        ]
+    };
+    let mut cells = neighbours(step);
+    let area = |hash: Hash| decode(hash, LAT_MAX);
+    // A cell as big as the radius can still leave part of the box outside
+    // the cells around it when the point is near its edge.
+    let too_coarse = area(cells[1]).latitude.1 < max_lat
+        || area(cells[2]).latitude.0 > min_lat
+        || area(cells[3]).longitude.1 < max_long
+        || area(cells[4]).longitude.0 > min_long;
+    if step > 1 && too_coarse {
+        step -= 1;
+        cells = neighbours(step);
+    }
+    let mut searched = [true; 9];
+    if step >= 2 {
+        let centre = area(cells[0]);
+        let mut skip = |beyond: bool, sides: [usize; 3]| {
+            if beyond {
+                sides.iter().for_each(|side| searched[*side] = false);
+            }
+        };
+        skip(centre.latitude.0 < min_lat, [2, 8, 7]);
+        skip(centre.latitude.1 > max_lat, [1, 5, 6]);
+        skip(centre.longitude.0 < min_long, [4, 8, 6]);
+        skip(centre.longitude.1 > max_long, [3, 7, 5]);
+    }
+    let mut ranges = Vec::with_capacity(9);
+    let mut last: Option<Hash> = None;
+    for (at, cell) in cells.iter().enumerate() {
+        if !searched[at] {
+            continue;
+        }
+        // Far reaching searches can have the same cell on several sides,
+        // though like Redis this only looks out for repeated neighbours.
+        if last == Some(*cell) {
+            continue;
+        }
+        let shift = 52 - u32::from(cell.step) * 2;
+        ranges.push((cell.bits << shift, (cell.bits + 1) << shift));
+        last = if at == 0 { None } else { Some(*cell) };
+    }
+    ranges
+}
+
+#[cfg(test)]
+mod test {
+    use super::{distance, point, score, standard, LAT_MAX};
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn points_survive_their_scores(longitude in -180.0..180.0f64, latitude in -LAT_MAX..LAT_MAX) {
+            let (decoded_longitude, decoded_latitude) = point(score(longitude, latitude));
+            assert!((decoded_longitude - longitude).abs() < 1e-5);
+            assert!((decoded_latitude - latitude).abs() < 1e-5);
+            assert!(distance(longitude, latitude, decoded_longitude, decoded_latitude) < 1.0);
+            assert_eq!(score(decoded_longitude, decoded_latitude), score(longitude, latitude));
+        }
+    }
+
+    #[test]
+    fn matches_redis() {
+        let palermo = point(score(13.361389, 38.115556));
+        let catania = point(score(15.087269, 37.502669));
+        assert_eq!(
+            format!(
+                "{:.4}",
+                distance(palermo.0, palermo.1, catania.0, catania.1)
+            ),
+            "166274.1516"
+        );
+        assert_eq!(standard(palermo.0, palermo.1), "sqc8b49rny0");
+        assert_eq!(standard(catania.0, catania.1), "sqdtr74hyu0");
+        assert_eq!(format!("{:.17}", palermo.0), "13.36138933897018433");
+        assert_eq!(format!("{:.17}", palermo.1), "38.11555639549629859");
+    }
+}
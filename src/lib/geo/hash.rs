@@ -0,0 +1,22 @@
+use super::super::domain::Data;
+use super::super::errors::ApplicationError;
+use super::super::zset::utilities::get_sorted_set;
+use super::geohash;
+use std::collections::HashMap;
+
+/// GEOHASH: the standard geohash of each member. Scores use a narrower
+/// range of latitudes, so each point is worked out and encoded afresh.
+pub fn command(
+    store: &HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    members: &[Vec<u8>],
+) -> Result<Vec<Option<String>>, ApplicationError> {
+    let set = get_sorted_set(store, key)?;
+    Ok(members
+        .iter()
+        .map(|member| {
+            let (longitude, latitude) = geohash::point(set?.score(member)?);
+            Some(geohash::standard(longitude, latitude))
+        })
+        .collect())
+}
@@ -0,0 +1,41 @@
+pub mod add;
+pub mod dist;
+mod geohash;
+pub mod hash;
+pub mod pos;
+pub mod search;
+
+use super::errors::ApplicationError;
+
+/// The units GEO commands take distances in.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Unit {
+    Meters,
+    Kilometers,
+    Feet,
+    Miles,
+}
+
+impl Unit {
+    pub fn meters(self) -> f64 {
+        match self {
+            Unit::Meters => 1.0,
+            Unit::Kilometers => 1000.0,
+            Unit::Feet => 0.3048,
+            Unit::Miles => 1609.34,
+        }
+    }
+}
+
+/// Fails unless a longitude and latitude are within what geohashes cover.
+fn check_point(longitude: f64, latitude: f64) -> Result<(), ApplicationError> {
+    if geohash::is_valid(longitude, latitude) {
+        Ok(())
+    } else {
+        Err(format!(
+            "Invalid longitude,latitude pair {:.6},{:.6}",
+            longitude, latitude
+        )
+        .into())
+    }
+}
@@ -0,0 +1,19 @@
+use super::super::domain::Data;
+use super::super::errors::ApplicationError;
+use super::super::zset::utilities::get_sorted_set;
+use super::geohash;
+use std::collections::HashMap;
+
+/// GEOPOS: the longitude and latitude of each member, as far as its
+/// geohash pins it down.
+pub fn command(
+    store: &HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    members: &[Vec<u8>],
+) -> Result<Vec<Option<(f64, f64)>>, ApplicationError> {
+    let set = get_sorted_set(store, key)?;
+    Ok(members
+        .iter()
+        .map(|member| set.and_then(|set| set.score(member)).map(geohash::point))
+        .collect())
+}
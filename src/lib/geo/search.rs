@@ -0,0 +1,267 @@
+use super::super::domain::Data;
+use super::super::errors::{ApplicationError, Fallible};
+use super::super::zset::utilities::{get_sorted_set, store_result};
+use super::super::zset::{ScoreBound, SortedSet};
+use super::{check_point, geohash, Unit};
+use std::collections::HashMap;
+
+/// Where a search is centred: on a member or on a longitude and latitude.
+#[derive(Debug, PartialEq, Clone)]
+pub enum From {
+    Member(Vec<u8>),
+    Point(f64, f64),
+}
+
+/// What a search covers: a circle of the given radius or a box of the
+/// given width and height.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Shape {
+    Radius(f64),
+    Box(f64, f64),
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Order {
+    Asc,
+    Desc,
+}
+
+/// A GEOSEARCH. Unless `order` is given, results come in no particular
+/// order, except that a `count` takes the nearest ones. With `any`, the
+/// count takes the first ones found instead, which can be much quicker.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Query {
+    pub from: From,
+    pub shape: Shape,
+    pub unit: Unit,
+    pub order: Option<Order>,
+    pub count: Option<usize>,
+    pub any: bool,
+}
+
+/// What GEOSEARCH replies with besides each member: its distance, its
+/// score and its longitude and latitude.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct With {
+    pub dist: bool,
+    pub hash: bool,
+    pub coord: bool,
+}
+
+/// A member found by a search, with its distance from the centre in the
+/// query's unit.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Found {
+    pub member: Vec<u8>,
+    pub distance: f64,
+    pub score: f64,
+    pub longitude: f64,
+    pub latitude: f64,
+}
+
+/// Looks through the scores of the cells that cover the search, checking
+/// the distance to each member in them.
+pub fn command(
+    store: &HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    query: &Query,
+) -> Result<Vec<Found>, ApplicationError> {
+    let set = match get_sorted_set(store, key)? {
+        Some(set) => set,
+        None => return Ok(Vec::new()),
+    };
+    let centre = match &query.from {
+        From::Member(member) => geohash::point(
+            set.score(member)
+                .fail_to("Could not decode the requested member")?,
+        ),
+        From::Point(longitude, latitude) => {
+            check_point(*longitude, *latitude)?;
+            (*longitude, *latitude)
+        }
+    };
+    let meters = query.unit.meters();
+    let (half_width, half_height, radius) = match query.shape {
+        Shape::Radius(radius) => (meters * radius, meters * radius, radius * meters),
+        Shape::Box(width, height) => (
+            meters * (width / 2.0),
+            meters * (height / 2.0),
+            ((width / 2.0) * (width / 2.0) + (height / 2.0) * (height / 2.0)).sqrt() * meters,
+        ),
+    };
+    let limit = match query.count {
+        Some(count) if query.any => count,
+        _ => usize::MAX,
+    };
+    let mut found = Vec::new();
+    for (min, max) in geohash::ranges(centre, half_width, half_height, radius) {
+        let window = set.score_range(
+            ScoreBound::Inclusive(min as f64),
+            ScoreBound::Exclusive(max as f64),
+        );
+        let (first, last) = match window {
+            Some(window) if found.len() < limit => window,
+            _ => continue,
+        };
+        for (member, score) in set.iter_from(first, false).take(last - first + 1) {
+            let point = geohash::point(score);
+            let distance = match query.shape {
+                Shape::Radius(_) => Some(geohash::distance(centre.0, centre.1, point.0, point.1))
+                    .filter(|distance| *distance <= radius),
+                Shape::Box(width, height) => {
+                    geohash::distance_in_box(width * meters, height * meters, centre, point)
+                }
+            };
+            if let Some(distance) = distance {
+                found.push(Found {
+                    member: member.to_vec(),
+                    distance: distance / meters,
+                    score,
+                    longitude: point.0,
+                    latitude: point.1,
+                });
+                if found.len() >= limit {
+                    break;
+                }
+            }
+        }
+    }
+    let order = match (query.order, query.count) {
+        (None, Some(_)) if !query.any => Some(Order::Asc),
+        (order, _) => order,
+    };
+    match order {
+        Some(Order::Asc) => found.sort_by(|a, b| a.distance.total_cmp(&b.distance)),
+        Some(Order::Desc) => found.sort_by(|a, b| b.distance.total_cmp(&a.distance)),
+        None => {}
+    }
+    found.truncate(query.count.unwrap_or(usize::MAX));
+    Ok(found)
+}
+
+/// GEOSEARCHSTORE, which stores the members found with their scores or,
+/// with `store_dist`, their distances, and returns how many there are.
+/// The destination is deleted if there are none.
+pub fn store_command(
+    store: &mut HashMap<Vec<u8>, Data>,
+    destination: &[u8],
+    key: &[u8],
+    query: &Query,
+    store_dist: bool,
+) -> Result<usize, ApplicationError> {
+    let mut result = SortedSet::new();
+    for found in command(store, key, query)? {
+        let score = if store_dist {
+            found.distance
+        } else {
+            found.score
+        };
+        result.insert(found.member, score);
+    }
+    Ok(store_result(store, destination, result))
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::zset::add::Options;
+    use super::super::{add, dist, Unit};
+    use super::{command, store_command, Data, From, Order, Query, Shape};
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+    use std::collections::HashMap;
+
+    fn query(from: From, shape: Shape) -> Query {
+        Query {
+            from,
+            shape,
+            unit: Unit::Kilometers,
+            order: Some(Order::Asc),
+            count: None,
+            any: false,
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn finds_exactly_the_members_in_range(
+            centre in (-170.0..170.0f64, -70.0..70.0f64),
+            offsets in vec((-5.0..5.0f64, -5.0..5.0f64), 1..50),
+            radius in 1.0..1000.0f64,
+        ) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            let points = offsets
+                .iter()
+                .enumerate()
+                .map(|(at, (x, y))| (centre.0 + x, centre.1 + y, at.to_string().into_bytes()))
+                .chain(std::iter::once((centre.0, centre.1, b"centre".to_vec())))
+                .collect();
+            add::command(&mut store, b"geo", Options::default(), points)?;
+            let found = command(
+                &store,
+                b"geo",
+                &query(From::Member(b"centre".to_vec()), Shape::Radius(radius)),
+            )?;
+            let expected = (0..offsets.len())
+                .map(|at| at.to_string().into_bytes())
+                .chain(std::iter::once(b"centre".to_vec()))
+                .filter(|member| {
+                    let distance = dist::command(&store, b"geo", b"centre", member, Unit::Kilometers);
+                    distance.unwrap().unwrap() <= radius
+                })
+                .count();
+            prop_assert_eq!(found.len(), expected);
+            prop_assert!(found.windows(2).all(|pair| pair[0].distance <= pair[1].distance));
+            prop_assert_eq!(&found[0].member, &b"centre".to_vec());
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn boxes_hold_what_circles_inside_them_hold(
+            centre in (-170.0..170.0f64, -70.0..70.0f64),
+            offsets in vec((-5.0..5.0f64, -5.0..5.0f64), 1..50),
+            side in 1.0..1000.0f64,
+        ) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            let points = offsets
+                .iter()
+                .enumerate()
+                .map(|(at, (x, y))| (centre.0 + x, centre.1 + y, at.to_string().into_bytes()))
+                .collect();
+            add::command(&mut store, b"geo", Options::default(), points)?;
+            let from = From::Point(centre.0, centre.1);
+            let in_box = command(&store, b"geo", &query(from.clone(), Shape::Box(side, side)))?;
+            let in_circle = command(&store, b"geo", &query(from.clone(), Shape::Radius(side / 2.0)))?;
+            let around_box = command(&store, b"geo", &query(from, Shape::Radius(side)))?;
+            prop_assert!(in_circle.iter().all(|found| in_box.contains(found)));
+            prop_assert!(in_box.iter().all(|found| around_box.contains(found)));
+        }
+    }
+
+    #[test]
+    fn counts_take_the_nearest_and_stores_replace_the_destination() {
+        let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+        let points = vec![
+            (13.361389, 38.115556, b"Palermo".to_vec()),
+            (15.087269, 37.502669, b"Catania".to_vec()),
+            (12.758489, 38.788135, b"edge1".to_vec()),
+            (17.241510, 38.788135, b"edge2".to_vec()),
+        ];
+        add::command(&mut store, b"Sicily", Options::default(), points).unwrap();
+        let mut nearest = query(From::Point(15.0, 37.0), Shape::Radius(200.0));
+        nearest.order = None;
+        nearest.count = Some(2);
+        let found = command(&store, b"Sicily", &nearest).unwrap();
+        let members: Vec<&[u8]> = found.iter().map(|found| &found.member[..]).collect();
+        assert_eq!(members, vec![&b"Catania"[..], &b"Palermo"[..]]);
+        assert_eq!(format!("{:.4}", found[0].distance), "56.4413");
+        assert_eq!(format!("{:.4}", found[1].distance), "190.4424");
+
+        let stored = store_command(&mut store, b"dest", b"Sicily", &nearest, true).unwrap();
+        assert_eq!(stored, 2);
+        nearest.from = From::Point(0.0, 0.0);
+        let stored = store_command(&mut store, b"dest", b"Sicily", &nearest, true).unwrap();
+        assert_eq!(stored, 0);
+        assert!(!store.contains_key(&b"dest"[..]));
+    }
+}
@@ -0,0 +1,66 @@
+use super::super::domain::{Data, Primitive};
+use super::super::errors::ApplicationError;
+use super::utilities::get_hash_mut;
+use std::collections::HashMap;
+
+/// Deletes the given fields, and the hash itself once it has none left.
+pub fn command(
+    store: &mut HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    fields: &[Primitive],
+) -> Result<usize, ApplicationError> {
+    let hash = match get_hash_mut(store, key)? {
+        Some(hash) => hash,
+        None => return Ok(0),
+    };
+    let removed = fields
+        .iter()
+        .filter(|field| hash.remove(field).is_some())
+        .count();
+    if hash.is_empty() {
+        store.remove(key);
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::domain::Primitive;
+    use super::super::{len, set};
+    use super::{command, Data, HashMap};
+    use proptest::collection::{hash_map, vec};
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn removes_only_present_fields(
+            key in vec(any::<u8>(), 0..32),
+            pairs in hash_map(any::<Primitive>(), any::<Primitive>(), 1..50),
+            fields in vec(any::<Primitive>(), 1..50)
+        ) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            let present = fields.iter().collect::<std::collections::HashSet<_>>()
+                .into_iter()
+                .filter(|f| pairs.contains_key(f))
+                .count();
+            let len = pairs.len();
+            set::command(&mut store, &key, pairs.into_iter().collect())?;
+            assert_eq!(command(&mut store, &key, &fields)?, present);
+            assert_eq!(len::command(&store, &key)?, len - present)
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn deleting_every_field_deletes_the_hash(
+            key in vec(any::<u8>(), 0..32),
+            pairs in hash_map(any::<Primitive>(), any::<Primitive>(), 1..50)
+        ) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            let fields: Vec<Primitive> = pairs.keys().cloned().collect();
+            set::command(&mut store, &key, pairs.into_iter().collect())?;
+            assert_eq!(command(&mut store, &key, &fields)?, fields.len());
+            assert!(store.is_empty())
+        }
+    }
+}
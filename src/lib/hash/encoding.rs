@@ -0,0 +1,191 @@
+use super::super::domain::Primitive;
+use std::collections::HashMap;
+
+/// Hashes with up to this many fields are kept as a flat list of pairs, the
+/// way Redis keeps them in a listpack: at that size a scan is as quick as a
+/// lookup and the pairs take far less memory than a table.
+const MAX_COMPACT_FIELDS: usize = 128;
+/// Nor may a compact hash hold a field or value longer than this.
+const MAX_COMPACT_LEN: usize = 64;
+
+/// A map of fields to values. It starts out compact and turns into a hash
+/// table for good once it outgrows the compact encoding.
+#[derive(Debug, Clone)]
+pub struct Hash(Encoding);
+
+#[derive(Debug, Clone)]
+enum Encoding {
+    Compact(Vec<(Primitive, Primitive)>),
+    Table(HashMap<Primitive, Primitive>),
+}
+
+impl Hash {
+    pub fn new() -> Self {
+        Hash(Encoding::Compact(Vec::new()))
+    }
+
+    pub fn is_compact(&self) -> bool {
+        match self.0 {
+            Encoding::Compact(_) => true,
+            Encoding::Table(_) => false,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match &self.0 {
+            Encoding::Compact(pairs) => pairs.len(),
+            Encoding::Table(table) => table.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get(&self, field: &Primitive) -> Option<&Primitive> {
+        match &self.0 {
+            Encoding::Compact(pairs) => pairs.iter().find(|(f, _)| f == field).map(|(_, v)| v),
+            Encoding::Table(table) => table.get(field),
+        }
+    }
+
+    pub fn contains(&self, field: &Primitive) -> bool {
+        self.get(field).is_some()
+    }
+
+    /// Sets a field, returning whether it is a new one.
+    pub fn insert(&mut self, field: Primitive, value: Primitive) -> bool {
+        if let Encoding::Compact(pairs) = &mut self.0 {
+            if let Some(pair) = pairs.iter_mut().find(|(f, _)| *f == field) {
+                if value.len() <= MAX_COMPACT_LEN {
+                    pair.1 = value;
+                    return false;
+                }
+            } else if pairs.len() < MAX_COMPACT_FIELDS
+                && field.len() <= MAX_COMPACT_LEN
+                && value.len() <= MAX_COMPACT_LEN
+            {
+                pairs.push((field, value));
+                return true;
+            }
+            let table = pairs.drain(..).collect();
+            self.0 = Encoding::Table(table);
+        }
+        match &mut self.0 {
+            Encoding::Table(table) => table.insert(field, value).is_none(),
+            Encoding::Compact(_) => unreachable!("compact hashes were converted above"),
+        }
+    }
+
+    pub fn remove(&mut self, field: &Primitive) -> Option<Primitive> {
+        match &mut self.0 {
+            Encoding::Compact(pairs) => {
+                let i = pairs.iter().position(|(f, _)| f == field)?;
+                Some(pairs.remove(i).1)
+            }
+            Encoding::Table(table) => table.remove(field),
+        }
+    }
+
+    /// The fields and their values, in insertion order while compact.
+    pub fn iter(&self) -> Box<dyn Iterator<Item = (&Primitive, &Primitive)> + '_> {
+        match &self.0 {
+            Encoding::Compact(pairs) => Box::new(pairs.iter().map(|(f, v)| (f, v))),
+            Encoding::Table(table) => Box::new(table.iter()),
+        }
+    }
+}
+
+impl Default for Hash {
+    fn default() -> Self {
+        Hash::new()
+    }
+}
+
+/// Hashes are equal when they hold the same fields, whatever their encoding.
+impl PartialEq for Hash {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().all(|(f, v)| other.get(f) == Some(v))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::domain::Primitive;
+    use super::{Hash, MAX_COMPACT_FIELDS, MAX_COMPACT_LEN};
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+    use std::collections::HashMap;
+
+    #[derive(Debug, Clone)]
+    enum Op {
+        Insert(Primitive, Primitive),
+        Remove(Primitive),
+    }
+
+    fn fields() -> impl Strategy<Value = Primitive> {
+        prop_oneof![
+            (0i64..200).prop_map(Primitive::from),
+            vec(any::<u8>(), 0..80).prop_map(Primitive::from),
+        ]
+    }
+
+    fn ops() -> impl Strategy<Value = Vec<Op>> {
+        vec(
+            prop_oneof![
+                3 => (fields(), fields()).prop_map(|(f, v)| Op::Insert(f, v)),
+                1 => fields().prop_map(Op::Remove),
+            ],
+            0..400,
+        )
+    }
+
+    proptest! {
+        #[test]
+        fn behaves_like_a_map(ops in ops()) {
+            let mut hash = Hash::new();
+            let mut model = HashMap::new();
+            for op in ops {
+                match op {
+                    Op::Insert(f, v) => assert_eq!(hash.insert(f.clone(), v.clone()), model.insert(f, v).is_none()),
+                    Op::Remove(f) => assert_eq!(hash.remove(&f), model.remove(&f)),
+                }
+            }
+            assert_eq!(hash.len(), model.len());
+            assert_eq!(hash.iter().map(|(f, v)| (f.clone(), v.clone())).collect::<HashMap<_, _>>(), model)
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn stays_compact_while_small(fields in vec((0i64..1000).prop_map(Primitive::from), 0..MAX_COMPACT_FIELDS)) {
+            let mut hash = Hash::new();
+            fields.into_iter().for_each(|f| { hash.insert(f.clone(), f); });
+            assert!(hash.is_compact())
+        }
+    }
+
+    #[test]
+    fn converts_when_outgrown() {
+        let mut many = Hash::new();
+        (0..=MAX_COMPACT_FIELDS as i64).for_each(|n| {
+            many.insert(n.into(), n.into());
+        });
+        assert!(!many.is_compact());
+        assert_eq!(many.len(), MAX_COMPACT_FIELDS + 1);
+
+        let mut long = Hash::new();
+        long.insert("field".into(), "short".into());
+        long.insert("field".into(), "x".repeat(MAX_COMPACT_LEN + 1).into());
+        assert!(!long.is_compact());
+        assert_eq!(long.len(), 1);
+
+        (1..=MAX_COMPACT_FIELDS as i64).for_each(|n| {
+            many.remove(&n.into());
+        });
+        let mut compact = Hash::new();
+        compact.insert(0.into(), 0.into());
+        assert!(!many.is_compact() && compact.is_compact());
+        assert_eq!(compact, many);
+    }
+}
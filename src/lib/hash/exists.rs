@@ -0,0 +1,40 @@
+use super::super::domain::{Data, Primitive};
+use super::super::errors::ApplicationError;
+use super::utilities::get_hash;
+use std::collections::HashMap;
+
+pub fn command(
+    store: &HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    field: &Primitive,
+) -> Result<bool, ApplicationError> {
+    Ok(get_hash(store, key)?.is_some_and(|hash| hash.contains(field)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::domain::Primitive;
+    use super::super::set;
+    use super::{command, Data, HashMap};
+    use proptest::collection::{hash_map, vec};
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn finds_set_fields(
+            key in vec(any::<u8>(), 0..32),
+            pairs in hash_map(any::<Primitive>(), any::<Primitive>(), 1..50),
+            other in any::<Primitive>()
+        ) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            let expected = pairs.contains_key(&other);
+            let fields: Vec<Primitive> = pairs.keys().cloned().collect();
+            set::command(&mut store, &key, pairs.into_iter().collect())?;
+            fields.iter().try_for_each(|f| {
+                prop_assert!(command(&store, &key, f)?);
+                Ok(())
+            })?;
+            assert_eq!(command(&store, &key, &other)?, expected)
+        }
+    }
+}
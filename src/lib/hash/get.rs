@@ -0,0 +1,58 @@
+use super::super::domain::{Data, Primitive};
+use super::super::errors::ApplicationError;
+use super::utilities::get_hash;
+use std::collections::HashMap;
+
+pub fn command(
+    store: &HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    field: &Primitive,
+) -> Result<Option<Primitive>, ApplicationError> {
+    Ok(get_hash(store, key)?
+        .and_then(|hash| hash.get(field))
+        .cloned())
+}
+
+pub fn multi_command(
+    store: &HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    fields: &[Primitive],
+) -> Result<Vec<Option<Primitive>>, ApplicationError> {
+    let hash = get_hash(store, key)?;
+    Ok(fields
+        .iter()
+        .map(|field| hash.and_then(|hash| hash.get(field)).cloned())
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::domain::Primitive;
+    use super::super::set;
+    use super::{command, multi_command, Data, HashMap};
+    use proptest::collection::{hash_map, vec};
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn multi_agrees_with_single_gets(
+            key in vec(any::<u8>(), 0..32),
+            pairs in hash_map(any::<Primitive>(), any::<Primitive>(), 1..50),
+            fields in vec(any::<Primitive>(), 1..20)
+        ) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            let fields: Vec<Primitive> = pairs.keys().cloned().chain(fields).collect();
+            set::command(&mut store, &key, pairs.into_iter().collect())?;
+            let expected = fields.iter().map(|f| command(&store, &key, f)).collect::<Result<Vec<_>, _>>()?;
+            assert_eq!(multi_command(&store, &key, &fields)?, expected)
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn missing_hashes_have_no_fields(key in vec(any::<u8>(), 0..32), fields in vec(any::<Primitive>(), 1..20)) {
+            let store: HashMap<Vec<u8>, Data> = HashMap::new();
+            assert_eq!(multi_command(&store, &key, &fields)?, vec![None; fields.len()])
+        }
+    }
+}
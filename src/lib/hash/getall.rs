@@ -0,0 +1,59 @@
+use super::super::domain::{Data, Primitive};
+use super::super::errors::ApplicationError;
+use super::utilities::get_hash;
+use std::collections::HashMap;
+
+pub fn command(
+    store: &HashMap<Vec<u8>, Data>,
+    key: &[u8],
+) -> Result<Vec<(Primitive, Primitive)>, ApplicationError> {
+    Ok(get_hash(store, key)?.map_or_else(Vec::new, |hash| {
+        hash.iter()
+            .map(|(field, value)| (field.clone(), value.clone()))
+            .collect()
+    }))
+}
+
+pub fn keys_command(
+    store: &HashMap<Vec<u8>, Data>,
+    key: &[u8],
+) -> Result<Vec<Primitive>, ApplicationError> {
+    Ok(command(store, key)?
+        .into_iter()
+        .map(|(field, _)| field)
+        .collect())
+}
+
+pub fn vals_command(
+    store: &HashMap<Vec<u8>, Data>,
+    key: &[u8],
+) -> Result<Vec<Primitive>, ApplicationError> {
+    Ok(command(store, key)?
+        .into_iter()
+        .map(|(_, value)| value)
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::domain::Primitive;
+    use super::super::set;
+    use super::{command, keys_command, vals_command, Data, HashMap};
+    use proptest::collection::{hash_map, vec};
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn returns_every_pair(
+            key in vec(any::<u8>(), 0..32),
+            pairs in hash_map(any::<Primitive>(), any::<Primitive>(), 1..200)
+        ) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            set::command(&mut store, &key, pairs.clone().into_iter().collect())?;
+            let all = command(&store, &key)?;
+            assert_eq!(all.iter().cloned().collect::<HashMap<Primitive, Primitive>>(), pairs);
+            assert_eq!(keys_command(&store, &key)?, all.iter().map(|(f, _)| f.clone()).collect::<Vec<_>>());
+            assert_eq!(vals_command(&store, &key)?, all.into_iter().map(|(_, v)| v).collect::<Vec<_>>())
+        }
+    }
+}
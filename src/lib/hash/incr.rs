@@ -0,0 +1,100 @@
+use super::super::domain::{Data, Primitive};
+use super::super::errors::ApplicationError;
+use super::super::incr::{increment, increment_float};
+use super::utilities::{get_hash, get_or_create_hash};
+use std::collections::HashMap;
+
+fn get_field<'a>(
+    store: &'a HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    field: &Primitive,
+) -> Result<Option<&'a Primitive>, ApplicationError> {
+    Ok(get_hash(store, key)?.and_then(|hash| hash.get(field)))
+}
+
+pub fn command(
+    store: &mut HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    field: Primitive,
+    by: i64,
+) -> Result<i64, ApplicationError> {
+    let val = increment(get_field(store, key, &field)?, by)?;
+    get_or_create_hash(store, key)?.insert(field, val.into());
+    Ok(val)
+}
+
+pub fn float_command(
+    store: &mut HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    field: Primitive,
+    by: f64,
+) -> Result<Primitive, ApplicationError> {
+    let val = increment_float(get_field(store, key, &field)?, by)?;
+    get_or_create_hash(store, key)?.insert(field, val.clone());
+    Ok(val)
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::domain::Primitive;
+    use super::super::get;
+    use super::{command, float_command, Data, HashMap};
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn sums_increments(
+            key in vec(any::<u8>(), 0..32),
+            field in any::<Primitive>(),
+            increments in vec(-1000i64..1000, 1..20)
+        ) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            let sum: i64 = increments.iter().sum();
+            increments.into_iter().try_for_each(|by| command(&mut store, &key, field.clone(), by).map(|_| ()))?;
+            assert_eq!(get::command(&store, &key, &field)?, Some(Primitive::from(sum)))
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn failed_increments_change_nothing(
+            key in vec(any::<u8>(), 0..32),
+            field in any::<Primitive>(),
+            by in 1i64..1000
+        ) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            command(&mut store, &key, field.clone(), i64::MAX)?;
+            command(&mut store, &key, field.clone(), by).unwrap_err();
+            assert_eq!(get::command(&store, &key, &field)?, Some(Primitive::from(i64::MAX)));
+            float_command(&mut store, &key, "other".into(), f64::MAX)?;
+            float_command(&mut store, &key, "other".into(), f64::MAX).unwrap_err();
+            assert_eq!(get::command(&store, &key, &"other".into())?, Some(Primitive::from(format!("{}", f64::MAX))))
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn creates_no_hash_on_failure(key in vec(any::<u8>(), 0..32), field in any::<Primitive>()) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            float_command(&mut store, &key, field, f64::NAN).unwrap_err();
+            assert!(store.is_empty())
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn float_increments_accumulate(
+            key in vec(any::<u8>(), 0..32),
+            field in any::<Primitive>(),
+            a in -1e6f64..1e6,
+            b in -1e6f64..1e6
+        ) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            float_command(&mut store, &key, field.clone(), a)?;
+            let sum = float_command(&mut store, &key, field.clone(), b)?;
+            assert_eq!(get::command(&store, &key, &field)?, Some(sum.clone()));
+            assert_eq!(String::from_utf8(sum.into_bytes()).unwrap().parse::<f64>().unwrap(), a + b)
+        }
+    }
+}
@@ -0,0 +1,32 @@
+use super::super::domain::Data;
+use super::super::errors::ApplicationError;
+use super::utilities::get_hash;
+use std::collections::HashMap;
+
+pub fn command(store: &HashMap<Vec<u8>, Data>, key: &[u8]) -> Result<usize, ApplicationError> {
+    Ok(get_hash(store, key)?.map_or(0, |hash| hash.len()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::domain::Primitive;
+    use super::super::set;
+    use super::{command, Data, HashMap};
+    use proptest::collection::{hash_map, vec};
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn returns_number_of_fields(
+            key in vec(any::<u8>(), 0..32),
+            pairs in hash_map(any::<Primitive>(), any::<Primitive>(), 0..200)
+        ) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            let len = pairs.len();
+            if len > 0 {
+                set::command(&mut store, &key, pairs.into_iter().collect())?;
+            }
+            assert_eq!(command(&store, &key)?, len)
+        }
+    }
+}
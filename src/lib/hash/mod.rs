@@ -0,0 +1,12 @@
+pub mod del;
+mod encoding;
+pub mod exists;
+pub mod get;
+pub mod getall;
+pub mod incr;
+pub mod len;
+pub mod set;
+pub mod strlen;
+mod utilities;
+
+pub use encoding::Hash;
@@ -0,0 +1,80 @@
+use super::super::domain::{Data, Primitive};
+use super::super::errors::ApplicationError;
+use super::utilities::get_or_create_hash;
+use std::collections::HashMap;
+
+/// Sets the given fields, returning how many of them are new.
+pub fn command(
+    store: &mut HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    pairs: Vec<(Primitive, Primitive)>,
+) -> Result<usize, ApplicationError> {
+    let hash = get_or_create_hash(store, key)?;
+    Ok(pairs
+        .into_iter()
+        .filter(|(field, value)| hash.insert(field.clone(), value.clone()))
+        .count())
+}
+
+/// Sets a field only if the hash doesn't have it yet.
+pub fn nx_command(
+    store: &mut HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    field: Primitive,
+    value: Primitive,
+) -> Result<bool, ApplicationError> {
+    let hash = get_or_create_hash(store, key)?;
+    Ok(!hash.contains(&field) && hash.insert(field, value))
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::domain::Primitive;
+    use super::super::get;
+    use super::{command, nx_command, Data, HashMap};
+    use proptest::collection::{hash_map, vec};
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn returns_n_new_fields(
+            key in vec(any::<u8>(), 0..32),
+            first in hash_map(any::<Primitive>(), any::<Primitive>(), 1..50),
+            second in hash_map(any::<Primitive>(), any::<Primitive>(), 1..50)
+        ) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            let new = second.keys().filter(|f| !first.contains_key(f)).count();
+            let first_len = first.len();
+            assert_eq!(command(&mut store, &key, first.into_iter().collect())?, first_len);
+            assert_eq!(command(&mut store, &key, second.clone().into_iter().collect())?, new);
+            second.into_iter().try_for_each(|(f, v)| {
+                prop_assert_eq!(get::command(&store, &key, &f)?, Some(v));
+                Ok(())
+            })?
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn nx_keeps_existing_values(
+            key in vec(any::<u8>(), 0..32),
+            field in any::<Primitive>(),
+            first in any::<Primitive>(),
+            second in any::<Primitive>()
+        ) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            assert!(nx_command(&mut store, &key, field.clone(), first.clone())?);
+            assert!(!nx_command(&mut store, &key, field.clone(), second)?);
+            assert_eq!(get::command(&store, &key, &field)?, Some(first))
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn refuses_other_types(key in vec(any::<u8>(), 0..32), field in any::<Primitive>()) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            store.insert(key.clone(), Data::from(1));
+            command(&mut store, &key, vec![(field.clone(), field)]).unwrap_err();
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use super::super::domain::{Data, Primitive};
+use super::super::errors::ApplicationError;
+use super::utilities::get_hash;
+use std::collections::HashMap;
+
+/// The length of a field's value, or 0 if there is no such field.
+pub fn command(
+    store: &HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    field: &Primitive,
+) -> Result<usize, ApplicationError> {
+    Ok(get_hash(store, key)?
+        .and_then(|hash| hash.get(field))
+        .map_or(0, Primitive::len))
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::domain::Primitive;
+    use super::super::set;
+    use super::{command, Data, HashMap};
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn returns_value_length(
+            key in vec(any::<u8>(), 0..32),
+            field in any::<Primitive>(),
+            value in any::<Primitive>(),
+            other in any::<Primitive>()
+        ) {
+            prop_assume!(field != other);
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            let len = value.to_bytes().len();
+            set::command(&mut store, &key, vec![(field.clone(), value)])?;
+            assert_eq!(command(&store, &key, &field)?, len);
+            assert_eq!(command(&store, &key, &other)?, 0)
+        }
+    }
+}
@@ -0,0 +1,40 @@
+use super::super::domain::{Data, Escaped};
+use super::super::errors::ApplicationError;
+use super::Hash;
+use std::collections::HashMap;
+
+pub fn get_hash<'a>(
+    store: &'a HashMap<Vec<u8>, Data>,
+    key: &[u8],
+) -> Result<Option<&'a Hash>, ApplicationError> {
+    match store.get(key) {
+        Some(Data::Hash(hash)) => Ok(Some(hash)),
+        Some(_) => Err(format!("Value at {} is not a hash", Escaped(key)).into()),
+        None => Ok(None),
+    }
+}
+
+pub fn get_hash_mut<'a>(
+    store: &'a mut HashMap<Vec<u8>, Data>,
+    key: &[u8],
+) -> Result<Option<&'a mut Hash>, ApplicationError> {
+    match store.get_mut(key) {
+        Some(Data::Hash(hash)) => Ok(Some(hash)),
+        Some(_) => Err(format!("Value at {} is not a hash", Escaped(key)).into()),
+        None => Ok(None),
+    }
+}
+
+/// Gets the hash at `key` for writing, creating an empty one if need be.
+pub fn get_or_create_hash<'a>(
+    store: &'a mut HashMap<Vec<u8>, Data>,
+    key: &[u8],
+) -> Result<&'a mut Hash, ApplicationError> {
+    match store
+        .entry(key.to_vec())
+        .or_insert_with(|| Data::Hash(Hash::new()))
+    {
+        Data::Hash(hash) => Ok(hash),
+        _ => Err(format!("Value at {} is not a hash", Escaped(key)).into()),
+    }
+}
@@ -0,0 +1,24 @@
+use super::super::domain::Data;
+use super::super::errors::ApplicationError;
+use super::utilities::{edit_hll, get_hll};
+use std::collections::HashMap;
+
+/// PFADD: returns whether the estimate may have changed, which is when a
+/// register was raised or the HyperLogLog was created.
+pub fn command(
+    store: &mut HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    elements: &[Vec<u8>],
+) -> Result<bool, ApplicationError> {
+    let created = get_hll(store, key)?.is_none();
+    edit_hll(store, key, |hll| {
+        let mut changed = created;
+        for element in elements {
+            changed |= hll.add(element)?;
+        }
+        if changed {
+            hll.invalidate();
+        }
+        Ok(changed)
+    })
+}
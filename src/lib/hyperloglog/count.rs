@@ -0,0 +1,78 @@
+use super::super::domain::Data;
+use super::super::errors::ApplicationError;
+use super::utilities::{edit_hll, get_hll};
+use super::{estimate, registers, DENSE, REGISTERS};
+use std::collections::HashMap;
+
+/// PFCOUNT. The estimate of a single HyperLogLog is cached in its header;
+/// that of several is the estimate of their union, worked out afresh.
+pub fn command(
+    store: &mut HashMap<Vec<u8>, Data>,
+    keys: &[Vec<u8>],
+) -> Result<u64, ApplicationError> {
+    match keys {
+        [] => Err(String::from("No key provided").into()),
+        [key] => {
+            if get_hll(store, key)?.is_none() {
+                return Ok(0);
+            }
+            edit_hll(store, key, |hll| {
+                if let Some(card) = hll.cached() {
+                    return Ok(card);
+                }
+                let card = estimate(&registers(&hll.0)?);
+                hll.cache(card);
+                Ok(card)
+            })
+        }
+        keys => Ok(estimate(&union(store, keys)?.0)),
+    }
+}
+
+/// The highest value of each register across the HyperLogLogs at `keys`,
+/// and whether any of them is dense. Missing keys count as empty.
+pub fn union(
+    store: &HashMap<Vec<u8>, Data>,
+    keys: &[Vec<u8>],
+) -> Result<(Vec<u8>, bool), ApplicationError> {
+    let mut max = vec![0; REGISTERS];
+    let mut dense = false;
+    for key in keys {
+        if let Some(hll) = get_hll(store, key)? {
+            let bytes = hll.as_bytes();
+            dense |= bytes[4] == DENSE;
+            for (max, count) in max.iter_mut().zip(registers(&bytes)?) {
+                *max = count.max(*max);
+            }
+        }
+    }
+    Ok((max, dense))
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::add;
+    use super::{command, Data};
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+    use std::collections::HashMap;
+
+    proptest! {
+        #[test]
+        fn counts_are_cached_until_the_next_change(
+            a in vec(vec(any::<u8>(), 0..8), 0..200),
+            b in vec(vec(any::<u8>(), 0..8), 0..200),
+        ) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            let key = vec![b"hll".to_vec()];
+            add::command(&mut store, &key[0], &a)?;
+            let first = command(&mut store, &key)?;
+            prop_assert_eq!(command(&mut store, &key)?, first);
+            add::command(&mut store, b"other", &b)?;
+            let union = command(&mut store, &[key[0].clone(), b"other".to_vec()])?;
+            add::command(&mut store, &key[0], &b)?;
+            prop_assert_eq!(command(&mut store, &key)?, union);
+            prop_assert_eq!(command(&mut store, &[b"missing".to_vec()])?, 0);
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use super::super::domain::Data;
+use super::super::errors::ApplicationError;
+use super::count::union;
+use super::utilities::edit_hll;
+use std::collections::HashMap;
+
+/// PFMERGE, which merges the HyperLogLogs at `keys` into the one at
+/// `destination`, creating it if need be. The destination only turns dense
+/// if one of them is.
+pub fn store_command(
+    store: &mut HashMap<Vec<u8>, Data>,
+    destination: &[u8],
+    keys: &[Vec<u8>],
+) -> Result<(), ApplicationError> {
+    let sources: Vec<Vec<u8>> = std::iter::once(destination.to_vec())
+        .chain(keys.iter().cloned())
+        .collect();
+    let (max, dense) = union(store, &sources)?;
+    edit_hll(store, destination, |hll| {
+        if dense {
+            hll.densify()?;
+        }
+        for (index, count) in max.into_iter().enumerate() {
+            if count > 0 {
+                hll.set(index, count)?;
+            }
+        }
+        hll.invalidate();
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::add;
+    use super::super::utilities::get_hll;
+    use super::{store_command, Data};
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+    use std::collections::HashMap;
+
+    proptest! {
+        #[test]
+        fn merging_is_adding_everything(
+            a in vec(vec(any::<u8>(), 0..8), 0..300),
+            b in vec(vec(any::<u8>(), 0..8), 0..300),
+        ) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            add::command(&mut store, b"a", &a)?;
+            add::command(&mut store, b"b", &b)?;
+            add::command(&mut store, b"both", &a)?;
+            add::command(&mut store, b"both", &b)?;
+            store_command(&mut store, b"merged", &[b"a".to_vec(), b"b".to_vec()])?;
+            let registers = |store: &HashMap<Vec<u8>, Data>, key: &[u8]| {
+                super::super::registers(&get_hll(store, key).unwrap().unwrap().as_bytes()).unwrap()
+            };
+            prop_assert_eq!(registers(&store, b"merged"), registers(&store, b"both"));
+        }
+    }
+}
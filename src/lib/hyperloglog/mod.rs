@@ -0,0 +1,432 @@
+//! HyperLogLogs, kept in string values in the same format as Redis so that
+//! they can be dumped from one and loaded into the other.
+//!
+//! A HyperLogLog is a 16 byte header followed by its 16384 registers. The
+//! header is the magic `HYLL`, an encoding byte, three unused bytes and the
+//! last cardinality PFCOUNT worked out, little endian, whose top bit is set
+//! once the registers have changed since. The registers come in one of two
+//! encodings:
+//!
+//! * Dense: 6 bits per register, packed least significant bit first.
+//! * Sparse: a run-length encoding of opcodes, which is what new
+//!   HyperLogLogs start out as. `00xxxxxx` is a run of up to 64 zeros,
+//!   `01xxxxxx yyyyyyyy` a run of up to 16384 zeros and `1vvvvvxx` a run of
+//!   up to 4 registers holding a value of at most 32. Once a register needs
+//!   a bigger value or the string grows past `SPARSE_MAX_BYTES`, it turns
+//!   dense for good.
+//!
+//! Sparse edits are made the way Redis makes them, down to which adjacent
+//! runs get merged, so that the same commands leave the same bytes behind.
+
+pub mod add;
+pub mod count;
+pub mod merge;
+mod utilities;
+
+use super::errors::ApplicationError;
+use std::convert::TryInto;
+
+/// How many bits of an element's hash pick its register.
+const P: u32 = 14;
+/// How many bits of the hash are left to count zeros in.
+const Q: usize = 64 - P as usize;
+pub const REGISTERS: usize = 1 << P;
+const HEADER: usize = 16;
+const DENSE_SIZE: usize = HEADER + (REGISTERS * 6).div_ceil(8);
+const DENSE: u8 = 0;
+const SPARSE: u8 = 1;
+/// Redis's default `hll-sparse-max-bytes`.
+const SPARSE_MAX_BYTES: usize = 3000;
+const SPARSE_MAX_VALUE: u8 = 32;
+const SPARSE_MAX_RUN: usize = 4;
+const ZERO_MAX_RUN: usize = 64;
+const XZERO_MAX_RUN: usize = 16384;
+const STALE: u8 = 0x80;
+const ALPHA_INF: f64 = 0.721_347_520_444_481_7;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct HyperLogLog(Vec<u8>);
+
+impl HyperLogLog {
+    /// An empty HyperLogLog, sparse and with a cardinality of 0 cached.
+    pub fn new() -> Self {
+        let mut bytes = b"HYLL".to_vec();
+        bytes.extend_from_slice(&[SPARSE, 0, 0, 0]);
+        bytes.extend_from_slice(&[0; 8]);
+        let mut left = REGISTERS;
+        while left > 0 {
+            let run = left.min(XZERO_MAX_RUN);
+            push_zeros(&mut bytes, run);
+            left -= run;
+        }
+        HyperLogLog(bytes)
+    }
+
+    /// Whether `bytes` holds a HyperLogLog, going by its header alone.
+    pub fn is_valid(bytes: &[u8]) -> bool {
+        bytes.len() >= HEADER
+            && &bytes[..4] == b"HYLL"
+            && match bytes[4] {
+                DENSE => bytes.len() == DENSE_SIZE,
+                SPARSE => true,
+                _ => false,
+            }
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.0
+    }
+
+    /// Hashes `element` into its register, returning whether the register
+    /// changed.
+    pub fn add(&mut self, element: &[u8]) -> Result<bool, ApplicationError> {
+        let (index, count) = hash(element);
+        self.set(index, count)
+    }
+
+    /// Raises the register at `index` to `count`, returning whether it was
+    /// lower.
+    pub fn set(&mut self, index: usize, count: u8) -> Result<bool, ApplicationError> {
+        if self.0[4] == DENSE {
+            Ok(dense_set(&mut self.0[HEADER..], index, count))
+        } else {
+            self.sparse_set(index, count)
+        }
+    }
+
+    pub fn is_dense(&self) -> bool {
+        self.0[4] == DENSE
+    }
+
+    /// The cardinality last cached, unless the registers changed since.
+    pub fn cached(&self) -> Option<u64> {
+        if self.0[15] & STALE == 0 {
+            let card: [u8; 8] = self.0[8..HEADER].try_into().unwrap_or_default();
+            Some(u64::from_le_bytes(card))
+        } else {
+            None
+        }
+    }
+
+    pub fn cache(&mut self, card: u64) {
+        self.0[8..HEADER].copy_from_slice(&card.to_le_bytes());
+    }
+
+    pub fn invalidate(&mut self) {
+        self.0[15] |= STALE;
+    }
+
+    /// Turns the registers dense, keeping the header as it was.
+    pub fn densify(&mut self) -> Result<(), ApplicationError> {
+        if self.is_dense() {
+            return Ok(());
+        }
+        let registers = registers(&self.0)?;
+        let mut dense = self.0[..HEADER].to_vec();
+        dense[4] = DENSE;
+        dense.resize(DENSE_SIZE, 0);
+        for (index, count) in registers.into_iter().enumerate() {
+            if count > 0 {
+                dense_set(&mut dense[HEADER..], index, count);
+            }
+        }
+        self.0 = dense;
+        Ok(())
+    }
+
+    fn sparse_set(&mut self, index: usize, count: u8) -> Result<bool, ApplicationError> {
+        if count > SPARSE_MAX_VALUE {
+            return self.promote(index, count);
+        }
+        // Find the opcode whose run covers the register.
+        let (mut at, mut first, mut previous) = (HEADER, 0, None);
+        let (value, run, len) = loop {
+            let (value, run, len) = opcode(&self.0[at..])?;
+            if index < first + run {
+                break (value, run, len);
+            }
+            previous = Some(at);
+            at += len;
+            first += run;
+        };
+        if value > 0 && value >= count {
+            return Ok(false);
+        }
+        if run == 1 && len == 1 {
+            self.0[at] = value_op(count, 1);
+            self.merge_values(previous.unwrap_or(HEADER));
+            return Ok(true);
+        }
+        // Split the run into what comes before the register, the register
+        // and what comes after it.
+        let last = first + run - 1;
+        let mut split = Vec::with_capacity(5);
+        let push_run = |split: &mut Vec<u8>, run: usize| match value {
+            0 => push_zeros(split, run),
+            value => split.push(value_op(value, run)),
+        };
+        if index != first {
+            push_run(&mut split, index - first);
+        }
+        split.push(value_op(count, 1));
+        if index != last {
+            push_run(&mut split, last - index);
+        }
+        if split.len() > len && self.0.len() + split.len() - len > SPARSE_MAX_BYTES {
+            return self.promote(index, count);
+        }
+        self.0.splice(at..at + len, split);
+        self.merge_values(previous.unwrap_or(HEADER));
+        Ok(true)
+    }
+
+    fn promote(&mut self, index: usize, count: u8) -> Result<bool, ApplicationError> {
+        self.densify()?;
+        Ok(dense_set(&mut self.0[HEADER..], index, count))
+    }
+
+    /// Merges neighbouring value runs that hold the same value and fit in
+    /// one opcode, looking at no more than five opcodes from `at`.
+    fn merge_values(&mut self, mut at: usize) {
+        let mut scan = 5;
+        while at < self.0.len() && scan > 0 {
+            scan -= 1;
+            let op = self.0[at];
+            if op & 0xc0 == 0x40 {
+                at += 2;
+                continue;
+            }
+            if op & 0x80 == 0 {
+                at += 1;
+                continue;
+            }
+            if let Some(next) = self.0.get(at + 1).filter(|next| *next & 0x80 != 0) {
+                let run = op_run(op) + op_run(*next);
+                if op_value(op) == op_value(*next) && run <= SPARSE_MAX_RUN {
+                    self.0[at + 1] = value_op(op_value(op), run);
+                    self.0.remove(at);
+                    continue;
+                }
+            }
+            at += 1;
+        }
+    }
+}
+
+impl Default for HyperLogLog {
+    fn default() -> Self {
+        HyperLogLog::new()
+    }
+}
+
+fn corrupted() -> ApplicationError {
+    String::from("Corrupted HyperLogLog object detected").into()
+}
+
+fn op_value(op: u8) -> u8 {
+    ((op >> 2) & 0x1f) + 1
+}
+
+fn op_run(op: u8) -> usize {
+    usize::from(op & 0x03) + 1
+}
+
+fn value_op(value: u8, run: usize) -> u8 {
+    0x80 | ((value - 1) << 2) | (run - 1) as u8
+}
+
+fn push_zeros(ops: &mut Vec<u8>, run: usize) {
+    if run > ZERO_MAX_RUN {
+        ops.push(0x40 | ((run - 1) >> 8) as u8);
+        ops.push(((run - 1) & 0xff) as u8);
+    } else {
+        ops.push((run - 1) as u8);
+    }
+}
+
+/// The value, run length and size of the sparse opcode `ops` starts with.
+fn opcode(ops: &[u8]) -> Result<(u8, usize, usize), ApplicationError> {
+    match ops {
+        [op, ..] if op & 0x80 != 0 => Ok((op_value(*op), op_run(*op), 1)),
+        [op, next, ..] if op & 0x40 != 0 => {
+            Ok((0, (usize::from(op & 0x3f) << 8 | usize::from(*next)) + 1, 2))
+        }
+        [op, ..] if op & 0x40 == 0 => Ok((0, usize::from(*op) + 1, 1)),
+        _ => Err(corrupted()),
+    }
+}
+
+fn dense_get(registers: &[u8], index: usize) -> u8 {
+    let (byte, shift) = (index * 6 / 8, index * 6 % 8);
+    let low = u16::from(registers[byte]) >> shift;
+    let high = u16::from(registers.get(byte + 1).copied().unwrap_or(0)) << (8 - shift);
+    ((low | high) & 0x3f) as u8
+}
+
+fn dense_set(registers: &mut [u8], index: usize, count: u8) -> bool {
+    if dense_get(registers, index) >= count {
+        return false;
+    }
+    let (byte, shift) = (index * 6 / 8, index * 6 % 8);
+    let (mask, value) = (0x3fu16 << shift, u16::from(count) << shift);
+    registers[byte] = (registers[byte] & !mask as u8) | value as u8;
+    if let Some(next) = registers.get_mut(byte + 1) {
+        *next = (*next & !(mask >> 8) as u8) | (value >> 8) as u8;
+    }
+    true
+}
+
+/// Every register of the HyperLogLog in `bytes`, one per byte.
+fn registers(bytes: &[u8]) -> Result<Vec<u8>, ApplicationError> {
+    if bytes[4] == DENSE {
+        let dense = &bytes[HEADER..];
+        return Ok((0..REGISTERS)
+            .map(|index| dense_get(dense, index))
+            .collect());
+    }
+    let mut registers = Vec::with_capacity(REGISTERS);
+    let mut ops = &bytes[HEADER..];
+    while !ops.is_empty() {
+        let (value, run, len) = opcode(ops)?;
+        if registers.len() + run > REGISTERS {
+            return Err(corrupted());
+        }
+        registers.resize(registers.len() + run, value);
+        ops = &ops[len..];
+    }
+    if registers.len() == REGISTERS {
+        Ok(registers)
+    } else {
+        Err(corrupted())
+    }
+}
+
+/// The register an element lands in and the value it offers it: one more
+/// than the number of trailing zeros in the rest of its hash.
+fn hash(element: &[u8]) -> (usize, u8) {
+    let hash = murmur_hash_64a(element, 0xadc8_3b19);
+    let index = (hash & (REGISTERS as u64 - 1)) as usize;
+    let rest = (hash >> P) | (1 << Q);
+    (index, rest.trailing_zeros() as u8 + 1)
+}
+
+fn murmur_hash_64a(key: &[u8], seed: u64) -> u64 {
+    const M: u64 = 0xc6a4_a793_5bd1_e995;
+    const R: u32 = 47;
+    let mut h = seed ^ (key.len() as u64).wrapping_mul(M);
+    let words = key.chunks_exact(8);
+    let tail = words.remainder();
+    for word in words {
+        let mut k = u64::from_le_bytes(word.try_into().unwrap_or_default());
+        k = k.wrapping_mul(M);
+        k ^= k >> R;
+        k = k.wrapping_mul(M);
+        h ^= k;
+        h = h.wrapping_mul(M);
+    }
+    if !tail.is_empty() {
+        for (at, byte) in tail.iter().enumerate() {
+            h ^= u64::from(*byte) << (8 * at);
+        }
+        h = h.wrapping_mul(M);
+    }
+    h ^= h >> R;
+    h = h.wrapping_mul(M);
+    h ^ (h >> R)
+}
+
+/// Estimates the cardinality of `registers` with Otmar Ertl's improved
+/// estimator, as Redis does.
+fn estimate(registers: &[u8]) -> u64 {
+    let mut histogram = [0u32; 64];
+    for count in registers {
+        histogram[usize::from(*count)] += 1;
+    }
+    let m = REGISTERS as f64;
+    let mut z = m * tau((m - f64::from(histogram[Q + 1])) / m);
+    for count in histogram[1..=Q].iter().rev() {
+        z += f64::from(*count);
+        z *= 0.5;
+    }
+    z += m * sigma(f64::from(histogram[0]) / m);
+    (ALPHA_INF * m * m / z).round() as u64
+}
+
+fn tau(mut x: f64) -> f64 {
+    if x == 0.0 || x == 1.0 {
+        return 0.0;
+    }
+    let (mut y, mut z) = (1.0, 1.0 - x);
+    loop {
+        x = x.sqrt();
+        let previous = z;
+        y *= 0.5;
+        z -= (1.0 - x).powi(2) * y;
+        if previous == z {
+            return z / 3.0;
+        }
+    }
+}
+
+fn sigma(mut x: f64) -> f64 {
+    if x == 1.0 {
+        return f64::INFINITY;
+    }
+    let (mut y, mut z) = (1.0, x);
+    loop {
+        x *= x;
+        let previous = z;
+        z += x * y;
+        y += y;
+        if previous == z {
+            return z;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{estimate, registers, HyperLogLog, HEADER, REGISTERS, SPARSE_MAX_BYTES};
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn sparse_and_dense_registers_agree(
+            sets in vec((0..REGISTERS, 1u8..40), 0..400),
+        ) {
+            let mut sparse = HyperLogLog::new();
+            let mut dense = HyperLogLog::new();
+            dense.densify()?;
+            let mut model = vec![0u8; REGISTERS];
+            for (index, count) in sets {
+                let raised = model[index] < count;
+                model[index] = model[index].max(count);
+                prop_assert_eq!(sparse.set(index, count)?, raised);
+                prop_assert_eq!(dense.set(index, count)?, raised);
+            }
+            prop_assert!(sparse.is_dense() || sparse.0.len() <= SPARSE_MAX_BYTES);
+            prop_assert_eq!(registers(&sparse.0)?, model.clone());
+            prop_assert_eq!(registers(&dense.0)?, model);
+            sparse.densify()?;
+            prop_assert_eq!(&sparse.0[HEADER..], &dense.0[HEADER..]);
+        }
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(16))]
+        /// HyperLogLog's standard error is 1.04 / sqrt(registers), 0.81%
+        /// here, so estimates stay within three of them, about 2.4%.
+        #[test]
+        fn estimates_are_close(n in 0u32..50_000, salt in any::<u32>()) {
+            let mut hll = HyperLogLog::new();
+            for element in 0..n {
+                hll.add(format!("{}:{}", salt, element).as_bytes())?;
+            }
+            let estimate = estimate(&registers(&hll.0)?) as f64;
+            let error = (estimate - f64::from(n)).abs();
+            let bound = 3.0 * 1.04 / (REGISTERS as f64).sqrt();
+            prop_assert!(error <= (f64::from(n) * bound).max(1.0), "{} for {}", estimate, n);
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use super::super::domain::{Data, Escaped, Primitive};
+use super::super::errors::ApplicationError;
+use super::HyperLogLog;
+use std::collections::HashMap;
+
+fn not_a_hyperloglog(key: &[u8]) -> ApplicationError {
+    format!("Value at {} is not a HyperLogLog", Escaped(key)).into()
+}
+
+/// The bytes of the HyperLogLog at `key`, if there is one.
+pub fn get_hll<'a>(
+    store: &'a HashMap<Vec<u8>, Data>,
+    key: &[u8],
+) -> Result<Option<&'a Primitive>, ApplicationError> {
+    match store.get(key) {
+        Some(Data::Primitive(p)) if HyperLogLog::is_valid(&p.as_bytes()) => Ok(Some(p)),
+        Some(_) => Err(not_a_hyperloglog(key)),
+        None => Ok(None),
+    }
+}
+
+/// Runs `edit` on the HyperLogLog at `key`, creating an empty one if need
+/// be.
+pub fn edit_hll<T, F>(
+    store: &mut HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    edit: F,
+) -> Result<T, ApplicationError>
+where
+    F: FnOnce(&mut HyperLogLog) -> Result<T, ApplicationError>,
+{
+    if get_hll(store, key)?.is_none() {
+        let empty = HyperLogLog::new().into_bytes();
+        store.insert(key.to_vec(), Data::Primitive(Primitive::from(empty)));
+    }
+    match store.get_mut(key) {
+        Some(Data::Primitive(p)) => p.edit(|bytes| {
+            let mut hll = HyperLogLog(std::mem::take(bytes));
+            let edited = edit(&mut hll);
+            *bytes = hll.into_bytes();
+            edited
+        }),
+        _ => Err(not_a_hyperloglog(key)),
+    }
+}
@@ -1,43 +1,174 @@
 use super::domain::{Data, Primitive};
-use super::errors::ApplicationError;
+use super::errors::{ApplicationError, Fallible};
 use std::collections::HashMap;
 
-pub fn command(store: &mut HashMap<String, Data>, key: &str) -> Result<i64, ApplicationError> {
+/// Adds `by` to an integer string, where a missing one counts as 0.
+pub fn increment(value: Option<&Primitive>, by: i64) -> Result<i64, ApplicationError> {
+    let old_val = match value {
+        Some(p) => p.as_integer().fail_to(&format!("{} is not a number", p))?,
+        None => 0,
+    };
+    old_val
+        .checked_add(by)
+        .fail_to("increment or decrement would overflow")
+}
+
+/// Adds `by` to a string holding a float, where a missing one counts as 0.
+pub fn increment_float(value: Option<&Primitive>, by: f64) -> Result<Primitive, ApplicationError> {
+    let old_val = match value {
+        Some(p) => p.as_float().fail_to(&format!("{} is not a float", p))?,
+        None => 0.0,
+    };
+    let val = old_val + by;
+    if !val.is_finite() {
+        return Err(String::from("increment would produce NaN or Infinity").into());
+    }
+    Ok(val.into())
+}
+
+/// Parses a float the way Redis does, refusing infinities and NaN.
+pub fn parse_float(bytes: &[u8]) -> Option<f64> {
+    match std::str::from_utf8(bytes).map(str::parse::<f64>) {
+        Ok(Ok(n)) if n.is_finite() => Some(n),
+        _ => None,
+    }
+}
+
+/// INCR, INCRBY, DECR and DECRBY, which all add `by` to an integer.
+pub fn command(
+    store: &mut HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    by: i64,
+) -> Result<i64, ApplicationError> {
+    let val = increment(get_primitive(store, key)?, by)?;
+    store.insert(key.to_vec(), val.into());
+    Ok(val)
+}
+
+/// INCRBYFLOAT, which replies with the float as it was stored.
+pub fn float_command(
+    store: &mut HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    by: f64,
+) -> Result<Primitive, ApplicationError> {
+    let val = increment_float(get_primitive(store, key)?, by)?;
+    store.insert(key.to_vec(), val.clone().into());
+    Ok(val)
+}
+
+fn get_primitive<'a>(
+    store: &'a HashMap<Vec<u8>, Data>,
+    key: &[u8],
+) -> Result<Option<&'a Primitive>, ApplicationError> {
     match store.get(key) {
-        Some(Data::Primitive(Primitive::Number(old_val))) => {
-            let val = old_val + 1;
-            store.insert(key.into(), val.into());
-            Ok(val)
-        }
+        Some(Data::Primitive(p)) => Ok(Some(p)),
         Some(bad_value) => Err(format!("{} is not a number", bad_value).into()),
-        None => {
-            store.insert(key.into(), 1.into());
-            Ok(1)
-        }
+        None => Ok(None),
     }
 }
 
 #[cfg(test)]
 mod test {
-    use super::{command, Data, HashMap};
+    use super::super::domain::Primitive;
+    use super::{command, float_command, increment, increment_float, Data, HashMap};
+    use proptest::collection::vec;
     use proptest::prelude::*;
 
     proptest! {
         #[test]
-        fn incr_empty_key_is_1(key in "\".*\"") {
-            let mut store: HashMap<String, Data> = HashMap::new();
-            assert_eq!(command(&mut store, &key)?, 1)
+        fn incr_empty_key_is_1(key in vec(any::<u8>(), 0..32)) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            assert_eq!(command(&mut store, &key, 1)?, 1)
         }
     }
 
     proptest! {
         #[test]
-        fn incr_n_times_is_n(key in "\".*\"", n in 1i64..10) {
-            let mut store: HashMap<String, Data> = HashMap::new();
+        fn incr_n_times_is_n(key in vec(any::<u8>(), 0..32), n in 1i64..10) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
             for _ in 1..n {
-                command(&mut store, &key)?;
+                command(&mut store, &key, 1)?;
             }
-            assert_eq!(command(&mut store, &key)?, n)
+            assert_eq!(command(&mut store, &key, 1)?, n)
         }
     }
+
+    proptest! {
+        #[test]
+        fn incr_parses_numeric_strings(key in vec(any::<u8>(), 0..32), n in -1000i64..1000) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            store.insert(key.clone(), format!("{}", n).into());
+            assert_eq!(command(&mut store, &key, 1)?, n + 1)
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn incr_rejects_non_canonical_numbers(key in vec(any::<u8>(), 0..32), n in "(0|\\+|-0| )[0-9]+") {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            store.insert(key.clone(), n.into());
+            command(&mut store, &key, 1).unwrap_err();
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn increments_never_overflow(n in any::<i64>(), by in any::<i64>()) {
+            match n.checked_add(by) {
+                Some(sum) => assert_eq!(increment(Some(&Primitive::from(n)), by)?, sum),
+                None => {
+                    increment(Some(&Primitive::from(n)), by).unwrap_err();
+                }
+            }
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn incr_leaves_the_value_alone_on_overflow(key in vec(any::<u8>(), 0..32)) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            store.insert(key.clone(), i64::MAX.into());
+            command(&mut store, &key, 1).unwrap_err();
+            assert_eq!(store.get(&key), Some(&Data::from(i64::MAX)))
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn increments_add_up(key in vec(any::<u8>(), 0..32), bys in vec(-1000i64..1000, 1..20)) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            for (i, by) in bys.iter().enumerate() {
+                assert_eq!(command(&mut store, &key, *by)?, bys[..=i].iter().sum::<i64>())
+            }
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn float_commands_store_what_they_reply(key in vec(any::<u8>(), 0..32), n in -1000i64..1000, by in -1e6f64..1e6) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            store.insert(key.clone(), n.into());
+            let val = float_command(&mut store, &key, by)?;
+            assert_eq!(store.get(&key), Some(&Data::from(val)))
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn float_increments_round_trip(n in -1e12f64..1e12, by in -1e12f64..1e12) {
+            let sum = increment_float(Some(&Primitive::from(format!("{}", n))), by)?;
+            assert_eq!(String::from_utf8(sum.into_bytes()).unwrap().parse::<f64>().unwrap(), n + by)
+        }
+    }
+
+    #[test]
+    fn floats_render_like_redis() {
+        let render = |n: &str, by: f64| increment_float(Some(&Primitive::from(n)), by).unwrap();
+        assert_eq!(render("10.50", 0.1), Primitive::from("10.6"));
+        assert_eq!(render("5.0e3", 200.0), Primitive::from("5200"));
+        assert_eq!(render("3", -5.5), Primitive::from("-2.5"));
+        increment_float(Some(&Primitive::from("abc")), 1.0).unwrap_err();
+        increment_float(Some(&Primitive::from("1")), f64::MAX).unwrap();
+        increment_float(Some(&Primitive::from(format!("{}", f64::MAX))), f64::MAX).unwrap_err();
+    }
 }
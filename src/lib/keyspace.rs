@@ -0,0 +1,221 @@
+use super::clock::{Clock, SystemClock};
+use super::db::Db;
+use super::domain::{Command, Reply};
+use super::errors::ApplicationError;
+use super::execute::execute;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+/// How long a blocked client sleeps before checking whether it should give
+/// up, for instance because it hung up.
+const BLOCKED_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How many keys with deadlines each round of active expiry looks at.
+const ACTIVE_EXPIRE_SAMPLES: usize = 20;
+
+/// How long one active expiry cycle may go on for.
+const ACTIVE_EXPIRE_BUDGET: Duration = Duration::from_millis(25);
+
+/// The keyspace every connection shares. Each command holds the lock for
+/// its whole run, which gives commands the same atomicity they have in Redis.
+///
+/// Clients blocked on keys queue up on each of them. Whenever a command may
+/// have changed the keyspace the blocked clients wake up, and only the first
+/// in line for a key gets to serve itself from it, so clients are served in
+/// the order they blocked. Clients that only read from their keys needn't
+/// wait their turn.
+///
+/// Commands run at the time the keyspace's clock gives when they start.
+pub struct Keyspace {
+    state: Mutex<State>,
+    changed: Condvar,
+    clock: Arc<dyn Clock>,
+}
+
+#[derive(Default)]
+struct State {
+    db: Db,
+    waiting: HashMap<Vec<u8>, VecDeque<u64>>,
+    next_ticket: u64,
+}
+
+impl Keyspace {
+    pub fn new() -> Self {
+        Keyspace::with_clock(Arc::new(SystemClock))
+    }
+
+    pub fn with_clock(clock: Arc<dyn Clock>) -> Self {
+        Keyspace {
+            state: Mutex::new(State::default()),
+            changed: Condvar::new(),
+            clock,
+        }
+    }
+
+    /// A command that panicked has already lost its connection, so the rest
+    /// of the server carries on with the keyspace.
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn execute(&self, command: Command) -> Result<Reply, ApplicationError> {
+        self.with_store(|db, now| execute(db, command, now))
+    }
+
+    /// Runs `run` on the store at the current time with the keyspace locked,
+    /// waking blocked clients in case it changed anything.
+    pub fn with_store<T, F>(&self, run: F) -> T
+    where
+        F: FnOnce(&mut Db, u64) -> T,
+    {
+        let mut state = self.lock();
+        let result = run(&mut state.db, self.clock.now());
+        if !state.waiting.is_empty() {
+            self.changed.notify_all();
+        }
+        result
+    }
+
+    /// Waits until `serve` can produce a reply from one of `keys`, trying
+    /// them in order, and replies with a null array once `timeout` runs out
+    /// or the client hangs up. Without a timeout it waits for as long as it
+    /// takes.
+    pub fn block<S, H>(
+        &self,
+        keys: &[Vec<u8>],
+        timeout: Option<Duration>,
+        in_line: bool,
+        hung_up: H,
+        mut serve: S,
+    ) -> Result<Reply, ApplicationError>
+    where
+        S: FnMut(&mut Db, &[u8], u64) -> Result<Option<Reply>, ApplicationError>,
+        H: Fn() -> bool,
+    {
+        // A deadline too far off to represent is as good as none.
+        let deadline = timeout.and_then(|timeout| Instant::now().checked_add(timeout));
+        let mut state = self.lock();
+        let ticket = state.next_ticket;
+        state.next_ticket += 1;
+        keys.iter().for_each(|key| {
+            let queue = state.waiting.entry(key.clone()).or_default();
+            queue.push_back(ticket)
+        });
+        let served = loop {
+            match serve_in_turn(
+                &mut state,
+                ticket,
+                in_line,
+                keys,
+                self.clock.now(),
+                &mut serve,
+            ) {
+                Ok(None) => {}
+                served => break served,
+            }
+            let now = Instant::now();
+            let wait = match deadline {
+                Some(deadline) if deadline <= now => break Ok(None),
+                Some(deadline) => (deadline - now).min(BLOCKED_POLL_INTERVAL),
+                None => BLOCKED_POLL_INTERVAL,
+            };
+            if hung_up() {
+                break Ok(None);
+            }
+            state = self
+                .changed
+                .wait_timeout(state, wait)
+                .unwrap_or_else(PoisonError::into_inner)
+                .0;
+        };
+        keys.iter().for_each(|key| {
+            if let Some(queue) = state.waiting.get_mut(key) {
+                queue.retain(|waiting| *waiting != ticket);
+                if queue.is_empty() {
+                    state.waiting.remove(key);
+                }
+            }
+        });
+        // Whoever was behind us may be able to go now.
+        self.changed.notify_all();
+        served.map(|reply| reply.unwrap_or(Reply::NullArray))
+    }
+
+    /// Deletes expired keys nobody has touched, the way Redis does: it
+    /// checks keys with deadlines at random and carries on for as long as
+    /// more than a quarter of them turn out to be expired, within a time
+    /// budget. Other clients get the lock between rounds.
+    pub fn expire_actively(&self) {
+        let started = Instant::now();
+        loop {
+            let now = self.clock.now();
+            let (checked, expired) = self.lock().db.expire_sample(now, ACTIVE_EXPIRE_SAMPLES);
+            if expired * 4 <= checked || started.elapsed() >= ACTIVE_EXPIRE_BUDGET {
+                return;
+            }
+        }
+    }
+}
+
+impl Default for Keyspace {
+    fn default() -> Self {
+        Keyspace::new()
+    }
+}
+
+/// Serves the client holding `ticket` from the first key it is next in line
+/// for, or any key when it isn't `in_line`, that has something for it.
+fn serve_in_turn<S>(
+    state: &mut State,
+    ticket: u64,
+    in_line: bool,
+    keys: &[Vec<u8>],
+    now: u64,
+    serve: &mut S,
+) -> Result<Option<Reply>, ApplicationError>
+where
+    S: FnMut(&mut Db, &[u8], u64) -> Result<Option<Reply>, ApplicationError>,
+{
+    for key in keys {
+        let first_in_line = state.waiting.get(key).and_then(VecDeque::front) == Some(&ticket);
+        if first_in_line || !in_line {
+            if let Some(reply) = serve(&mut state.db, key, now)? {
+                return Ok(Some(reply));
+            }
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Keyspace, Reply};
+    use std::time::Duration;
+
+    #[test]
+    fn distant_deadlines_are_no_deadline() {
+        let keyspace = Keyspace::new();
+        let served = keyspace.block(
+            &[b"queue".to_vec()],
+            Some(Duration::MAX),
+            true,
+            || false,
+            |_, _, _| Ok(Some(Reply::Integer(1))),
+        );
+        assert_eq!(served.unwrap(), Reply::Integer(1));
+    }
+
+    #[test]
+    fn timing_out_replies_with_a_null_array() {
+        let keyspace = Keyspace::new();
+        let served = keyspace.block(
+            &[b"queue".to_vec()],
+            Some(Duration::from_millis(10)),
+            true,
+            || false,
+            |_, _, _| Ok(None),
+        );
+        assert_eq!(served.unwrap(), Reply::NullArray);
+    }
+}
@@ -0,0 +1,37 @@
+use super::super::domain::{Data, Primitive};
+use super::super::errors::ApplicationError;
+use super::utilities::{get_list, index};
+use std::collections::HashMap;
+
+pub fn command(
+    store: &HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    i: i64,
+) -> Result<Option<Primitive>, ApplicationError> {
+    Ok(get_list(store, key)?
+        .and_then(|list| index(i, list.len()).and_then(|i| list.get(i)))
+        .cloned())
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::domain::Primitive;
+    use super::super::{push, range, End};
+    use super::{command, Data, HashMap};
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn agrees_with_range(
+            key in vec(any::<u8>(), 0..32),
+            values in vec(any::<Primitive>(), 1..50),
+            i in -60i64..60
+        ) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            push::command(&mut store, &key, values, End::Right)?;
+            let expected = range::command(&store, &key, i, i)?;
+            assert_eq!(command(&store, &key, i)?.into_iter().collect::<Vec<Primitive>>(), expected)
+        }
+    }
+}
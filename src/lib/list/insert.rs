@@ -0,0 +1,80 @@
+use super::super::domain::{Data, Primitive};
+use super::super::errors::ApplicationError;
+use super::utilities::get_list_mut;
+use std::collections::HashMap;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Position {
+    Before,
+    After,
+}
+
+/// Inserts `value` next to the first occurrence of `pivot`, returning the
+/// new length, -1 when the pivot isn't there and 0 when the list isn't.
+pub fn command(
+    store: &mut HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    position: Position,
+    pivot: &Primitive,
+    value: Primitive,
+) -> Result<i64, ApplicationError> {
+    let list = match get_list_mut(store, key)? {
+        Some(list) => list,
+        None => return Ok(0),
+    };
+    match list.iter().position(|element| element == pivot) {
+        Some(i) => {
+            let i = match position {
+                Position::Before => i,
+                Position::After => i + 1,
+            };
+            list.insert(i, value);
+            Ok(list.len() as i64)
+        }
+        None => Ok(-1),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::domain::Primitive;
+    use super::super::{push, range, End};
+    use super::{command, Data, HashMap, Position};
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn inserts_around_the_first_pivot(
+            key in vec(any::<u8>(), 0..32),
+            values in vec((0i64..8).prop_map(Primitive::from), 1..50),
+            pivot in 0i64..8,
+            before in any::<bool>()
+        ) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            let pivot = Primitive::from(pivot);
+            let value = Primitive::from("inserted");
+            let position = if before { Position::Before } else { Position::After };
+            let mut expected = values.clone();
+            let len = values.len() as i64;
+            push::command(&mut store, &key, values, End::Right)?;
+            match expected.iter().position(|v| *v == pivot) {
+                Some(i) => {
+                    expected.insert(if before { i } else { i + 1 }, value.clone());
+                    assert_eq!(command(&mut store, &key, position, &pivot, value)?, len + 1)
+                }
+                None => assert_eq!(command(&mut store, &key, position, &pivot, value)?, -1),
+            }
+            assert_eq!(range::command(&store, &key, 0, -1)?, expected)
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn does_nothing_without_a_list(key in vec(any::<u8>(), 0..32), pivot in any::<Primitive>()) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            assert_eq!(command(&mut store, &key, Position::Before, &pivot, pivot.clone())?, 0);
+            assert!(store.is_empty())
+        }
+    }
+}
@@ -0,0 +1,29 @@
+use super::super::domain::Data;
+use super::super::errors::ApplicationError;
+use super::utilities::get_list;
+use std::collections::HashMap;
+
+pub fn command(store: &HashMap<Vec<u8>, Data>, key: &[u8]) -> Result<usize, ApplicationError> {
+    Ok(get_list(store, key)?.map_or(0, |list| list.len()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::domain::Primitive;
+    use super::super::{push, End};
+    use super::{command, Data, HashMap};
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn returns_list_length(key in vec(any::<u8>(), 0..32), values in vec(any::<Primitive>(), 0..100)) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            let len = values.len();
+            if len > 0 {
+                push::command(&mut store, &key, values, End::Right)?;
+            }
+            assert_eq!(command(&store, &key)?, len)
+        }
+    }
+}
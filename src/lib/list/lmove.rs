@@ -0,0 +1,91 @@
+use super::super::domain::{Data, Primitive};
+use super::super::errors::ApplicationError;
+use super::utilities::get_list;
+use super::{pop, push, End};
+use std::collections::HashMap;
+
+/// Pops a value off one end of `source` and pushes it onto one end of
+/// `destination`, returning it, or `None` when there is nothing to move.
+pub fn command(
+    store: &mut HashMap<Vec<u8>, Data>,
+    source: &[u8],
+    destination: &[u8],
+    from: End,
+    to: End,
+) -> Result<Option<Primitive>, ApplicationError> {
+    // Nothing may be popped when it can't be pushed.
+    get_list(store, destination)?;
+    let value = match pop::command(store, source, 1, from)? {
+        Some(popped) => popped.into_iter().next(),
+        None => None,
+    };
+    if let Some(value) = &value {
+        push::command(store, destination, vec![value.clone()], to)?;
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::domain::Primitive;
+    use super::super::{len, push, range};
+    use super::{command, Data, End, HashMap};
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    fn valid_keys() -> impl Strategy<Value = Vec<u8>> {
+        vec(any::<u8>(), 1..16)
+    }
+
+    proptest! {
+        #[test]
+        fn moves_one_value_across(
+            a_key in valid_keys(),
+            b_key in valid_keys(),
+            a in vec(any::<Primitive>(), 1..50),
+            b in vec(any::<Primitive>(), 0..50)
+        ) {
+            prop_assume!(a_key != b_key);
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            let (a_len, b_len) = (a.len(), b.len());
+            let first = a[0].clone();
+            push::command(&mut store, &a_key, a, End::Right)?;
+            if b_len > 0 {
+                push::command(&mut store, &b_key, b, End::Right)?;
+            }
+            assert_eq!(command(&mut store, &a_key, &b_key, End::Left, End::Right)?, Some(first.clone()));
+            assert_eq!(len::command(&store, &a_key)?, a_len - 1);
+            assert_eq!(len::command(&store, &b_key)?, b_len + 1);
+            assert_eq!(range::command(&store, &b_key, -1, -1)?, vec![first])
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn moving_within_a_list_rotates_it(key in valid_keys(), values in vec(any::<Primitive>(), 1..50)) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            push::command(&mut store, &key, values.clone(), End::Right)?;
+            command(&mut store, &key, &key, End::Right, End::Left)?;
+            let mut rotated = values;
+            rotated.rotate_right(1);
+            assert_eq!(range::command(&store, &key, 0, -1)?, rotated)
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn leaves_the_source_alone_when_the_destination_is_not_a_list(
+            a_key in valid_keys(),
+            b_key in valid_keys(),
+            a in vec(any::<Primitive>(), 1..50)
+        ) {
+            prop_assume!(a_key != b_key);
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            let a_len = a.len();
+            push::command(&mut store, &a_key, a, End::Right)?;
+            store.insert(b_key.clone(), Data::from(1));
+            command(&mut store, &a_key, &b_key, End::Left, End::Left).unwrap_err();
+            assert_eq!(len::command(&store, &a_key)?, a_len)
+        }
+    }
+}
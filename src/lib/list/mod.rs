@@ -0,0 +1,19 @@
+pub mod index;
+pub mod insert;
+pub mod len;
+pub mod lmove;
+pub mod pop;
+pub mod pos;
+pub mod push;
+pub mod range;
+pub mod rem;
+pub mod set;
+pub mod trim;
+pub(crate) mod utilities;
+
+/// Which end of a list a command works on.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum End {
+    Left,
+    Right,
+}
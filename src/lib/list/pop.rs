@@ -0,0 +1,65 @@
+use super::super::domain::{Data, Primitive};
+use super::super::errors::ApplicationError;
+use super::utilities::{get_list_mut, remove_if_empty};
+use super::End;
+use std::collections::HashMap;
+
+/// Pops up to `count` values off one end of the list, or returns `None` if
+/// there is no list at the key.
+pub fn command(
+    store: &mut HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    count: usize,
+    end: End,
+) -> Result<Option<Vec<Primitive>>, ApplicationError> {
+    let popped = match get_list_mut(store, key)? {
+        Some(list) => (0..count.min(list.len()))
+            .filter_map(|_| match end {
+                End::Left => list.pop_front(),
+                End::Right => list.pop_back(),
+            })
+            .collect(),
+        None => return Ok(None),
+    };
+    remove_if_empty(store, key);
+    Ok(Some(popped))
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::domain::Primitive;
+    use super::super::push;
+    use super::{command, Data, End, HashMap};
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn pops_what_was_pushed(
+            key in vec(any::<u8>(), 0..32),
+            values in vec(any::<Primitive>(), 1..50)
+        ) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            push::command(&mut store, &key, values.clone(), End::Left)?;
+            let popped = command(&mut store, &key, values.len(), End::Right)?;
+            assert_eq!(popped, Some(values))
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn popping_everything_deletes_the_key(
+            key in vec(any::<u8>(), 0..32),
+            values in vec(any::<Primitive>(), 1..50),
+            extra in 0usize..10
+        ) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            let len = values.len();
+            push::command(&mut store, &key, values, End::Right)?;
+            let popped = command(&mut store, &key, len + extra, End::Left)?;
+            assert_eq!(popped.map(|p| p.len()), Some(len));
+            assert_eq!(store.get(&key), None);
+            assert_eq!(command(&mut store, &key, 1, End::Left)?, None)
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use super::super::domain::{Data, Primitive};
+use super::super::errors::ApplicationError;
+use super::utilities::get_list;
+use std::collections::HashMap;
+
+/// The options LPOS takes. `rank` picks which match to start from, counting
+/// back from the tail when negative. `count` is how many matches to return,
+/// with 0 meaning all of them; without it LPOS replies with a bare index.
+/// `maxlen` caps how many elements are compared, with 0 meaning no cap.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Options {
+    pub rank: i64,
+    pub count: Option<usize>,
+    pub maxlen: usize,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            rank: 1,
+            count: None,
+            maxlen: 0,
+        }
+    }
+}
+
+/// Returns the indexes of the matching elements, counted from the head
+/// whichever way the list was scanned.
+pub fn command(
+    store: &HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    value: &Primitive,
+    options: Options,
+) -> Result<Vec<usize>, ApplicationError> {
+    let list = match get_list(store, key)? {
+        Some(list) => list,
+        None => return Ok(Vec::new()),
+    };
+    let maxlen = if options.maxlen == 0 {
+        list.len()
+    } else {
+        options.maxlen
+    };
+    let count = match options.count {
+        Some(0) => usize::MAX,
+        Some(count) => count,
+        None => 1,
+    };
+    let skip = options.rank.unsigned_abs() as usize - 1;
+    let indexes: Box<dyn Iterator<Item = usize>> = if options.rank > 0 {
+        Box::new(0..list.len())
+    } else {
+        Box::new((0..list.len()).rev())
+    };
+    Ok(indexes
+        .take(maxlen)
+        .filter(|i| list[*i] == *value)
+        .skip(skip)
+        .take(count)
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::domain::Primitive;
+    use super::super::{index, push, End};
+    use super::{command, Data, HashMap, Options};
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    fn small_values() -> impl Strategy<Value = Vec<Primitive>> {
+        vec((0i64..4).prop_map(Primitive::from), 1..50)
+    }
+
+    proptest! {
+        #[test]
+        fn finds_every_match_in_order(key in vec(any::<u8>(), 0..32), values in small_values(), value in 0i64..4) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            let value = Primitive::from(value);
+            let expected: Vec<usize> = (0..values.len()).filter(|i| values[*i] == value).collect();
+            push::command(&mut store, &key, values, End::Right)?;
+            let options = Options { count: Some(0), ..Options::default() };
+            assert_eq!(command(&store, &key, &value, options)?, expected.clone());
+            let options = Options { rank: -1, count: Some(0), ..Options::default() };
+            assert_eq!(command(&store, &key, &value, options)?, expected.into_iter().rev().collect::<Vec<usize>>())
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn ranked_matches_hold_the_value(
+            key in vec(any::<u8>(), 0..32),
+            values in small_values(),
+            value in 0i64..4,
+            rank in prop_oneof![-10i64..0, 1i64..10],
+            maxlen in 0usize..60
+        ) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            let value = Primitive::from(value);
+            let len = values.len();
+            push::command(&mut store, &key, values, End::Right)?;
+            let options = Options { rank, count: Some(0), maxlen };
+            command(&store, &key, &value, options)?.into_iter().try_for_each(|i| {
+                if maxlen > 0 && rank > 0 {
+                    prop_assert!(i < maxlen);
+                } else if maxlen > 0 {
+                    prop_assert!(i >= len.saturating_sub(maxlen));
+                }
+                prop_assert_eq!(index::command(&store, &key, i as i64)?, Some(value.clone()));
+                Ok(())
+            })?
+        }
+    }
+}
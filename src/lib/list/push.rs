@@ -0,0 +1,80 @@
+use super::super::domain::{Data, Escaped, Primitive};
+use super::super::errors::ApplicationError;
+use super::End;
+use std::collections::{HashMap, VecDeque};
+
+fn push_values(list: &mut VecDeque<Primitive>, values: Vec<Primitive>, end: End) -> usize {
+    values.into_iter().for_each(|val| match end {
+        End::Left => list.push_front(val),
+        End::Right => list.push_back(val),
+    });
+    list.len()
+}
+
+pub fn command(
+    store: &mut HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    values: Vec<Primitive>,
+    end: End,
+) -> Result<usize, ApplicationError> {
+    match store.get_mut(key) {
+        Some(Data::List(list)) => Ok(push_values(list, values, end)),
+        None => {
+            let mut list = VecDeque::with_capacity(values.len());
+            let len = push_values(&mut list, values, end);
+            store.insert(key.to_vec(), Data::List(list));
+            Ok(len)
+        }
+        Some(_) => Err(format!("Value at {} is not a list", Escaped(key)).into()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::domain::Primitive;
+    use super::super::range;
+    use super::{command, Data, End, HashMap};
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn returns_length_after_push(
+            key in vec(any::<u8>(), 0..32),
+            first in vec(any::<Primitive>(), 1..50),
+            second in vec(any::<Primitive>(), 1..50)
+        ) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            let (first_len, second_len) = (first.len(), second.len());
+            assert_eq!(command(&mut store, &key, first, End::Right)?, first_len);
+            assert_eq!(command(&mut store, &key, second, End::Left)?, first_len + second_len)
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn rpush_keeps_order_and_lpush_reverses_it(
+            key in vec(any::<u8>(), 0..32),
+            values in vec(any::<Primitive>(), 1..50)
+        ) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            command(&mut store, &key, values.clone(), End::Right)?;
+            assert_eq!(range::command(&store, &key, 0, -1)?, values.clone());
+            store.clear();
+            command(&mut store, &key, values.clone(), End::Left)?;
+            assert_eq!(range::command(&store, &key, 0, -1)?, values.into_iter().rev().collect::<Vec<Primitive>>())
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn refuses_to_push_onto_other_types(
+            key in vec(any::<u8>(), 0..32),
+            values in vec(any::<Primitive>(), 1..50)
+        ) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            store.insert(key.clone(), Data::from(1));
+            command(&mut store, &key, values, End::Left).unwrap_err();
+        }
+    }
+}
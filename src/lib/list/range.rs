@@ -0,0 +1,55 @@
+use super::super::domain::{Data, Primitive};
+use super::super::errors::ApplicationError;
+use super::utilities::{get_list, range};
+use std::collections::HashMap;
+
+pub fn command(
+    store: &HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    start: i64,
+    stop: i64,
+) -> Result<Vec<Primitive>, ApplicationError> {
+    let list = match get_list(store, key)? {
+        Some(list) => list,
+        None => return Ok(Vec::new()),
+    };
+    Ok(match range(start, stop, list.len()) {
+        Some((start, stop)) => list.range(start..=stop).cloned().collect(),
+        None => Vec::new(),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::domain::Primitive;
+    use super::super::{push, End};
+    use super::{command, Data, HashMap};
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn ranges_match_slices(
+            key in vec(any::<u8>(), 0..32),
+            values in vec(any::<Primitive>(), 1..50),
+            start in -60i64..60,
+            stop in -60i64..60
+        ) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            push::command(&mut store, &key, values.clone(), End::Right)?;
+            let len = values.len() as i64;
+            let from = if start < 0 { (len + start).max(0) } else { start.min(len) };
+            let to = if stop < 0 { len + stop + 1 } else { (stop + 1).min(len) };
+            let expected = if from < to { values[from as usize..to as usize].to_vec() } else { vec![] };
+            assert_eq!(command(&store, &key, start, stop)?, expected)
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn missing_lists_are_empty(key in vec(any::<u8>(), 0..32), start in -5i64..5, stop in -5i64..5) {
+            let store: HashMap<Vec<u8>, Data> = HashMap::new();
+            assert_eq!(command(&store, &key, start, stop)?, vec![])
+        }
+    }
+}
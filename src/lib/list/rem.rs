@@ -0,0 +1,90 @@
+use super::super::domain::{Data, Primitive};
+use super::super::errors::ApplicationError;
+use super::utilities::{get_list_mut, remove_if_empty};
+use std::collections::HashMap;
+
+/// Removes occurrences of `value`: the first `count` from the head when it
+/// is positive, the last `-count` from the tail when it is negative and
+/// every one of them when it is zero.
+pub fn command(
+    store: &mut HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    count: i64,
+    value: &Primitive,
+) -> Result<usize, ApplicationError> {
+    let list = match get_list_mut(store, key)? {
+        Some(list) => list,
+        None => return Ok(0),
+    };
+    let limit = if count == 0 {
+        usize::MAX
+    } else {
+        count.unsigned_abs() as usize
+    };
+    let mut matches: Vec<usize> = list
+        .iter()
+        .enumerate()
+        .filter(|(_, element)| *element == value)
+        .map(|(i, _)| i)
+        .collect();
+    if count < 0 {
+        matches.reverse();
+    }
+    matches.truncate(limit);
+    matches.sort_unstable();
+    matches.iter().rev().for_each(|i| {
+        list.remove(*i);
+    });
+    remove_if_empty(store, key);
+    Ok(matches.len())
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::domain::Primitive;
+    use super::super::{push, range, End};
+    use super::{command, Data, HashMap};
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    fn small_values() -> impl Strategy<Value = Vec<Primitive>> {
+        vec((0i64..4).prop_map(Primitive::from), 1..50)
+    }
+
+    proptest! {
+        #[test]
+        fn removes_every_occurrence(key in vec(any::<u8>(), 0..32), values in small_values(), value in 0i64..4) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            let value = Primitive::from(value);
+            let expected: Vec<Primitive> = values.iter().filter(|v| **v != value).cloned().collect();
+            push::command(&mut store, &key, values.clone(), End::Right)?;
+            assert_eq!(command(&mut store, &key, 0, &value)?, values.len() - expected.len());
+            assert_eq!(range::command(&store, &key, 0, -1)?, expected)
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn removes_from_the_requested_end(
+            key in vec(any::<u8>(), 0..32),
+            values in small_values(),
+            value in 0i64..4,
+            count in 1i64..10
+        ) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            let value = Primitive::from(value);
+            let mut remaining = count;
+            let mut expected: Vec<Primitive> = values.iter().rev().filter(|v| {
+                let matched = **v == value && remaining > 0;
+                if matched {
+                    remaining -= 1;
+                }
+                !matched
+            }).cloned().collect();
+            expected.reverse();
+            push::command(&mut store, &key, values, End::Right)?;
+            command(&mut store, &key, -count, &value)?;
+            assert_eq!(range::command(&store, &key, 0, -1)?, expected)
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use super::super::domain::{Data, Escaped, Primitive};
+use super::super::errors::{ApplicationError, Fallible};
+use super::utilities::{get_list_mut, index};
+use std::collections::HashMap;
+
+pub fn command(
+    store: &mut HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    i: i64,
+    value: Primitive,
+) -> Result<(), ApplicationError> {
+    let list = get_list_mut(store, key)?.fail_to(&format!("No list at {}", Escaped(key)))?;
+    let i = index(i, list.len()).fail_to("Index out of range")?;
+    list[i] = value;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::domain::Primitive;
+    use super::super::{index, push, End};
+    use super::{command, Data, HashMap};
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn sets_the_element_at_an_index(
+            key in vec(any::<u8>(), 0..32),
+            values in vec(any::<Primitive>(), 1..50),
+            value in any::<Primitive>(),
+            i in -60i64..60
+        ) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            let len = values.len() as i64;
+            push::command(&mut store, &key, values, End::Right)?;
+            if -len <= i && i < len {
+                command(&mut store, &key, i, value.clone())?;
+                assert_eq!(index::command(&store, &key, i)?, Some(value))
+            } else {
+                command(&mut store, &key, i, value).unwrap_err();
+            }
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn needs_a_list(key in vec(any::<u8>(), 0..32), value in any::<Primitive>()) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            command(&mut store, &key, 0, value).unwrap_err();
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use super::super::domain::Data;
+use super::super::errors::ApplicationError;
+use super::utilities::{get_list_mut, range, remove_if_empty};
+use std::collections::HashMap;
+
+pub fn command(
+    store: &mut HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    start: i64,
+    stop: i64,
+) -> Result<(), ApplicationError> {
+    let list = match get_list_mut(store, key)? {
+        Some(list) => list,
+        None => return Ok(()),
+    };
+    match range(start, stop, list.len()) {
+        Some((start, stop)) => {
+            list.truncate(stop + 1);
+            list.drain(..start);
+        }
+        None => list.clear(),
+    }
+    remove_if_empty(store, key);
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::domain::Primitive;
+    use super::super::{push, range, End};
+    use super::{command, Data, HashMap};
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn keeps_what_range_returns(
+            key in vec(any::<u8>(), 0..32),
+            values in vec(any::<Primitive>(), 1..50),
+            start in -60i64..60,
+            stop in -60i64..60
+        ) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            push::command(&mut store, &key, values, End::Right)?;
+            let expected = range::command(&store, &key, start, stop)?;
+            command(&mut store, &key, start, stop)?;
+            assert_eq!(range::command(&store, &key, 0, -1)?, expected.clone());
+            assert_eq!(store.contains_key(&key), !expected.is_empty())
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use super::super::domain::{Data, Escaped, Primitive};
+use super::super::errors::ApplicationError;
+use std::collections::{HashMap, VecDeque};
+
+pub fn get_list<'a>(
+    store: &'a HashMap<Vec<u8>, Data>,
+    key: &[u8],
+) -> Result<Option<&'a VecDeque<Primitive>>, ApplicationError> {
+    match store.get(key) {
+        Some(Data::List(list)) => Ok(Some(list)),
+        Some(_) => Err(format!("Value at {} is not a list", Escaped(key)).into()),
+        None => Ok(None),
+    }
+}
+
+pub fn get_list_mut<'a>(
+    store: &'a mut HashMap<Vec<u8>, Data>,
+    key: &[u8],
+) -> Result<Option<&'a mut VecDeque<Primitive>>, ApplicationError> {
+    match store.get_mut(key) {
+        Some(Data::List(list)) => Ok(Some(list)),
+        Some(_) => Err(format!("Value at {} is not a list", Escaped(key)).into()),
+        None => Ok(None),
+    }
+}
+
+/// Redis never keeps an empty list around, so a command that empties one
+/// deletes its key.
+pub fn remove_if_empty(store: &mut HashMap<Vec<u8>, Data>, key: &[u8]) {
+    if let Some(Data::List(list)) = store.get(key) {
+        if list.is_empty() {
+            store.remove(key);
+        }
+    }
+}
+
+/// Resolves an index that may count back from the end of the list.
+pub fn index(index: i64, len: usize) -> Option<usize> {
+    let index = if index < 0 { len as i64 + index } else { index };
+    if index >= 0 && (index as usize) < len {
+        Some(index as usize)
+    } else {
+        None
+    }
+}
+
+/// Resolves an inclusive range whose ends may count back from the end of
+/// the list, clamping it to the list. Returns `None` when it is empty.
+pub fn range(start: i64, stop: i64, len: usize) -> Option<(usize, usize)> {
+    let len = len as i64;
+    let start = if start < 0 {
+        (len + start).max(0)
+    } else {
+        start
+    };
+    let stop = if stop < 0 {
+        len + stop
+    } else {
+        stop.min(len - 1)
+    };
+    if start > stop || start >= len {
+        None
+    } else {
+        Some((start as usize, stop as usize))
+    }
+}
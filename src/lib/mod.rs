@@ -1,5 +1,20 @@
+pub mod clock;
+pub mod db;
 pub mod domain;
 pub mod errors;
+pub mod execute;
+pub mod expire;
+pub mod geo;
+pub mod hash;
+pub mod hyperloglog;
 pub mod incr;
+pub mod keyspace;
+pub mod list;
 pub mod parse;
+pub(crate) mod random;
+pub mod resp;
+pub mod server;
 pub mod set;
+pub mod stream;
+pub mod string;
+pub mod zset;
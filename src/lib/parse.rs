@@ -1,108 +1,1722 @@
 use super::domain::{Command, Primitive};
 use super::errors::{ApplicationError, Fallible};
-use std::collections::LinkedList;
+use super::expire::{self, Lifetime, When};
+use super::geo::{self, search};
+use super::incr::parse_float;
+use super::list::insert::Position;
+use super::list::{pos, End};
+use super::resp::Frame;
+use super::stream::add::{self as xadd, NewId};
+use super::stream::read::After;
+use super::stream::readgroup::{self, Start};
+use super::stream::trim::{Threshold, Trim};
+use super::stream::StreamId;
+use super::stream::{autoclaim, claim, pending};
+use super::string;
+use super::string::bits::{self, Unit};
+use super::string::{bitfield, bitop};
+use super::zset::add;
+use super::zset::range::{By, Range};
+use super::zset::{Aggregate, LexBound, ScoreBound};
+use std::iter::{Copied, Peekable};
+use std::slice::Iter;
+use std::time::Duration;
 
-pub fn parse_cmd(cmd: String) -> Result<Command, ApplicationError> {
-    let mut args = cmd.split_whitespace();
-    match args.next().fail_to("No command given")? {
-        "echo" => args
-            .next()
-            .fail_to("Nothing to echo".into())
-            .map(|echoed| Command::Echo(echoed.into())),
-        "set" => Ok(Command::Set(
-            args.next().fail_to("No key provided")?.into(),
+type Bytes<'a> = Peekable<Copied<Iter<'a, u8>>>;
+
+/// Turns a request frame, an array of bulk strings, into a command.
+pub fn parse_cmd(frame: Frame) -> Result<Command, ApplicationError> {
+    let args = match frame {
+        Frame::Array(items) => items
+            .into_iter()
+            .map(|item| match item {
+                Frame::Bulk(bytes) => Ok(bytes),
+                _ => Err(String::from("Arguments must be bulk strings").into()),
+            })
+            .collect::<Result<Vec<Vec<u8>>, ApplicationError>>()?,
+        _ => return Err(String::from("Requests must be arrays of bulk strings").into()),
+    };
+    parse_args(args.into_iter())
+}
+
+/// Splits an inline command, as typed at the CLI or sent by telnet, into a
+/// request frame. Arguments are separated by whitespace and may be quoted
+/// the way Redis allows: double quotes support escapes such as `\n` and
+/// `\x41`, while single quotes only allow `\'`.
+pub fn tokenize(line: &[u8]) -> Result<Frame, ApplicationError> {
+    let mut args = Vec::new();
+    let mut bytes = line.iter().copied().peekable();
+    loop {
+        while bytes.peek().is_some_and(|b| is_space(*b)) {
+            bytes.next();
+        }
+        if bytes.peek().is_none() {
+            return Ok(Frame::Array(args));
+        }
+        let mut arg = Vec::new();
+        while let Some(byte) = bytes.next() {
+            match byte {
+                b'"' => {
+                    read_double_quoted(&mut bytes, &mut arg)?;
+                    end_quoted(&mut bytes)?;
+                    break;
+                }
+                b'\'' => {
+                    read_single_quoted(&mut bytes, &mut arg)?;
+                    end_quoted(&mut bytes)?;
+                    break;
+                }
+                byte if is_space(byte) => break,
+                byte => arg.push(byte),
+            }
+        }
+        args.push(Frame::Bulk(arg));
+    }
+}
+
+fn is_space(byte: u8) -> bool {
+    byte.is_ascii_whitespace() || byte == 0x0b
+}
+
+fn read_double_quoted(bytes: &mut Bytes, arg: &mut Vec<u8>) -> Result<(), ApplicationError> {
+    loop {
+        match bytes.next().fail_to("Unbalanced quotes in request")? {
+            b'"' => return Ok(()),
+            b'\\' => match bytes.next().fail_to("Unbalanced quotes in request")? {
+                b'x' => match hex_escape(bytes) {
+                    Some(byte) => arg.push(byte),
+                    None => arg.push(b'x'),
+                },
+                b'n' => arg.push(b'\n'),
+                b'r' => arg.push(b'\r'),
+                b't' => arg.push(b'\t'),
+                b'b' => arg.push(0x08),
+                b'a' => arg.push(0x07),
+                other => arg.push(other),
+            },
+            other => arg.push(other),
+        }
+    }
+}
+
+/// Reads the two hex digits of a `\x` escape, leaving them be if they aren't.
+fn hex_escape(bytes: &mut Bytes) -> Option<u8> {
+    let mut ahead = bytes.clone();
+    let digits = [ahead.next()?, ahead.next()?];
+    let byte = u8::from_str_radix(std::str::from_utf8(&digits).ok()?, 16).ok()?;
+    if digits.iter().all(u8::is_ascii_hexdigit) {
+        *bytes = ahead;
+        Some(byte)
+    } else {
+        None
+    }
+}
+
+fn read_single_quoted(bytes: &mut Bytes, arg: &mut Vec<u8>) -> Result<(), ApplicationError> {
+    loop {
+        match bytes.next().fail_to("Unbalanced quotes in request")? {
+            b'\\' if bytes.peek() == Some(&b'\'') => {
+                bytes.next();
+                arg.push(b'\'');
+            }
+            b'\'' => return Ok(()),
+            other => arg.push(other),
+        }
+    }
+}
+
+/// A closing quote has to end its argument.
+fn end_quoted(bytes: &mut Bytes) -> Result<(), ApplicationError> {
+    match bytes.next() {
+        Some(byte) if !is_space(byte) => {
+            Err(String::from("Closing quote must be followed by a space").into())
+        }
+        _ => Ok(()),
+    }
+}
+
+fn parse_args<I>(mut args: I) -> Result<Command, ApplicationError>
+where
+    I: Iterator<Item = Vec<u8>>,
+{
+    match lowercase(&args.next().fail_to("No command given")?).as_str() {
+        "hello" => {
+            let version = args
+                .next()
+                .map(|version| parse_integer(&version, "Protocol version"))
+                .transpose()?;
+            while let Some(option) = args.next() {
+                match lowercase(&option).as_str() {
+                    "auth" => {
+                        args.next().fail_to("No username provided")?;
+                        args.next().fail_to("No password provided")?;
+                    }
+                    "setname" => {
+                        args.next().fail_to("No client name provided")?;
+                    }
+                    unknown => return Err(format!("Unknown HELLO option: {}", unknown).into()),
+                }
+            }
+            Ok(Command::Hello(version))
+        }
+        "echo" => args.next().fail_to("Nothing to echo").map(Command::Echo),
+        "set" => {
+            let key = args.next().fail_to("No key provided")?;
+            let value = args
+                .next()
+                .fail_to("No value provided")
+                .map(Primitive::from)?;
+            let mut options = string::set::Options::default();
+            let mut lifetime = None;
+            while let Some(option) = args.next() {
+                let option = lowercase(&option);
+                match option.as_str() {
+                    "nx" => options.nx = true,
+                    "xx" => options.xx = true,
+                    "get" => options.get = true,
+                    "keepttl" => set_once(&mut lifetime, Lifetime::Keep)?,
+                    "ex" | "px" | "exat" | "pxat" => {
+                        let when = parse_expiry(&option, &mut args)?;
+                        set_once(&mut lifetime, Lifetime::Expire(when))?
+                    }
+                    unknown => return Err(format!("Unknown SET option: {}", unknown).into()),
+                };
+            }
+            if options.nx && options.xx {
+                return Err(String::from("XX and NX options can't be combined").into());
+            }
+            options.lifetime = lifetime.unwrap_or(Lifetime::Persist);
+            Ok(Command::Set(key, value, options))
+        }
+        "setnx" => Ok(Command::Setnx(
+            args.next().fail_to("No key provided")?,
             args.next()
                 .fail_to("No value provided")
-                .map(parse_primitive)??
-                .into(),
+                .map(Primitive::from)?,
         )),
-        "get" => Ok(Command::Get(args.next().fail_to("No key provided")?.into())),
-        "incr" => Ok(Command::Incr(
-            args.next().fail_to("No key provided")?.into(),
+        "getset" => {
+            let options = string::set::Options {
+                get: true,
+                ..Default::default()
+            };
+            Ok(Command::Set(
+                args.next().fail_to("No key provided")?,
+                args.next()
+                    .fail_to("No value provided")
+                    .map(Primitive::from)?,
+                options,
+            ))
+        }
+        "mset" => Ok(Command::Mset(keys_and_values(args)?)),
+        "msetnx" => Ok(Command::Msetnx(keys_and_values(args)?)),
+        "mget" => {
+            let keys: Vec<Vec<u8>> = args.collect();
+            if keys.is_empty() {
+                return Err(String::from("No key provided").into());
+            }
+            Ok(Command::Mget(keys))
+        }
+        "append" => Ok(Command::Append(
+            args.next().fail_to("No key provided")?,
+            args.next().fail_to("No value provided")?,
         )),
-        "sadd" => {
+        "strlen" => Ok(Command::Strlen(args.next().fail_to("No key provided")?)),
+        "getrange" => Ok(Command::Getrange(
+            args.next().fail_to("No key provided")?,
+            parse_integer(&args.next().fail_to("No start provided")?, "Start")?,
+            parse_integer(&args.next().fail_to("No end provided")?, "End")?,
+        )),
+        "setrange" => Ok(Command::Setrange(
+            args.next().fail_to("No key provided")?,
+            parse_count(&args.next().fail_to("No offset provided")?, "Offset")?,
+            args.next().fail_to("No value provided")?,
+        )),
+        "lcs" => {
+            let key1 = args.next().fail_to("No key provided")?;
+            let key2 = args.next().fail_to("No second key provided")?;
+            let mut options = string::lcs::Options::default();
+            while let Some(option) = args.next() {
+                match lowercase(&option).as_str() {
+                    "len" => options.len = true,
+                    "idx" => options.idx = true,
+                    "minmatchlen" => {
+                        let len = args.next().fail_to("No MINMATCHLEN provided")?;
+                        options.min_match_len = parse_count(&len, "MINMATCHLEN")?;
+                    }
+                    "withmatchlen" => options.with_match_len = true,
+                    unknown => return Err(format!("Unknown LCS option: {}", unknown).into()),
+                }
+            }
+            if options.len && options.idx {
+                return Err(String::from(
+                    "LEN and IDX can't be combined; IDX gives the length too",
+                )
+                .into());
+            }
+            Ok(Command::Lcs(key1, key2, options))
+        }
+        "bitfield" => parse_bitfield(args, false),
+        "bitfield_ro" => parse_bitfield(args, true),
+        "setbit" => Ok(Command::Setbit(
+            args.next().fail_to("No key provided")?,
+            parse_bit_offset(&args.next().fail_to("No offset provided")?)?,
+            parse_bit(&args.next().fail_to("No bit provided")?)?,
+        )),
+        "getbit" => Ok(Command::Getbit(
+            args.next().fail_to("No key provided")?,
+            parse_bit_offset(&args.next().fail_to("No offset provided")?)?,
+        )),
+        "bitcount" => Ok(Command::Bitcount(
+            args.next().fail_to("No key provided")?,
+            parse_bit_range(args, true)?,
+        )),
+        "bitpos" => Ok(Command::Bitpos(
+            args.next().fail_to("No key provided")?,
+            parse_bit(&args.next().fail_to("No bit provided")?)?,
+            parse_bit_range(args, false)?,
+        )),
+        "bitop" => {
+            let op = match lowercase(&args.next().fail_to("No operation provided")?).as_str() {
+                "and" => bitop::Op::And,
+                "or" => bitop::Op::Or,
+                "xor" => bitop::Op::Xor,
+                "not" => bitop::Op::Not,
+                unknown => return Err(format!("Unknown BITOP operation: {}", unknown).into()),
+            };
+            let destination = args.next().fail_to("No destination provided")?;
+            let keys: Vec<Vec<u8>> = args.collect();
+            if keys.is_empty() {
+                return Err(String::from("No key provided").into());
+            }
+            Ok(Command::Bitop(op, destination, keys))
+        }
+        "pfadd" => Ok(Command::Pfadd(
+            args.next().fail_to("No key provided")?,
+            args.collect(),
+        )),
+        "pfcount" => {
+            let keys: Vec<Vec<u8>> = args.collect();
+            if keys.is_empty() {
+                return Err(String::from("No key provided").into());
+            }
+            Ok(Command::Pfcount(keys))
+        }
+        "pfmerge" => Ok(Command::Pfmerge(
+            args.next().fail_to("No destination provided")?,
+            args.collect(),
+        )),
+        "get" => Ok(Command::Get(args.next().fail_to("No key provided")?)),
+        "getdel" => Ok(Command::Getdel(args.next().fail_to("No key provided")?)),
+        "getex" => {
             let key = args.next().fail_to("No key provided")?;
-            let values: Vec<Primitive> = args
-                .map(parse_primitive)
-                .fold(Ok(LinkedList::new()), |acc, item| match (acc, item) {
-                    (Ok(mut acc), Ok(item)) => {
-                        acc.push_front(item);
-                        Ok(acc)
+            let mut lifetime = None;
+            while let Some(option) = args.next() {
+                let option = lowercase(&option);
+                match option.as_str() {
+                    "persist" => set_once(&mut lifetime, Lifetime::Persist)?,
+                    "ex" | "px" | "exat" | "pxat" => {
+                        let when = parse_expiry(&option, &mut args)?;
+                        set_once(&mut lifetime, Lifetime::Expire(when))?
                     }
-                    (Err(acc), _) => Err(acc),
-                    (_, Err(item)) => Err(item),
-                })?
-                .into_iter()
-                .collect();
-            Ok(Command::Sadd(key.into(), values))
+                    unknown => return Err(format!("Unknown GETEX option: {}", unknown).into()),
+                }
+            }
+            Ok(Command::Getex(key, lifetime.unwrap_or(Lifetime::Keep)))
         }
-        "scard" => Ok(Command::Scard(
-            args.next().fail_to("No key provided")?.into(),
+        "incr" => Ok(Command::Incrby(args.next().fail_to("No key provided")?, 1)),
+        "decr" => Ok(Command::Incrby(args.next().fail_to("No key provided")?, -1)),
+        "incrby" => Ok(Command::Incrby(
+            args.next().fail_to("No key provided")?,
+            parse_integer(&args.next().fail_to("No increment provided")?, "Increment")?,
+        )),
+        "decrby" => Ok(Command::Incrby(
+            args.next().fail_to("No key provided")?,
+            parse_integer(&args.next().fail_to("No decrement provided")?, "Decrement")?
+                .checked_neg()
+                .fail_to("decrement would overflow")?,
         )),
+        "incrbyfloat" => Ok(Command::Incrbyfloat(
+            args.next().fail_to("No key provided")?,
+            parse_float(&args.next().fail_to("No increment provided")?)
+                .fail_to("Increment is not a float")?,
+        )),
+        name @ ("expire" | "pexpire" | "expireat" | "pexpireat") => {
+            let key = args.next().fail_to("No key provided")?;
+            let time = args.next().fail_to("No expire time provided")?;
+            let when = parse_when(&time, name.starts_with('p'), name.ends_with("at"))?;
+            let mut options = expire::set::Options::default();
+            for option in args {
+                match lowercase(&option).as_str() {
+                    "nx" => options.nx = true,
+                    "xx" => options.xx = true,
+                    "gt" => options.gt = true,
+                    "lt" => options.lt = true,
+                    unknown => return Err(format!("Unknown EXPIRE option: {}", unknown).into()),
+                }
+            }
+            if options.nx && (options.xx || options.gt || options.lt) {
+                return Err(String::from("NX can't be combined with XX, GT or LT").into());
+            }
+            if options.gt && options.lt {
+                return Err(String::from("GT and LT options can't be combined").into());
+            }
+            Ok(Command::Expire(key, when, options))
+        }
+        "ttl" => Ok(Command::Ttl(args.next().fail_to("No key provided")?)),
+        "pttl" => Ok(Command::Pttl(args.next().fail_to("No key provided")?)),
+        "persist" => Ok(Command::Persist(args.next().fail_to("No key provided")?)),
+        "sadd" => {
+            let key = args.next().fail_to("No key provided")?;
+            Ok(Command::Sadd(key, values(args)?))
+        }
+        "scard" => Ok(Command::Scard(args.next().fail_to("No key provided")?)),
         "sismember" => Ok(Command::Sismember(
-            args.next().fail_to("No key provided")?.into(),
+            args.next().fail_to("No key provided")?,
             args.next()
                 .fail_to("No value provided")
-                .map(parse_primitive)??
-                .into(),
+                .map(Primitive::from)?,
+        )),
+        "smismember" => {
+            let key = args.next().fail_to("No key provided")?;
+            Ok(Command::Smismember(key, values(args)?))
+        }
+        "smembers" => Ok(Command::Smembers(args.next().fail_to("No key provided")?)),
+        "srem" => {
+            let key = args.next().fail_to("No key provided")?;
+            Ok(Command::Srem(key, values(args)?))
+        }
+        "spop" => Ok(Command::Spop(
+            args.next().fail_to("No key provided")?,
+            args.next()
+                .map(|count| parse_count(&count, "Count"))
+                .transpose()?,
+        )),
+        "srandmember" => Ok(Command::Srandmember(
+            args.next().fail_to("No key provided")?,
+            args.next()
+                .map(|count| parse_integer(&count, "Count"))
+                .transpose()?,
+        )),
+        "smove" => Ok(Command::Smove(
+            args.next().fail_to("No source provided")?,
+            args.next().fail_to("No destination provided")?,
+            args.next()
+                .fail_to("No value provided")
+                .map(Primitive::from)?,
         )),
         "sdiff" => Ok(Command::Sdiff(
-            args.next().fail_to("No base key provided")?.into(),
-            args.map(String::from).collect(),
+            args.next().fail_to("No base key provided")?,
+            args.collect(),
         )),
         "sdiffstore" => Ok(Command::SdiffStore(
-            args.next().fail_to("No destination provided")?.into(),
-            args.next().fail_to("No base key provided")?.into(),
-            args.map(String::from).collect(),
+            args.next().fail_to("No destination provided")?,
+            args.next().fail_to("No base key provided")?,
+            args.collect(),
         )),
-        "sinter" => Ok(Command::Sinter(args.map(String::from).collect())),
+        "sinter" => Ok(Command::Sinter(keys(args)?)),
+        "sintercard" => {
+            let keys = numbered_keys(&mut args)?;
+            let mut limit = 0;
+            while let Some(option) = args.next() {
+                match lowercase(&option).as_str() {
+                    "limit" => {
+                        let count = args.next().fail_to("No LIMIT provided")?;
+                        limit = parse_count(&count, "LIMIT")?;
+                    }
+                    unknown => return Err(format!("Unknown SINTERCARD option: {}", unknown).into()),
+                }
+            }
+            Ok(Command::Sintercard(keys, limit))
+        }
         "sinterstore" => Ok(Command::SinterStore(
-            args.next().fail_to("No destination provided")?.into(),
-            args.map(String::from).collect(),
+            args.next().fail_to("No destination provided")?,
+            keys(args)?,
         )),
-        "sunion" => Ok(Command::Sunion(args.map(String::from).collect())),
+        "sunion" => Ok(Command::Sunion(keys(args)?)),
         "sunionstore" => Ok(Command::SunionStore(
-            args.next().fail_to("No destination provided")?.into(),
-            args.map(String::from).collect(),
+            args.next().fail_to("No destination provided")?,
+            keys(args)?,
+        )),
+        "lpush" => {
+            let key = args.next().fail_to("No key provided")?;
+            Ok(Command::Lpush(key, values(args)?))
+        }
+        "rpush" => {
+            let key = args.next().fail_to("No key provided")?;
+            Ok(Command::Rpush(key, values(args)?))
+        }
+        "lpop" => Ok(Command::Lpop(
+            args.next().fail_to("No key provided")?,
+            args.next()
+                .map(|count| parse_count(&count, "Count"))
+                .transpose()?,
         )),
+        "rpop" => Ok(Command::Rpop(
+            args.next().fail_to("No key provided")?,
+            args.next()
+                .map(|count| parse_count(&count, "Count"))
+                .transpose()?,
+        )),
+        "lrange" => Ok(Command::Lrange(
+            args.next().fail_to("No key provided")?,
+            parse_integer(&args.next().fail_to("No start provided")?, "Start")?,
+            parse_integer(&args.next().fail_to("No stop provided")?, "Stop")?,
+        )),
+        "llen" => Ok(Command::Llen(args.next().fail_to("No key provided")?)),
+        "lindex" => Ok(Command::Lindex(
+            args.next().fail_to("No key provided")?,
+            parse_integer(&args.next().fail_to("No index provided")?, "Index")?,
+        )),
+        "lset" => Ok(Command::Lset(
+            args.next().fail_to("No key provided")?,
+            parse_integer(&args.next().fail_to("No index provided")?, "Index")?,
+            args.next()
+                .fail_to("No value provided")
+                .map(Primitive::from)?,
+        )),
+        "lrem" => Ok(Command::Lrem(
+            args.next().fail_to("No key provided")?,
+            parse_integer(&args.next().fail_to("No count provided")?, "Count")?,
+            args.next()
+                .fail_to("No value provided")
+                .map(Primitive::from)?,
+        )),
+        "ltrim" => Ok(Command::Ltrim(
+            args.next().fail_to("No key provided")?,
+            parse_integer(&args.next().fail_to("No start provided")?, "Start")?,
+            parse_integer(&args.next().fail_to("No stop provided")?, "Stop")?,
+        )),
+        "linsert" => {
+            let key = args.next().fail_to("No key provided")?;
+            let position = match lowercase(&args.next().fail_to("No position provided")?).as_str() {
+                "before" => Position::Before,
+                "after" => Position::After,
+                unknown => return Err(format!("Unknown LINSERT position: {}", unknown).into()),
+            };
+            Ok(Command::Linsert(
+                key,
+                position,
+                args.next()
+                    .fail_to("No pivot provided")
+                    .map(Primitive::from)?,
+                args.next()
+                    .fail_to("No value provided")
+                    .map(Primitive::from)?,
+            ))
+        }
+        "lpos" => {
+            let key = args.next().fail_to("No key provided")?;
+            let value = args
+                .next()
+                .fail_to("No value provided")
+                .map(Primitive::from)?;
+            let mut options = pos::Options::default();
+            while let Some(option) = args.next() {
+                match lowercase(&option).as_str() {
+                    "rank" => {
+                        options.rank =
+                            parse_integer(&args.next().fail_to("No rank provided")?, "Rank")?;
+                        if options.rank == 0 {
+                            return Err(String::from("Rank can't be zero").into());
+                        }
+                    }
+                    "count" => {
+                        options.count = Some(parse_count(
+                            &args.next().fail_to("No count provided")?,
+                            "Count",
+                        )?)
+                    }
+                    "maxlen" => {
+                        options.maxlen =
+                            parse_count(&args.next().fail_to("No maxlen provided")?, "Maxlen")?
+                    }
+                    unknown => return Err(format!("Unknown LPOS option: {}", unknown).into()),
+                }
+            }
+            Ok(Command::Lpos(key, value, options))
+        }
+        "lmove" => Ok(Command::Lmove(
+            args.next().fail_to("No source provided")?,
+            args.next().fail_to("No destination provided")?,
+            parse_end(&args.next().fail_to("No source end provided")?)?,
+            parse_end(&args.next().fail_to("No destination end provided")?)?,
+        )),
+        "blpop" => {
+            let (keys, timeout) = keys_and_timeout(args)?;
+            Ok(Command::Blpop(keys, timeout))
+        }
+        "brpop" => {
+            let (keys, timeout) = keys_and_timeout(args)?;
+            Ok(Command::Brpop(keys, timeout))
+        }
+        "blmove" => Ok(Command::Blmove(
+            args.next().fail_to("No source provided")?,
+            args.next().fail_to("No destination provided")?,
+            parse_end(&args.next().fail_to("No source end provided")?)?,
+            parse_end(&args.next().fail_to("No destination end provided")?)?,
+            parse_timeout(&args.next().fail_to("No timeout provided")?)?,
+        )),
+        "hset" => {
+            let key = args.next().fail_to("No key provided")?;
+            Ok(Command::Hset(key, pairs(args)?))
+        }
+        "hsetnx" => Ok(Command::Hsetnx(
+            args.next().fail_to("No key provided")?,
+            args.next()
+                .fail_to("No field provided")
+                .map(Primitive::from)?,
+            args.next()
+                .fail_to("No value provided")
+                .map(Primitive::from)?,
+        )),
+        "hget" => Ok(Command::Hget(
+            args.next().fail_to("No key provided")?,
+            args.next()
+                .fail_to("No field provided")
+                .map(Primitive::from)?,
+        )),
+        "hmget" => {
+            let key = args.next().fail_to("No key provided")?;
+            Ok(Command::Hmget(key, values(args)?))
+        }
+        "hdel" => {
+            let key = args.next().fail_to("No key provided")?;
+            Ok(Command::Hdel(key, values(args)?))
+        }
+        "hgetall" => Ok(Command::Hgetall(args.next().fail_to("No key provided")?)),
+        "hkeys" => Ok(Command::Hkeys(args.next().fail_to("No key provided")?)),
+        "hvals" => Ok(Command::Hvals(args.next().fail_to("No key provided")?)),
+        "hlen" => Ok(Command::Hlen(args.next().fail_to("No key provided")?)),
+        "hexists" => Ok(Command::Hexists(
+            args.next().fail_to("No key provided")?,
+            args.next()
+                .fail_to("No field provided")
+                .map(Primitive::from)?,
+        )),
+        "hincrby" => Ok(Command::Hincrby(
+            args.next().fail_to("No key provided")?,
+            args.next()
+                .fail_to("No field provided")
+                .map(Primitive::from)?,
+            parse_integer(&args.next().fail_to("No increment provided")?, "Increment")?,
+        )),
+        "hincrbyfloat" => Ok(Command::Hincrbyfloat(
+            args.next().fail_to("No key provided")?,
+            args.next()
+                .fail_to("No field provided")
+                .map(Primitive::from)?,
+            parse_float(&args.next().fail_to("No increment provided")?)
+                .fail_to("Increment is not a float")?,
+        )),
+        "hstrlen" => Ok(Command::Hstrlen(
+            args.next().fail_to("No key provided")?,
+            args.next()
+                .fail_to("No field provided")
+                .map(Primitive::from)?,
+        )),
+        "zadd" => {
+            let key = args.next().fail_to("No key provided")?;
+            let mut args = args.peekable();
+            let mut options = add::Options::default();
+            let mut incr = false;
+            while let Some(flag) = args.peek() {
+                match lowercase(flag).as_str() {
+                    "nx" => options.nx = true,
+                    "xx" => options.xx = true,
+                    "gt" => options.gt = true,
+                    "lt" => options.lt = true,
+                    "ch" => options.ch = true,
+                    "incr" => incr = true,
+                    _ => break,
+                }
+                args.next();
+            }
+            if options.nx && options.xx {
+                return Err(String::from("XX and NX options can't be combined").into());
+            }
+            if [options.nx, options.gt, options.lt]
+                .iter()
+                .filter(|set| **set)
+                .count()
+                > 1
+            {
+                return Err(String::from("GT, LT and NX options can't be combined").into());
+            }
+            let values = values(args)?;
+            if values.len() % 2 != 0 {
+                return Err(String::from("Every member needs a score").into());
+            }
+            let mut values = values.into_iter().map(Primitive::into_bytes);
+            let pairs = std::iter::from_fn(|| Some((values.next()?, values.next()?)))
+                .map(|(score, member)| Ok((parse_score(&score)?, member)))
+                .collect::<Result<Vec<(f64, Vec<u8>)>, ApplicationError>>()?;
+            match (incr, &pairs[..]) {
+                (false, _) => Ok(Command::Zadd(key, options, pairs)),
+                (true, [(by, member)]) => Ok(Command::ZaddIncr(key, options, *by, member.clone())),
+                (true, _) => Err(String::from("INCR takes a single score and member").into()),
+            }
+        }
+        "zincrby" => Ok(Command::Zincrby(
+            args.next().fail_to("No key provided")?,
+            parse_score(&args.next().fail_to("No increment provided")?)?,
+            args.next().fail_to("No member provided")?,
+        )),
+        "zrange" => {
+            let key = args.next().fail_to("No key provided")?;
+            let start = args.next().fail_to("No start provided")?;
+            let stop = args.next().fail_to("No stop provided")?;
+            let (mut by_score, mut by_lex, mut reverse, mut limit, mut with_scores) =
+                (false, false, false, None, false);
+            while let Some(option) = args.next() {
+                match lowercase(&option).as_str() {
+                    "byscore" => by_score = true,
+                    "bylex" => by_lex = true,
+                    "rev" => reverse = true,
+                    "limit" => limit = Some(parse_limit(&mut args)?),
+                    "withscores" => with_scores = true,
+                    unknown => return Err(format!("Unknown ZRANGE option: {}", unknown).into()),
+                }
+            }
+            // Reversed ranges of scores and members are given highest first.
+            let (min, max) = if reverse {
+                (&stop, &start)
+            } else {
+                (&start, &stop)
+            };
+            let by = match (by_score, by_lex) {
+                (true, true) => {
+                    return Err(String::from("BYSCORE and BYLEX can't be combined").into())
+                }
+                (true, false) => By::Score(parse_score_bound(min)?, parse_score_bound(max)?),
+                (false, true) if with_scores => {
+                    return Err(String::from("WITHSCORES can't be combined with BYLEX").into())
+                }
+                (false, true) => By::Lex(parse_lex_bound(min)?, parse_lex_bound(max)?),
+                (false, false) if limit.is_some() => {
+                    return Err(String::from("LIMIT needs BYSCORE or BYLEX").into())
+                }
+                (false, false) => By::Rank(
+                    parse_integer(&start, "Start")?,
+                    parse_integer(&stop, "Stop")?,
+                ),
+            };
+            Ok(Command::Zrange(
+                key,
+                Range { by, reverse, limit },
+                with_scores,
+            ))
+        }
+        "zrangebyscore" => {
+            let key = args.next().fail_to("No key provided")?;
+            let min = parse_score_bound(&args.next().fail_to("No min provided")?)?;
+            let max = parse_score_bound(&args.next().fail_to("No max provided")?)?;
+            let (mut limit, mut with_scores) = (None, false);
+            while let Some(option) = args.next() {
+                match lowercase(&option).as_str() {
+                    "limit" => limit = Some(parse_limit(&mut args)?),
+                    "withscores" => with_scores = true,
+                    unknown => {
+                        return Err(format!("Unknown ZRANGEBYSCORE option: {}", unknown).into())
+                    }
+                }
+            }
+            let by = By::Score(min, max);
+            Ok(Command::Zrange(
+                key,
+                Range {
+                    by,
+                    reverse: false,
+                    limit,
+                },
+                with_scores,
+            ))
+        }
+        "zrank" => Ok(Command::Zrank(
+            args.next().fail_to("No key provided")?,
+            args.next().fail_to("No member provided")?,
+        )),
+        "zscore" => Ok(Command::Zscore(
+            args.next().fail_to("No key provided")?,
+            args.next().fail_to("No member provided")?,
+        )),
+        "zrem" => {
+            let key = args.next().fail_to("No key provided")?;
+            let members: Vec<Vec<u8>> = args.collect();
+            if members.is_empty() {
+                return Err(String::from("No members provided").into());
+            }
+            Ok(Command::Zrem(key, members))
+        }
+        "zunionstore" => {
+            let destination = args.next().fail_to("No destination provided")?;
+            let keys = numbered_keys(&mut args)?;
+            let (weights, aggregate) = weights_and_aggregate(args, keys.len())?;
+            Ok(Command::ZunionStore(destination, keys, weights, aggregate))
+        }
+        "zinterstore" => {
+            let destination = args.next().fail_to("No destination provided")?;
+            let keys = numbered_keys(&mut args)?;
+            let (weights, aggregate) = weights_and_aggregate(args, keys.len())?;
+            Ok(Command::ZinterStore(destination, keys, weights, aggregate))
+        }
+        "zdiffstore" => {
+            let destination = args.next().fail_to("No destination provided")?;
+            let keys = numbered_keys(&mut args)?;
+            if let Some(extra) = args.next() {
+                return Err(format!("Unknown ZDIFFSTORE option: {}", lowercase(&extra)).into());
+            }
+            Ok(Command::ZdiffStore(destination, keys))
+        }
+        "geoadd" => {
+            let key = args.next().fail_to("No key provided")?;
+            let mut args = args.peekable();
+            let mut options = add::Options::default();
+            while let Some(flag) = args.peek() {
+                match lowercase(flag).as_str() {
+                    "nx" => options.nx = true,
+                    "xx" => options.xx = true,
+                    "ch" => options.ch = true,
+                    _ => break,
+                }
+                args.next();
+            }
+            if options.nx && options.xx {
+                return Err(String::from("XX and NX options can't be combined").into());
+            }
+            let args: Vec<Vec<u8>> = args.collect();
+            if args.is_empty() || !args.len().is_multiple_of(3) {
+                return Err(String::from("Every member needs a longitude and latitude").into());
+            }
+            let points = args
+                .chunks(3)
+                .map(|point| {
+                    let longitude = parse_coordinate(&point[0], "Longitude")?;
+                    let latitude = parse_coordinate(&point[1], "Latitude")?;
+                    Ok((longitude, latitude, point[2].clone()))
+                })
+                .collect::<Result<Vec<(f64, f64, Vec<u8>)>, ApplicationError>>()?;
+            Ok(Command::Geoadd(key, options, points))
+        }
+        "geodist" => {
+            let key = args.next().fail_to("No key provided")?;
+            let a = args.next().fail_to("No member provided")?;
+            let b = args.next().fail_to("No second member provided")?;
+            let unit = match args.next() {
+                Some(unit) => parse_unit(&unit)?,
+                None => geo::Unit::Meters,
+            };
+            Ok(Command::Geodist(key, a, b, unit))
+        }
+        "geopos" => Ok(Command::Geopos(
+            args.next().fail_to("No key provided")?,
+            args.collect(),
+        )),
+        "geohash" => Ok(Command::Geohash(
+            args.next().fail_to("No key provided")?,
+            args.collect(),
+        )),
+        "geosearch" => {
+            let key = args.next().fail_to("No key provided")?;
+            let (query, with, _) = parse_geosearch(args, false)?;
+            Ok(Command::Geosearch(key, query, with))
+        }
+        "geosearchstore" => {
+            let destination = args.next().fail_to("No destination provided")?;
+            let key = args.next().fail_to("No key provided")?;
+            let (query, _, store_dist) = parse_geosearch(args, true)?;
+            Ok(Command::GeosearchStore(destination, key, query, store_dist))
+        }
+        "xadd" => {
+            let key = args.next().fail_to("No key provided")?;
+            let mut args = args.peekable();
+            let mut options = xadd::Options::default();
+            while let Some(option) = args.peek() {
+                match lowercase(option).as_str() {
+                    "nomkstream" => {
+                        args.next();
+                        options.no_mkstream = true;
+                    }
+                    "maxlen" | "minid" => options.trim = Some(parse_trim(&mut args)?),
+                    _ => break,
+                }
+            }
+            let id = parse_new_id(&args.next().fail_to("No ID provided")?)?;
+            Ok(Command::Xadd(key, options, id, pairs(args)?))
+        }
+        name @ ("xrange" | "xrevrange") => {
+            let reverse = name == "xrevrange";
+            let key = args.next().fail_to("No key provided")?;
+            let first = args.next().fail_to("No start provided")?;
+            let second = args.next().fail_to("No end provided")?;
+            // Reversed ranges are given end first.
+            let (start, end) = if reverse {
+                (second, first)
+            } else {
+                (first, second)
+            };
+            let count = match args.next() {
+                Some(option) if lowercase(&option) == "count" => Some(parse_count(
+                    &args.next().fail_to("No count provided")?,
+                    "Count",
+                )?),
+                Some(option) => {
+                    return Err(format!("Unknown option: {}", lowercase(&option)).into())
+                }
+                None => None,
+            };
+            Ok(Command::Xrange(
+                key,
+                parse_range_start(&start)?,
+                parse_range_end(&end)?,
+                count,
+                reverse,
+            ))
+        }
+        "xread" => {
+            let ReadOptions { count, block, .. } = read_options(&mut args, false)?;
+            let (keys, afters) = streams(args, parse_after)?;
+            Ok(match block {
+                Some(timeout) => Command::XreadBlock(keys, afters, count, timeout),
+                None => Command::Xread(keys, afters, count),
+            })
+        }
+        "xreadgroup" => {
+            match args.next() {
+                Some(option) if lowercase(&option) == "group" => {}
+                _ => return Err(String::from("XREADGROUP needs GROUP").into()),
+            }
+            let group = args.next().fail_to("No group provided")?;
+            let consumer = args.next().fail_to("No consumer provided")?;
+            let ReadOptions {
+                count,
+                block,
+                noack,
+            } = read_options(&mut args, true)?;
+            let (keys, starts) = streams(args, |id| match id {
+                b">" => Ok(Start::New),
+                id => parse_stream_id(id, 0).map(Start::History),
+            })?;
+            let options = readgroup::Options {
+                group,
+                consumer,
+                count,
+                noack,
+            };
+            Ok(match block {
+                Some(timeout) => Command::XreadGroupBlock(options, keys, starts, timeout),
+                None => Command::XreadGroup(options, keys, starts),
+            })
+        }
+        "xlen" => Ok(Command::Xlen(args.next().fail_to("No key provided")?)),
+        "xtrim" => {
+            let key = args.next().fail_to("No key provided")?;
+            let mut args = args.peekable();
+            let trim = parse_trim(&mut args)?;
+            if let Some(extra) = args.next() {
+                return Err(format!("Unknown XTRIM option: {}", lowercase(&extra)).into());
+            }
+            Ok(Command::Xtrim(key, trim))
+        }
+        "xgroup" => {
+            let subcommand = lowercase(&args.next().fail_to("No XGROUP subcommand given")?);
+            let key = args.next().fail_to("No key provided")?;
+            let group = args.next().fail_to("No group provided")?;
+            let command = match subcommand.as_str() {
+                "create" => {
+                    let start = parse_after(&args.next().fail_to("No ID provided")?)?;
+                    let mkstream = match args.next() {
+                        Some(option) if lowercase(&option) == "mkstream" => true,
+                        Some(option) => {
+                            return Err(format!("Unknown option: {}", lowercase(&option)).into())
+                        }
+                        None => false,
+                    };
+                    Command::XgroupCreate(key, group, start, mkstream)
+                }
+                "setid" => Command::XgroupSetId(
+                    key,
+                    group,
+                    parse_after(&args.next().fail_to("No ID provided")?)?,
+                ),
+                "destroy" => Command::XgroupDestroy(key, group),
+                "createconsumer" => Command::XgroupCreateConsumer(
+                    key,
+                    group,
+                    args.next().fail_to("No consumer provided")?,
+                ),
+                "delconsumer" => Command::XgroupDelConsumer(
+                    key,
+                    group,
+                    args.next().fail_to("No consumer provided")?,
+                ),
+                unknown => return Err(format!("Unknown XGROUP subcommand: {}", unknown).into()),
+            };
+            if let Some(extra) = args.next() {
+                return Err(format!("Unknown XGROUP option: {}", lowercase(&extra)).into());
+            }
+            Ok(command)
+        }
+        "xack" => {
+            let key = args.next().fail_to("No key provided")?;
+            let group = args.next().fail_to("No group provided")?;
+            let ids = args
+                .map(|id| parse_stream_id(&id, 0))
+                .collect::<Result<Vec<StreamId>, ApplicationError>>()?;
+            if ids.is_empty() {
+                return Err(String::from("No IDs provided").into());
+            }
+            Ok(Command::Xack(key, group, ids))
+        }
+        "xpending" => {
+            let key = args.next().fail_to("No key provided")?;
+            let group = args.next().fail_to("No group provided")?;
+            let mut args = args.peekable();
+            if args.peek().is_none() {
+                return Ok(Command::Xpending(key, group, None));
+            }
+            let min_idle = match args.peek() {
+                Some(option) if lowercase(option) == "idle" => {
+                    args.next();
+                    parse_milliseconds(&args.next().fail_to("No idle time provided")?)?
+                }
+                _ => 0,
+            };
+            let filter = pending::Filter {
+                min_idle,
+                start: parse_range_start(&args.next().fail_to("No start provided")?)?,
+                end: parse_range_end(&args.next().fail_to("No end provided")?)?,
+                count: parse_count(&args.next().fail_to("No count provided")?, "Count")?,
+                consumer: args.next(),
+            };
+            if let Some(extra) = args.next() {
+                return Err(format!("Unknown XPENDING option: {}", lowercase(&extra)).into());
+            }
+            Ok(Command::Xpending(key, group, Some(filter)))
+        }
+        "xclaim" => {
+            let key = args.next().fail_to("No key provided")?;
+            let group = args.next().fail_to("No group provided")?;
+            let consumer = args.next().fail_to("No consumer provided")?;
+            let mut options = claim::Options {
+                min_idle: parse_milliseconds(&args.next().fail_to("No min idle time provided")?)?,
+                ..Default::default()
+            };
+            let mut args = args.peekable();
+            let mut ids = Vec::new();
+            while let Some(id) = args.peek().and_then(|id| StreamId::parse(id, 0)) {
+                args.next();
+                ids.push(id);
+            }
+            if ids.is_empty() {
+                return Err(String::from("No IDs provided").into());
+            }
+            while let Some(option) = args.next() {
+                let mut value = |name: &str| {
+                    args.next()
+                        .fail_to(&format!("No {} provided", name))
+                        .and_then(|value| parse_milliseconds(&value))
+                };
+                match lowercase(&option).as_str() {
+                    "idle" => options.idle = Some(value("idle time")?),
+                    "time" => options.time = Some(value("time")?),
+                    "retrycount" => options.retry_count = Some(value("retry count")?),
+                    "force" => options.force = true,
+                    "justid" => options.just_id = true,
+                    "lastid" => {
+                        let id = args.next().fail_to("No last ID provided")?;
+                        options.last_id = Some(parse_stream_id(&id, 0)?);
+                    }
+                    unknown => return Err(format!("Unknown XCLAIM option: {}", unknown).into()),
+                }
+            }
+            Ok(Command::Xclaim(key, group, consumer, ids, options))
+        }
+        "xautoclaim" => {
+            let key = args.next().fail_to("No key provided")?;
+            let group = args.next().fail_to("No group provided")?;
+            let consumer = args.next().fail_to("No consumer provided")?;
+            let mut options = autoclaim::Options {
+                min_idle: parse_milliseconds(&args.next().fail_to("No min idle time provided")?)?,
+                start: parse_range_start(&args.next().fail_to("No start provided")?)?,
+                count: 100,
+                just_id: false,
+            };
+            while let Some(option) = args.next() {
+                match lowercase(&option).as_str() {
+                    "count" => {
+                        options.count =
+                            parse_count(&args.next().fail_to("No count provided")?, "Count")?;
+                        if options.count == 0 {
+                            return Err(String::from("Count must be at least 1").into());
+                        }
+                    }
+                    "justid" => options.just_id = true,
+                    unknown => return Err(format!("Unknown XAUTOCLAIM option: {}", unknown).into()),
+                }
+            }
+            Ok(Command::Xautoclaim(key, group, consumer, options))
+        }
         unknown_command => Err(format!("No such command: {}", unknown_command).into()),
     }
 }
 
-fn parse_primitive(data: &str) -> Result<Primitive, ApplicationError> {
-    if let (Some('"'), Some('"')) = (data.chars().nth(0), data.chars().nth_back(0)) {
-        Ok(Primitive::String(data[1..data.len() - 1].into()))
-    } else if let Ok(n) = data.parse::<i64>() {
-        Ok(Primitive::Number(n))
+/// The keys of a command that needs at least one.
+fn keys<I>(args: I) -> Result<Vec<Vec<u8>>, ApplicationError>
+where
+    I: Iterator<Item = Vec<u8>>,
+{
+    let keys: Vec<Vec<u8>> = args.collect();
+    if keys.is_empty() {
+        Err(String::from("No key provided").into())
+    } else {
+        Ok(keys)
+    }
+}
+
+/// The values of a command that needs at least one.
+fn values<I>(args: I) -> Result<Vec<Primitive>, ApplicationError>
+where
+    I: Iterator<Item = Vec<u8>>,
+{
+    let values: Vec<Primitive> = args.map(Primitive::from).collect();
+    if values.is_empty() {
+        Err(String::from("No values provided").into())
+    } else {
+        Ok(values)
+    }
+}
+
+/// The field and value pairs of a command that needs at least one.
+fn pairs<I>(args: I) -> Result<Vec<(Primitive, Primitive)>, ApplicationError>
+where
+    I: Iterator<Item = Vec<u8>>,
+{
+    let args = values(args)?;
+    if args.len() % 2 != 0 {
+        return Err(String::from("Every field needs a value").into());
+    }
+    let mut args = args.into_iter();
+    Ok(std::iter::from_fn(|| Some((args.next()?, args.next()?))).collect())
+}
+
+/// The key and value pairs of a command that sets at least one string.
+fn keys_and_values<I>(args: I) -> Result<Vec<(Vec<u8>, Primitive)>, ApplicationError>
+where
+    I: Iterator<Item = Vec<u8>>,
+{
+    let args = values(args)?;
+    if args.len() % 2 != 0 {
+        return Err(String::from("Every key needs a value").into());
+    }
+    let mut args = args.into_iter();
+    Ok(std::iter::from_fn(|| Some((args.next()?.into_bytes(), args.next()?))).collect())
+}
+
+/// Scores are floats, infinities included.
+fn parse_score(arg: &[u8]) -> Result<f64, ApplicationError> {
+    match std::str::from_utf8(arg).map(str::parse::<f64>) {
+        Ok(Ok(score)) if !score.is_nan() => Ok(score),
+        _ => Err(String::from("Score is not a valid float").into()),
+    }
+}
+
+/// A score bound is inclusive unless it starts with `(`.
+fn parse_score_bound(arg: &[u8]) -> Result<ScoreBound, ApplicationError> {
+    match arg.split_first() {
+        Some((b'(', score)) => Ok(ScoreBound::Exclusive(parse_score(score)?)),
+        _ => Ok(ScoreBound::Inclusive(parse_score(arg)?)),
+    }
+}
+
+/// A member bound is `-` or `+` for either end of the set, or a member
+/// prefixed with `[` when inclusive or `(` when exclusive.
+fn parse_lex_bound(arg: &[u8]) -> Result<LexBound, ApplicationError> {
+    match arg.split_first() {
+        Some((b'-', [])) => Ok(LexBound::Min),
+        Some((b'+', [])) => Ok(LexBound::Max),
+        Some((b'[', member)) => Ok(LexBound::Inclusive(member.to_vec())),
+        Some((b'(', member)) => Ok(LexBound::Exclusive(member.to_vec())),
+        _ => Err(String::from("Member bounds must start with (, [, - or +").into()),
+    }
+}
+
+/// The offset and count following a LIMIT option.
+fn parse_limit<I>(args: &mut I) -> Result<(i64, i64), ApplicationError>
+where
+    I: Iterator<Item = Vec<u8>>,
+{
+    Ok((
+        parse_integer(&args.next().fail_to("No offset provided")?, "Offset")?,
+        parse_integer(&args.next().fail_to("No count provided")?, "Count")?,
+    ))
+}
+
+/// A count of keys followed by that many keys, as the sorted set algebra
+/// commands take them.
+fn numbered_keys<I>(args: &mut I) -> Result<Vec<Vec<u8>>, ApplicationError>
+where
+    I: Iterator<Item = Vec<u8>>,
+{
+    let numkeys = parse_count(&args.next().fail_to("No numkeys provided")?, "Numkeys")?;
+    if numkeys == 0 {
+        return Err(String::from("Numkeys must be at least 1").into());
+    }
+    let keys: Vec<Vec<u8>> = args.by_ref().take(numkeys).collect();
+    if keys.len() < numkeys {
+        return Err(String::from("Fewer keys provided than numkeys").into());
+    }
+    Ok(keys)
+}
+
+/// The WEIGHTS and AGGREGATE options of ZUNIONSTORE and ZINTERSTORE. Every
+/// input weighs 1 and scores are summed unless they say otherwise.
+fn weights_and_aggregate<I>(
+    mut args: I,
+    numkeys: usize,
+) -> Result<(Vec<f64>, Aggregate), ApplicationError>
+where
+    I: Iterator<Item = Vec<u8>>,
+{
+    let (mut weights, mut aggregate) = (vec![1.0; numkeys], Aggregate::Sum);
+    while let Some(option) = args.next() {
+        match lowercase(&option).as_str() {
+            "weights" => {
+                for weight in weights.iter_mut() {
+                    *weight = parse_score(&args.next().fail_to("Every key needs a weight")?)?;
+                }
+            }
+            "aggregate" => {
+                aggregate = match lowercase(&args.next().fail_to("No aggregate provided")?).as_str()
+                {
+                    "sum" => Aggregate::Sum,
+                    "min" => Aggregate::Min,
+                    "max" => Aggregate::Max,
+                    unknown => return Err(format!("Unknown aggregate: {}", unknown).into()),
+                }
+            }
+            unknown => return Err(format!("Unknown option: {}", unknown).into()),
+        }
+    }
+    Ok((weights, aggregate))
+}
+
+/// A longitude or latitude, which may be any finite float until checked.
+fn parse_coordinate(arg: &[u8], name: &str) -> Result<f64, ApplicationError> {
+    match std::str::from_utf8(arg).map(str::parse::<f64>) {
+        Ok(Ok(coordinate)) if coordinate.is_finite() => Ok(coordinate),
+        _ => Err(format!("{} is not a valid float", name).into()),
+    }
+}
+
+/// The unit GEO commands take distances in: M, KM, FT or MI.
+fn parse_unit(arg: &[u8]) -> Result<geo::Unit, ApplicationError> {
+    match lowercase(arg).as_str() {
+        "m" => Ok(geo::Unit::Meters),
+        "km" => Ok(geo::Unit::Kilometers),
+        "ft" => Ok(geo::Unit::Feet),
+        "mi" => Ok(geo::Unit::Miles),
+        unknown => Err(format!("Unsupported unit: {}; use M, KM, FT or MI", unknown).into()),
+    }
+}
+
+/// A radius, width or height, none of which can be negative.
+fn parse_distance(arg: &[u8], name: &str) -> Result<f64, ApplicationError> {
+    match parse_coordinate(arg, name)? {
+        distance if distance < 0.0 => Err(format!("{} can't be negative", name).into()),
+        distance => Ok(distance),
+    }
+}
+
+/// The options GEOSEARCH and GEOSEARCHSTORE share, along with what only
+/// one or the other takes: the WITH options and STOREDIST respectively.
+fn parse_geosearch<I>(
+    args: I,
+    store: bool,
+) -> Result<(search::Query, search::With, bool), ApplicationError>
+where
+    I: Iterator<Item = Vec<u8>>,
+{
+    let mut args = args.peekable();
+    let (mut from, mut shape, mut unit) = (None, None, geo::Unit::Meters);
+    let (mut order, mut count, mut any) = (None, None, false);
+    let (mut with, mut store_dist) = (search::With::default(), false);
+    while let Some(option) = args.next() {
+        let option = lowercase(&option);
+        match option.as_str() {
+            "frommember" | "fromlonlat" if from.is_some() => {
+                return Err(
+                    String::from("Only one of FROMMEMBER and FROMLONLAT can be given").into(),
+                )
+            }
+            "frommember" => {
+                from = Some(search::From::Member(
+                    args.next().fail_to("No member provided")?,
+                ))
+            }
+            "fromlonlat" => {
+                let longitude =
+                    parse_coordinate(&args.next().fail_to("No longitude provided")?, "Longitude")?;
+                let latitude =
+                    parse_coordinate(&args.next().fail_to("No latitude provided")?, "Latitude")?;
+                from = Some(search::From::Point(longitude, latitude));
+            }
+            "byradius" | "bybox" if shape.is_some() => {
+                return Err(String::from("Only one of BYRADIUS and BYBOX can be given").into())
+            }
+            "byradius" => {
+                let radius = parse_distance(&args.next().fail_to("No radius provided")?, "Radius")?;
+                shape = Some(search::Shape::Radius(radius));
+                unit = parse_unit(&args.next().fail_to("No unit provided")?)?;
+            }
+            "bybox" => {
+                let width = parse_distance(&args.next().fail_to("No width provided")?, "Width")?;
+                let height = parse_distance(&args.next().fail_to("No height provided")?, "Height")?;
+                shape = Some(search::Shape::Box(width, height));
+                unit = parse_unit(&args.next().fail_to("No unit provided")?)?;
+            }
+            "asc" => order = Some(search::Order::Asc),
+            "desc" => order = Some(search::Order::Desc),
+            "count" => {
+                match parse_count(&args.next().fail_to("No count provided")?, "Count")? {
+                    0 => return Err(String::from("Count must be positive").into()),
+                    n => count = Some(n),
+                }
+                if args.peek().is_some_and(|next| lowercase(next) == "any") {
+                    args.next();
+                    any = true;
+                }
+            }
+            "withdist" if !store => with.dist = true,
+            "withhash" if !store => with.hash = true,
+            "withcoord" if !store => with.coord = true,
+            "storedist" if store => store_dist = true,
+            unknown => return Err(format!("Unknown GEOSEARCH option: {}", unknown).into()),
+        }
+    }
+    let query = search::Query {
+        from: from.fail_to("One of FROMMEMBER and FROMLONLAT must be given")?,
+        shape: shape.fail_to("One of BYRADIUS and BYBOX must be given")?,
+        unit,
+        order,
+        count,
+        any,
+    };
+    Ok((query, with, store_dist))
+}
+
+/// A stream ID, where a bare millisecond gets the sequence number `seq`.
+fn parse_stream_id(arg: &[u8], seq: u64) -> Result<StreamId, ApplicationError> {
+    StreamId::parse(arg, seq).fail_to("Invalid stream ID")
+}
+
+/// The ID XADD gives its entry: `*`, `ms-*` or an explicit one.
+fn parse_new_id(arg: &[u8]) -> Result<NewId, ApplicationError> {
+    match arg {
+        b"*" => Ok(NewId::Auto),
+        [ms @ .., b'-', b'*'] => Ok(NewId::AutoSequence(parse_stream_id(ms, 0)?.ms)),
+        id => parse_stream_id(id, 0).map(NewId::Explicit),
+    }
+}
+
+/// The start of a range of stream IDs: `-`, an ID, or one prefixed with
+/// `(` to leave it out.
+fn parse_range_start(arg: &[u8]) -> Result<StreamId, ApplicationError> {
+    match arg {
+        b"-" => Ok(StreamId::MIN),
+        b"+" => Ok(StreamId::MAX),
+        [b'(', id @ ..] => parse_stream_id(id, 0)?
+            .next()
+            .fail_to("Invalid start of range"),
+        id => parse_stream_id(id, 0),
+    }
+}
+
+/// The end of a range of stream IDs, where a bare millisecond takes in
+/// every entry added in it.
+fn parse_range_end(arg: &[u8]) -> Result<StreamId, ApplicationError> {
+    match arg {
+        b"-" => Ok(StreamId::MIN),
+        b"+" => Ok(StreamId::MAX),
+        [b'(', id @ ..] => parse_stream_id(id, u64::MAX)?
+            .prev()
+            .fail_to("Invalid end of range"),
+        id => parse_stream_id(id, u64::MAX),
+    }
+}
+
+/// `MAXLEN|MINID [=|~] threshold [LIMIT count]`, as XADD and XTRIM take it.
+/// LIMIT only goes with `~`, as trimming exactly means trimming everything.
+fn parse_trim<I>(args: &mut Peekable<I>) -> Result<Trim, ApplicationError>
+where
+    I: Iterator<Item = Vec<u8>>,
+{
+    let strategy = lowercase(&args.next().fail_to("No trim strategy provided")?);
+    let approximate = match args.peek().map(Vec::as_slice) {
+        Some(b"=") => {
+            args.next();
+            false
+        }
+        Some(b"~") => {
+            args.next();
+            true
+        }
+        _ => false,
+    };
+    let threshold = args.next().fail_to("No threshold provided")?;
+    let threshold = match strategy.as_str() {
+        "maxlen" => Threshold::MaxLen(parse_count(&threshold, "MAXLEN")?),
+        "minid" => Threshold::MinId(parse_stream_id(&threshold, 0)?),
+        unknown => return Err(format!("Unknown trim strategy: {}", unknown).into()),
+    };
+    let limit = match args.peek() {
+        Some(option) if lowercase(option) == "limit" => {
+            args.next();
+            if !approximate {
+                return Err(String::from("LIMIT can only be used with ~").into());
+            }
+            Some(parse_count(
+                &args.next().fail_to("No limit provided")?,
+                "Limit",
+            )?)
+        }
+        _ => None,
+    };
+    Ok(Trim { threshold, limit })
+}
+
+/// The options XREAD and XREADGROUP take before STREAMS.
+struct ReadOptions {
+    /// The most entries to read from each stream, where 0 means no limit.
+    count: Option<usize>,
+    /// How long to block for, if at all, where no timeout means forever.
+    block: Option<Option<Duration>>,
+    /// Whether XREADGROUP can skip acknowledgements.
+    noack: bool,
+}
+
+fn read_options<I>(args: &mut I, noack_allowed: bool) -> Result<ReadOptions, ApplicationError>
+where
+    I: Iterator<Item = Vec<u8>>,
+{
+    let mut options = ReadOptions {
+        count: None,
+        block: None,
+        noack: false,
+    };
+    loop {
+        let option = args.next().fail_to("No STREAMS provided")?;
+        match lowercase(&option).as_str() {
+            "count" => {
+                let n = parse_count(&args.next().fail_to("No count provided")?, "Count")?;
+                options.count = Some(n).filter(|n| *n > 0);
+            }
+            "block" => {
+                let ms = parse_milliseconds(&args.next().fail_to("No timeout provided")?)?;
+                options.block = Some(Some(Duration::from_millis(ms)).filter(|_| ms > 0));
+            }
+            "noack" if noack_allowed => options.noack = true,
+            "streams" => return Ok(options),
+            unknown => return Err(format!("Unknown option: {}", unknown).into()),
+        }
+    }
+}
+
+/// The keys after STREAMS, followed by as many IDs for `parse` to read.
+fn streams<I, T, F>(args: I, parse: F) -> Result<(Vec<Vec<u8>>, Vec<T>), ApplicationError>
+where
+    I: Iterator<Item = Vec<u8>>,
+    F: Fn(&[u8]) -> Result<T, ApplicationError>,
+{
+    let mut keys: Vec<Vec<u8>> = args.collect();
+    if keys.is_empty() || !keys.len().is_multiple_of(2) {
+        return Err(String::from("Every stream needs an ID to read after").into());
+    }
+    let ids = keys
+        .split_off(keys.len() / 2)
+        .iter()
+        .map(|id| parse(id))
+        .collect::<Result<Vec<T>, ApplicationError>>()?;
+    Ok((keys, ids))
+}
+
+/// An ID to read after, or `$` for a stream's last.
+fn parse_after(arg: &[u8]) -> Result<After, ApplicationError> {
+    match arg {
+        b"$" => Ok(After::Last),
+        id => parse_stream_id(id, 0).map(After::Id),
+    }
+}
+
+fn parse_milliseconds(arg: &[u8]) -> Result<u64, ApplicationError> {
+    match std::str::from_utf8(arg).map(str::parse::<u64>) {
+        Ok(Ok(ms)) => Ok(ms),
+        _ => Err(String::from("Times must be non-negative integers of milliseconds").into()),
+    }
+}
+
+/// The keys of a blocking command followed by its timeout.
+fn keys_and_timeout<I>(args: I) -> Result<(Vec<Vec<u8>>, Option<Duration>), ApplicationError>
+where
+    I: Iterator<Item = Vec<u8>>,
+{
+    let mut keys: Vec<Vec<u8>> = args.collect();
+    let timeout = parse_timeout(&keys.pop().fail_to("No timeout provided")?)?;
+    if keys.is_empty() {
+        return Err(String::from("No key provided").into());
+    }
+    Ok((keys, timeout))
+}
+
+fn parse_end(arg: &[u8]) -> Result<End, ApplicationError> {
+    match lowercase(arg).as_str() {
+        "left" => Ok(End::Left),
+        "right" => Ok(End::Right),
+        unknown => Err(format!("Unknown list end: {}", unknown).into()),
+    }
+}
+
+/// EX, PX, EXAT or PXAT, as SET and GETEX take them, which must be
+/// followed by a positive time.
+fn parse_expiry<I>(option: &str, args: &mut I) -> Result<When, ApplicationError>
+where
+    I: Iterator<Item = Vec<u8>>,
+{
+    let time = args.next().fail_to("No expire time provided")?;
+    match parse_when(&time, option.starts_with('p'), option.ends_with("at"))? {
+        When::In(ms) | When::At(ms) if ms <= 0 => {
+            Err(String::from("Expire time must be positive").into())
+        }
+        when => Ok(when),
+    }
+}
+
+/// Records a key's lifetime, of which only one can be given.
+fn set_once(lifetime: &mut Option<Lifetime>, given: Lifetime) -> Result<(), ApplicationError> {
+    match lifetime.replace(given) {
+        Some(_) => Err(String::from("Only one expiry option can be given").into()),
+        None => Ok(()),
+    }
+}
+
+/// A deadline in seconds or, with `milliseconds`, milliseconds, counted
+/// from now or, with `at`, from the Unix epoch.
+fn parse_when(arg: &[u8], milliseconds: bool, at: bool) -> Result<When, ApplicationError> {
+    let time = parse_integer(arg, "Expire time")?;
+    let ms = if milliseconds {
+        time
     } else {
-        Err(ApplicationError::Error("Unknown Type".into()))
+        time.checked_mul(1000).fail_to("Invalid expire time")?
+    };
+    Ok(if at { When::At(ms) } else { When::In(ms) })
+}
+
+/// The start, end and unit BITCOUNT and BITPOS take, where BITCOUNT needs
+/// an end whenever it has a start.
+fn parse_bit_range<I>(mut args: I, end_required: bool) -> Result<bits::Range, ApplicationError>
+where
+    I: Iterator<Item = Vec<u8>>,
+{
+    let mut range = bits::Range::ALL;
+    if let Some(start) = args.next() {
+        range.start = parse_integer(&start, "Start")?;
+        match args.next() {
+            Some(end) => range.end = Some(parse_integer(&end, "End")?),
+            None if end_required => return Err(String::from("No end provided").into()),
+            None => {}
+        }
+        if let Some(unit) = args.next() {
+            range.unit = match lowercase(&unit).as_str() {
+                "byte" => Unit::Byte,
+                "bit" => Unit::Bit,
+                unknown => return Err(format!("Unknown range unit: {}", unknown).into()),
+            };
+        }
+    }
+    Ok(range)
+}
+
+/// BITFIELD's subcommands, each taking the overflow mode last set. Only
+/// GET is allowed when `read_only`, for BITFIELD_RO.
+fn parse_bitfield<I>(mut args: I, read_only: bool) -> Result<Command, ApplicationError>
+where
+    I: Iterator<Item = Vec<u8>>,
+{
+    let key = args.next().fail_to("No key provided")?;
+    let mut ops = Vec::new();
+    let mut overflow = bitfield::Overflow::Wrap;
+    while let Some(subcommand) = args.next() {
+        let subcommand = lowercase(&subcommand);
+        if read_only && subcommand != "get" {
+            return Err(String::from("BITFIELD_RO only supports GET").into());
+        }
+        let mut field = || {
+            let kind = args.next().fail_to("No type provided")?;
+            let offset = args.next().fail_to("No offset provided")?;
+            parse_field(&kind, &offset)
+        };
+        match subcommand.as_str() {
+            "get" => ops.push(bitfield::Op::Get(field()?)),
+            "set" => {
+                let field = field()?;
+                let value = parse_integer(&args.next().fail_to("No value provided")?, "Value")?;
+                ops.push(bitfield::Op::Set(field, value, overflow));
+            }
+            "incrby" => {
+                let field = field()?;
+                let by =
+                    parse_integer(&args.next().fail_to("No increment provided")?, "Increment")?;
+                ops.push(bitfield::Op::Incrby(field, by, overflow));
+            }
+            "overflow" => {
+                overflow =
+                    match lowercase(&args.next().fail_to("No overflow mode provided")?).as_str() {
+                        "wrap" => bitfield::Overflow::Wrap,
+                        "sat" => bitfield::Overflow::Sat,
+                        "fail" => bitfield::Overflow::Fail,
+                        unknown => return Err(format!("Unknown overflow mode: {}", unknown).into()),
+                    }
+            }
+            unknown => return Err(format!("Unknown BITFIELD subcommand: {}", unknown).into()),
+        }
+    }
+    Ok(Command::Bitfield(key, ops))
+}
+
+/// A type such as `i16` or `u8` and an offset in bits or, prefixed with
+/// `#`, in multiples of the type's width.
+fn parse_field(kind: &[u8], offset: &[u8]) -> Result<bitfield::Field, ApplicationError> {
+    let kind = lowercase(kind);
+    let bits = kind.get(1..).and_then(|bits| bits.parse::<u32>().ok());
+    let (signed, bits) = match (kind.chars().next(), bits) {
+        (Some('i'), Some(bits @ 1..=64)) => (true, bits),
+        (Some('u'), Some(bits @ 1..=63)) => (false, bits),
+        _ => {
+            return Err(String::from(
+                "Invalid bitfield type; use something like i16 or u8, where u64 isn't supported but i64 is",
+            )
+            .into())
+        }
+    };
+    let offset = match offset.strip_prefix(b"#") {
+        Some(index) => check_bit_offset(parse_bit_offset(index)?.saturating_mul(u64::from(bits)))?,
+        None => parse_bit_offset(offset)?,
+    };
+    Ok(bitfield::Field {
+        signed,
+        bits,
+        offset,
+    })
+}
+
+fn parse_bit_offset(arg: &[u8]) -> Result<u64, ApplicationError> {
+    match std::str::from_utf8(arg).map(str::parse::<u64>) {
+        Ok(Ok(offset)) => check_bit_offset(offset),
+        _ => check_bit_offset(u64::MAX),
+    }
+}
+
+fn check_bit_offset(offset: u64) -> Result<u64, ApplicationError> {
+    if offset < (string::MAX_LEN as u64) * 8 {
+        Ok(offset)
+    } else {
+        Err(String::from("Bit offset is not an integer or out of range").into())
+    }
+}
+
+fn parse_bit(arg: &[u8]) -> Result<bool, ApplicationError> {
+    match arg {
+        b"0" => Ok(false),
+        b"1" => Ok(true),
+        _ => Err(String::from("Bit must be 0 or 1").into()),
+    }
+}
+
+/// Timeouts are in seconds and may be fractional. Zero means no timeout.
+fn parse_timeout(arg: &[u8]) -> Result<Option<Duration>, ApplicationError> {
+    match std::str::from_utf8(arg).map(str::parse::<f64>) {
+        Ok(Ok(0.0)) => Ok(None),
+        // Redis wants the timeout in milliseconds to fit in 64 bits.
+        Ok(Ok(seconds)) if seconds > 0.0 && seconds * 1000.0 < i64::MAX as f64 => {
+            Ok(Some(Duration::from_secs_f64(seconds)))
+        }
+        Ok(Ok(seconds)) if seconds > 0.0 => Err(String::from("Timeout is out of range").into()),
+        Ok(Ok(seconds)) if seconds < 0.0 => {
+            Err(String::from("Timeout must not be negative").into())
+        }
+        _ => Err(String::from("Timeout is not a number").into()),
+    }
+}
+
+/// Command names and options are matched case insensitively.
+fn lowercase(arg: &[u8]) -> String {
+    String::from_utf8_lossy(arg).to_ascii_lowercase()
+}
+
+fn parse_integer(arg: &[u8], name: &str) -> Result<i64, ApplicationError> {
+    match std::str::from_utf8(arg).map(str::parse::<i64>) {
+        Ok(Ok(n)) => Ok(n),
+        _ => Err(format!("{} is not an integer", name).into()),
+    }
+}
+
+fn parse_count(arg: &[u8], name: &str) -> Result<usize, ApplicationError> {
+    match std::str::from_utf8(arg).map(str::parse::<usize>) {
+        Ok(Ok(n)) => Ok(n),
+        _ => Err(format!("{} must be a non-negative integer", name).into()),
     }
 }
 
 #[cfg(test)]
 mod test {
-    use super::super::domain::Primitive;
-    use super::parse_primitive;
+    use super::super::domain::Escaped;
+    use super::super::resp::Frame;
+    use super::tokenize;
+    use proptest::collection::vec;
     use proptest::prelude::*;
 
+    fn escaped(arg: &[u8]) -> String {
+        let escapes: String = arg.iter().map(|b| format!("\\x{:02x}", b)).collect();
+        format!("\"{}\"", escapes)
+    }
+
     proptest! {
         #[test]
-        fn quoted_strings_are_valid(s in "\".*\"") {
-            assert_eq!(parse_primitive(&s)?, Primitive::String(s[1..s.len()-1].to_string()))
+        fn quoted_strings_are_unquoted(s in "\"[^\\s\"\\\\]*\"") {
+            assert_eq!(tokenize(s.as_bytes())?, Frame::Array(vec![Frame::Bulk(s[1..s.len()-1].into())]))
         }
     }
 
     proptest! {
         #[test]
-        fn only_quoted_strings_are_valid(s in "[^\"0-9].*") {
-            parse_primitive(&s).unwrap_err()
+        fn escaped_bytes_round_trip(args in vec(vec(any::<u8>(), 0..32), 1..8)) {
+            let line = args.iter().map(|arg| escaped(arg)).collect::<Vec<String>>().join(" ");
+            let expected = args.into_iter().map(Frame::Bulk).collect();
+            assert_eq!(tokenize(line.as_bytes())?, Frame::Array(expected))
         }
     }
 
     proptest! {
         #[test]
-        fn numbers_are_valid(n in -1000i64..1000) {
-            parse_primitive(&format!("{}", n))?
+        fn unterminated_quotes_are_invalid(s in "[\"'][^\"'\\\\]*") {
+            tokenize(s.as_bytes()).unwrap_err();
+        }
+    }
+
+    #[test]
+    fn quoted_arguments_keep_their_spaces() {
+        let tokens = tokenize(br#"set  "a key" 'it\'s' "\x41\n""#).unwrap();
+        let expected = vec!["set", "a key", "it's", "A\n"]
+            .into_iter()
+            .map(|arg| Frame::Bulk(arg.into()))
+            .collect();
+        assert_eq!(tokens, Frame::Array(expected))
+    }
+
+    proptest! {
+        #[test]
+        fn escaped_rendering_is_lossless(arg in vec(any::<u8>(), 0..64)) {
+            let rendered = format!("\"{}\"", Escaped(&arg));
+            assert_eq!(tokenize(rendered.as_bytes())?, Frame::Array(vec![Frame::Bulk(arg)]))
         }
     }
 }
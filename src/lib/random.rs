@@ -0,0 +1,50 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+
+/// A xorshift generator, which is plenty for the random choices the server
+/// makes: which keys to check for expiry, how tall skiplist nodes grow and
+/// which set members to pick.
+#[derive(Debug, Clone)]
+pub struct Random(u64);
+
+impl Random {
+    /// A generator seeded from the random keys the standard library gives
+    /// hash maps, so no two start out alike.
+    pub fn new() -> Self {
+        Random::with_seed(RandomState::new().build_hasher().finish())
+    }
+
+    /// A generator that always produces the same numbers for `seed`.
+    pub fn with_seed(seed: u64) -> Self {
+        // Xorshift gets stuck at 0.
+        Random(seed | 1)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// A number from 0 up to but not including `n`.
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Random;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn numbers_stay_below_the_bound(seed in any::<u64>(), n in 1usize..1000) {
+            let mut random = Random::with_seed(seed);
+            for _ in 0..100 {
+                prop_assert!(random.below(n) < n);
+            }
+        }
+    }
+}
@@ -0,0 +1,556 @@
+use super::domain::Reply;
+use super::errors::ApplicationError;
+
+const MAX_BULK_LEN: usize = 512 * 1024 * 1024;
+const MAX_PREALLOCATED_ITEMS: usize = 1024;
+const MAX_INLINE_LEN: usize = 64 * 1024;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Frame {
+    Simple(String),
+    Error(String),
+    Integer(i64),
+    Bulk(Vec<u8>),
+    NullBulk,
+    Array(Vec<Frame>),
+    NullArray,
+    Null,
+    Map(Vec<(Frame, Frame)>),
+    Set(Vec<Frame>),
+    Double(f64),
+    Boolean(bool),
+    BigNumber(String),
+    Verbatim(String, Vec<u8>),
+    Push(Vec<Frame>),
+}
+
+/// The protocol version a connection speaks, as negotiated with HELLO.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Protocol {
+    Resp2,
+    Resp3,
+}
+
+impl Protocol {
+    pub fn version(self) -> i64 {
+        match self {
+            Protocol::Resp2 => 2,
+            Protocol::Resp3 => 3,
+        }
+    }
+
+    /// Encodes a reply using the richest frame types the protocol has,
+    /// falling back to the RESP2 equivalents Redis uses for older clients.
+    pub fn frame(self, reply: Reply) -> Frame {
+        let frames = |items: Vec<Reply>| -> Vec<Frame> {
+            items.into_iter().map(|i| self.frame(i)).collect()
+        };
+        match (self, reply) {
+            (_, Reply::Simple(s)) => Frame::Simple(s),
+            (_, Reply::Error(e)) => Frame::Error(e),
+            (_, Reply::Integer(n)) => Frame::Integer(n),
+            (_, Reply::Bulk(s)) => Frame::Bulk(s),
+            (_, Reply::Array(items)) => Frame::Array(frames(items)),
+            (Protocol::Resp2, Reply::Nil) => Frame::NullBulk,
+            (Protocol::Resp2, Reply::NullArray) => Frame::NullArray,
+            (Protocol::Resp2, Reply::Set(items)) => Frame::Array(frames(items)),
+            (Protocol::Resp2, Reply::Map(entries)) => Frame::Array(
+                entries
+                    .into_iter()
+                    .flat_map(|(k, v)| vec![self.frame(k), self.frame(v)])
+                    .collect(),
+            ),
+            (Protocol::Resp2, Reply::Double(d)) => Frame::Bulk(format_double(d).into_bytes()),
+            (Protocol::Resp2, Reply::Boolean(b)) => Frame::Integer(if b { 1 } else { 0 }),
+            (Protocol::Resp3, Reply::Nil | Reply::NullArray) => Frame::Null,
+            (Protocol::Resp3, Reply::Set(items)) => Frame::Set(frames(items)),
+            (Protocol::Resp3, Reply::Map(entries)) => Frame::Map(
+                entries
+                    .into_iter()
+                    .map(|(k, v)| (self.frame(k), self.frame(v)))
+                    .collect(),
+            ),
+            (Protocol::Resp3, Reply::Double(d)) => Frame::Double(d),
+            (Protocol::Resp3, Reply::Boolean(b)) => Frame::Boolean(b),
+        }
+    }
+}
+
+pub fn encode(frame: &Frame, out: &mut Vec<u8>) {
+    match frame {
+        Frame::Simple(s) => out.extend(format!("+{}\r\n", s).bytes()),
+        Frame::Error(e) => out.extend(format!("-{}\r\n", e).bytes()),
+        Frame::Integer(n) => out.extend(format!(":{}\r\n", n).bytes()),
+        Frame::Bulk(bytes) => {
+            out.extend(format!("${}\r\n", bytes.len()).bytes());
+            out.extend(bytes);
+            out.extend(b"\r\n");
+        }
+        Frame::NullBulk => out.extend(b"$-1\r\n"),
+        Frame::Array(items) => encode_aggregate('*', items, out),
+        Frame::NullArray => out.extend(b"*-1\r\n"),
+        Frame::Null => out.extend(b"_\r\n"),
+        Frame::Map(entries) => {
+            out.extend(format!("%{}\r\n", entries.len()).bytes());
+            entries.iter().for_each(|(k, v)| {
+                encode(k, out);
+                encode(v, out);
+            });
+        }
+        Frame::Set(items) => encode_aggregate('~', items, out),
+        Frame::Double(d) => out.extend(format!(",{}\r\n", format_double(*d)).bytes()),
+        Frame::Boolean(b) => out.extend(if *b { b"#t\r\n" } else { b"#f\r\n" }),
+        Frame::BigNumber(n) => out.extend(format!("({}\r\n", n).bytes()),
+        Frame::Verbatim(format, text) => {
+            out.extend(format!("={}\r\n{}:", text.len() + 4, format).bytes());
+            out.extend(text);
+            out.extend(b"\r\n");
+        }
+        Frame::Push(items) => encode_aggregate('>', items, out),
+    }
+}
+
+fn encode_aggregate(kind: char, items: &[Frame], out: &mut Vec<u8>) {
+    out.extend(format!("{}{}\r\n", kind, items.len()).bytes());
+    items.iter().for_each(|item| encode(item, out));
+}
+
+/// Renders a double the way Redis does: the shortest digits that read back
+/// as the same number, written out in full unless `%.17g` would switch to
+/// an exponent, as it does for very large and very small magnitudes.
+fn format_double(d: f64) -> String {
+    if d.is_nan() {
+        return String::from("nan");
+    } else if d.is_infinite() {
+        return String::from(if d > 0.0 { "inf" } else { "-inf" });
+    }
+    let scientific = format!("{:e}", d);
+    let (mantissa, exponent) = scientific.split_once('e').unwrap_or((&scientific, "0"));
+    let exponent: i32 = exponent.parse().unwrap_or(0);
+    if (-4..17).contains(&exponent) {
+        format!("{}", d)
+    } else {
+        let sign = if exponent < 0 { '-' } else { '+' };
+        format!("{}e{}{:02}", mantissa, sign, exponent.abs())
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Aggregate {
+    Array,
+    Map,
+    Set,
+    Push,
+}
+
+/// An aggregate whose header has been read but whose items are still arriving.
+struct PartialAggregate {
+    kind: Aggregate,
+    expected: usize,
+    items: Vec<Frame>,
+}
+
+impl PartialAggregate {
+    fn into_frame(self) -> Frame {
+        match self.kind {
+            Aggregate::Array => Frame::Array(self.items),
+            Aggregate::Set => Frame::Set(self.items),
+            Aggregate::Push => Frame::Push(self.items),
+            Aggregate::Map => {
+                let mut items = self.items.into_iter();
+                let mut entries = Vec::with_capacity(self.expected / 2);
+                while let (Some(k), Some(v)) = (items.next(), items.next()) {
+                    entries.push((k, v));
+                }
+                Frame::Map(entries)
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Blob {
+    Bulk,
+    Verbatim,
+}
+
+/// Incrementally decodes frames out of a byte stream. Bytes are fed in with
+/// `extend` as they arrive, and everything already decoded is kept between
+/// calls, so a frame split over many reads is only ever parsed once.
+#[derive(Default)]
+pub struct Decoder {
+    buffer: Vec<u8>,
+    position: usize,
+    scanned: usize,
+    pending_blob: Option<(Blob, usize)>,
+    aggregates: Vec<PartialAggregate>,
+}
+
+impl Decoder {
+    pub fn new() -> Self {
+        Decoder::default()
+    }
+
+    pub fn extend(&mut self, bytes: &[u8]) {
+        if self.position > 0 && self.position == self.buffer.len() {
+            self.buffer.clear();
+            self.scanned = 0;
+            self.position = 0;
+        }
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /// Whether the decoder holds no bytes of an incomplete frame.
+    pub fn is_empty(&self) -> bool {
+        self.position == self.buffer.len()
+            && self.pending_blob.is_none()
+            && self.aggregates.is_empty()
+    }
+
+    /// Whether the next request is an inline command rather than a RESP
+    /// array. Like Redis, this is decided by the first byte alone.
+    pub fn is_inline(&self) -> bool {
+        self.pending_blob.is_none()
+            && self.aggregates.is_empty()
+            && self.buffer.get(self.position).is_some_and(|b| *b != b'*')
+    }
+
+    /// Returns the next inline command line without its line ending, or
+    /// `None` if the rest of the line has yet to arrive.
+    pub fn take_inline(&mut self) -> Result<Option<Vec<u8>>, ApplicationError> {
+        let from = self.scanned.max(self.position);
+        match self.buffer[from..].iter().position(|b| *b == b'\n') {
+            Some(offset) => {
+                let end = from + offset;
+                let mut line = self.buffer[self.position..end].to_vec();
+                if line.last() == Some(&b'\r') {
+                    line.pop();
+                }
+                self.position = end + 1;
+                self.scanned = self.position;
+                self.compact();
+                Ok(Some(line))
+            }
+            None if self.buffer.len() - self.position > MAX_INLINE_LEN => {
+                Err(String::from("Protocol error: too big inline request").into())
+            }
+            None => {
+                self.scanned = self.buffer.len();
+                Ok(None)
+            }
+        }
+    }
+
+    /// Returns the next complete frame, or `None` if more bytes are needed.
+    pub fn decode(&mut self) -> Result<Option<Frame>, ApplicationError> {
+        loop {
+            let frame = if let Some((blob, len)) = self.pending_blob {
+                match self.take_blob(len)? {
+                    Some(bytes) => {
+                        self.pending_blob = None;
+                        blob_frame(blob, bytes)?
+                    }
+                    None => return Ok(None),
+                }
+            } else {
+                let line = match self.take_line() {
+                    Some(line) => line,
+                    None => return Ok(None),
+                };
+                match self.start_frame(&line)? {
+                    Some(frame) => frame,
+                    None => continue,
+                }
+            };
+            if let Some(frame) = self.complete(frame) {
+                self.compact();
+                return Ok(Some(frame));
+            }
+        }
+    }
+
+    /// Interprets a header line, returning the frame if it is already complete.
+    fn start_frame(&mut self, line: &[u8]) -> Result<Option<Frame>, ApplicationError> {
+        let (kind, rest) = match line.split_first() {
+            Some((kind, rest)) => (*kind, String::from_utf8_lossy(rest).into_owned()),
+            None => return Err(String::from("Protocol error: empty line").into()),
+        };
+        match kind {
+            b'+' => Ok(Some(Frame::Simple(rest))),
+            b'-' => Ok(Some(Frame::Error(rest))),
+            b':' => Ok(Some(Frame::Integer(parse_integer(&rest)?))),
+            b'$' => self.start_blob(Blob::Bulk, &rest),
+            b'=' => self.start_blob(Blob::Verbatim, &rest),
+            b'*' => self.start_aggregate(Aggregate::Array, &rest),
+            b'%' => self.start_aggregate(Aggregate::Map, &rest),
+            b'~' => self.start_aggregate(Aggregate::Set, &rest),
+            b'>' => self.start_aggregate(Aggregate::Push, &rest),
+            b'_' if rest.is_empty() => Ok(Some(Frame::Null)),
+            b',' => rest
+                .parse()
+                .map(|d| Some(Frame::Double(d)))
+                .map_err(|_| format!("Protocol error: invalid double {:?}", rest).into()),
+            b'#' => match rest.as_str() {
+                "t" => Ok(Some(Frame::Boolean(true))),
+                "f" => Ok(Some(Frame::Boolean(false))),
+                _ => Err(format!("Protocol error: invalid boolean {:?}", rest).into()),
+            },
+            b'(' => {
+                let digits = rest.strip_prefix('-').unwrap_or(&rest);
+                if !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()) {
+                    Ok(Some(Frame::BigNumber(rest)))
+                } else {
+                    Err(format!("Protocol error: invalid big number {:?}", rest).into())
+                }
+            }
+            other => Err(format!(
+                "Protocol error: unexpected frame type '{}'",
+                char::from(other).escape_default()
+            )
+            .into()),
+        }
+    }
+
+    fn start_blob(&mut self, blob: Blob, len: &str) -> Result<Option<Frame>, ApplicationError> {
+        match parse_integer(len)? {
+            -1 if blob == Blob::Bulk => Ok(Some(Frame::NullBulk)),
+            len if len < 0 || len as usize > MAX_BULK_LEN => {
+                Err(String::from("Protocol error: invalid bulk length").into())
+            }
+            len => {
+                self.pending_blob = Some((blob, len as usize));
+                Ok(None)
+            }
+        }
+    }
+
+    fn start_aggregate(
+        &mut self,
+        kind: Aggregate,
+        len: &str,
+    ) -> Result<Option<Frame>, ApplicationError> {
+        let len = match parse_integer(len)? {
+            -1 if kind == Aggregate::Array => return Ok(Some(Frame::NullArray)),
+            len if len < 0 => {
+                return Err(String::from("Protocol error: invalid multibulk length").into())
+            }
+            len => len as usize,
+        };
+        let expected = if kind == Aggregate::Map { len * 2 } else { len };
+        let aggregate = PartialAggregate {
+            kind,
+            expected,
+            items: Vec::with_capacity(expected.min(MAX_PREALLOCATED_ITEMS)),
+        };
+        if expected == 0 {
+            Ok(Some(aggregate.into_frame()))
+        } else {
+            self.aggregates.push(aggregate);
+            Ok(None)
+        }
+    }
+
+    /// Files a finished frame into the aggregate being built, closing every
+    /// aggregate it completes. Returns the frame once there is nothing left
+    /// to nest it in.
+    fn complete(&mut self, mut frame: Frame) -> Option<Frame> {
+        while let Some(mut aggregate) = self.aggregates.pop() {
+            aggregate.items.push(frame);
+            if aggregate.items.len() < aggregate.expected {
+                self.aggregates.push(aggregate);
+                return None;
+            }
+            frame = aggregate.into_frame();
+        }
+        Some(frame)
+    }
+
+    fn take_line(&mut self) -> Option<Vec<u8>> {
+        let unscanned = &self.buffer[self.scanned..];
+        match unscanned.windows(2).position(|window| window == b"\r\n") {
+            Some(offset) => {
+                let end = self.scanned + offset;
+                let line = self.buffer[self.position..end].to_vec();
+                self.position = end + 2;
+                self.scanned = self.position;
+                Some(line)
+            }
+            None => {
+                self.scanned = self.buffer.len().saturating_sub(1).max(self.position);
+                None
+            }
+        }
+    }
+
+    fn take_blob(&mut self, len: usize) -> Result<Option<Vec<u8>>, ApplicationError> {
+        if self.buffer.len() - self.position < len + 2 {
+            return Ok(None);
+        }
+        let end = self.position + len;
+        if &self.buffer[end..end + 2] != b"\r\n" {
+            return Err(
+                String::from("Protocol error: bulk string is not terminated by CRLF").into(),
+            );
+        }
+        let bytes = self.buffer[self.position..end].to_vec();
+        self.position = end + 2;
+        self.scanned = self.position;
+        Ok(Some(bytes))
+    }
+
+    fn compact(&mut self) {
+        if self.position > 0 && self.position * 2 >= self.buffer.len() {
+            self.buffer.drain(..self.position);
+            self.scanned -= self.position;
+            self.position = 0;
+        }
+    }
+}
+
+fn blob_frame(blob: Blob, mut bytes: Vec<u8>) -> Result<Frame, ApplicationError> {
+    match blob {
+        Blob::Bulk => Ok(Frame::Bulk(bytes)),
+        Blob::Verbatim if bytes.len() >= 4 && bytes[3] == b':' => {
+            let text = bytes.split_off(4);
+            Ok(Frame::Verbatim(
+                String::from_utf8_lossy(&bytes[..3]).into_owned(),
+                text,
+            ))
+        }
+        Blob::Verbatim => Err(String::from("Protocol error: invalid verbatim string").into()),
+    }
+}
+
+fn parse_integer(digits: &str) -> Result<i64, ApplicationError> {
+    digits
+        .parse()
+        .map_err(|_| format!("Protocol error: invalid integer {:?}", digits).into())
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::domain::Reply;
+    use super::{encode, Decoder, Frame, Protocol};
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    fn frames() -> impl Strategy<Value = Frame> {
+        let leaf = prop_oneof![
+            "[^\r\n]*".prop_map(Frame::Simple),
+            "[^\r\n]*".prop_map(Frame::Error),
+            any::<i64>().prop_map(Frame::Integer),
+            vec(any::<u8>(), 0..64).prop_map(Frame::Bulk),
+            Just(Frame::NullBulk),
+            Just(Frame::NullArray),
+            Just(Frame::Null),
+            any::<f64>()
+                .prop_filter("NaN never compares equal", |d| !d.is_nan())
+                .prop_map(Frame::Double),
+            any::<bool>().prop_map(Frame::Boolean),
+            "-?[0-9]{1,40}".prop_map(Frame::BigNumber),
+            ("[a-z]{3}", vec(any::<u8>(), 0..64))
+                .prop_map(|(format, text)| Frame::Verbatim(format, text)),
+        ];
+        leaf.prop_recursive(4, 64, 8, |inner| {
+            prop_oneof![
+                vec(inner.clone(), 0..8).prop_map(Frame::Array),
+                vec(inner.clone(), 0..8).prop_map(Frame::Set),
+                vec(inner.clone(), 0..8).prop_map(Frame::Push),
+                vec((inner.clone(), inner), 0..4).prop_map(Frame::Map),
+            ]
+        })
+    }
+
+    fn replies() -> impl Strategy<Value = Reply> {
+        let leaf = prop_oneof![
+            "[^\r\n]*".prop_map(Reply::Simple),
+            any::<i64>().prop_map(Reply::Integer),
+            vec(any::<u8>(), 0..64).prop_map(Reply::Bulk),
+            Just(Reply::Nil),
+            Just(Reply::NullArray),
+            any::<f64>().prop_map(Reply::Double),
+            any::<bool>().prop_map(Reply::Boolean),
+        ];
+        leaf.prop_recursive(4, 64, 8, |inner| {
+            prop_oneof![
+                vec(inner.clone(), 0..8).prop_map(Reply::Array),
+                vec(inner.clone(), 0..8).prop_map(Reply::Set),
+                vec((inner.clone(), inner), 0..4).prop_map(Reply::Map),
+            ]
+        })
+    }
+
+    fn is_resp2(frame: &Frame) -> bool {
+        match frame {
+            Frame::Simple(_) | Frame::Error(_) | Frame::Integer(_) | Frame::Bulk(_) => true,
+            Frame::NullBulk | Frame::NullArray => true,
+            Frame::Array(items) => items.iter().all(is_resp2),
+            _ => false,
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn frames_round_trip(frame in frames()) {
+            let mut encoded = Vec::new();
+            encode(&frame, &mut encoded);
+            let mut decoder = Decoder::new();
+            decoder.extend(&encoded);
+            assert_eq!(decoder.decode()?, Some(frame));
+            assert_eq!(decoder.decode()?, None);
+            assert!(decoder.is_empty())
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn split_frames_round_trip(frames in vec(frames(), 1..8), chunk_size in 1usize..16) {
+            let mut encoded = Vec::new();
+            frames.iter().for_each(|frame| encode(frame, &mut encoded));
+            let mut decoder = Decoder::new();
+            let mut decoded = Vec::new();
+            for chunk in encoded.chunks(chunk_size) {
+                decoder.extend(chunk);
+                while let Some(frame) = decoder.decode()? {
+                    decoded.push(frame);
+                }
+            }
+            assert_eq!(decoded, frames);
+            assert!(decoder.is_empty())
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn unknown_frame_types_are_rejected(garbage in "[^-+:$*=%~>_,#(]\r\n") {
+            let mut decoder = Decoder::new();
+            decoder.extend(garbage.as_bytes());
+            decoder.decode().unwrap_err();
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn resp2_replies_only_use_resp2_frames(reply in replies()) {
+            assert!(is_resp2(&Protocol::Resp2.frame(reply)))
+        }
+    }
+
+    #[test]
+    fn doubles_switch_to_exponents_like_redis() {
+        let encoded = |protocol: Protocol, d: f64| {
+            let mut out = Vec::new();
+            encode(&protocol.frame(Reply::Double(d)), &mut out);
+            String::from_utf8(out).unwrap()
+        };
+        assert_eq!(encoded(Protocol::Resp2, 1e300), "$6\r\n1e+300\r\n");
+        assert_eq!(encoded(Protocol::Resp2, 1e-300), "$6\r\n1e-300\r\n");
+        assert_eq!(encoded(Protocol::Resp3, 1e300), ",1e+300\r\n");
+        assert_eq!(encoded(Protocol::Resp3, -1.5e-300), ",-1.5e-300\r\n");
+        assert_eq!(encoded(Protocol::Resp3, 1.25e-5), ",1.25e-05\r\n");
+        assert_eq!(encoded(Protocol::Resp3, 0.0001), ",0.0001\r\n");
+        assert_eq!(encoded(Protocol::Resp3, 2.5), ",2.5\r\n");
+        assert_eq!(encoded(Protocol::Resp3, 1e16), ",10000000000000000\r\n");
+        assert_eq!(encoded(Protocol::Resp3, 1e17), ",1e+17\r\n");
+    }
+}
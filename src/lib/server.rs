@@ -0,0 +1,608 @@
+use super::domain::{Command, Reply};
+use super::errors::ApplicationError;
+use super::execute::{arrive, serve_blocked, Arrival};
+use super::keyspace::Keyspace;
+use super::parse::{parse_cmd, tokenize};
+use super::resp::{encode, Decoder, Frame, Protocol};
+use std::io::{ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+const READ_BUFFER_SIZE: usize = 16 * 1024;
+const MAX_PENDING_OUTPUT: usize = 64 * 1024;
+
+/// How often expired keys are looked for in the background, which is ten
+/// times a second as with Redis's default `hz`.
+const ACTIVE_EXPIRE_INTERVAL: Duration = Duration::from_millis(100);
+
+pub fn serve(listener: TcpListener) -> Result<(), ApplicationError> {
+    serve_keyspace(listener, Arc::new(Keyspace::new()))
+}
+
+/// Serves clients from `keyspace`, expiring its keys in the background for
+/// as long as it is around.
+pub fn serve_keyspace(
+    listener: TcpListener,
+    keyspace: Arc<Keyspace>,
+) -> Result<(), ApplicationError> {
+    let expiring = Arc::downgrade(&keyspace);
+    thread::spawn(move || loop {
+        thread::sleep(ACTIVE_EXPIRE_INTERVAL);
+        match expiring.upgrade() {
+            Some(keyspace) => keyspace.expire_actively(),
+            None => return,
+        }
+    });
+    for (id, stream) in listener.incoming().enumerate() {
+        let stream = stream?;
+        let keyspace = Arc::clone(&keyspace);
+        thread::spawn(move || {
+            if let Err(error) = handle_connection(&keyspace, id as i64 + 1, stream) {
+                eprintln!("connection closed: {}", error);
+            }
+        });
+    }
+    Ok(())
+}
+
+/// Serves one client. Every complete frame in the read buffer is run in
+/// order and the replies go back in a single write, so pipelined requests
+/// cost one round trip. The keyspace is only locked one command at a time,
+/// which keeps a long pipeline from stalling other clients. A blocked client
+/// gives the lock up while it waits.
+fn handle_connection(
+    keyspace: &Keyspace,
+    id: i64,
+    mut stream: TcpStream,
+) -> Result<(), ApplicationError> {
+    let mut protocol = Protocol::Resp2;
+    let mut decoder = Decoder::new();
+    let mut buffer = [0; READ_BUFFER_SIZE];
+    let mut out = Vec::new();
+    loop {
+        let read = stream.read(&mut buffer)?;
+        if read == 0 {
+            return Ok(());
+        }
+        decoder.extend(&buffer[..read]);
+        loop {
+            let frame = match next_request(&mut decoder) {
+                Ok(Some(Frame::Array(args))) if args.is_empty() => continue,
+                Ok(Some(frame)) => frame,
+                Ok(None) => break,
+                Err(error) => {
+                    let reply = Reply::Error(format!("ERR {}", error));
+                    encode(&protocol.frame(reply), &mut out);
+                    stream.write_all(&out)?;
+                    return Err(error);
+                }
+            };
+            let command = parse_cmd(frame);
+            if command
+                .as_ref()
+                .is_ok_and(|command| command.blocks_on().is_some())
+            {
+                // Replies to earlier requests shouldn't wait on a blocked one.
+                stream.write_all(&out)?;
+                out.clear();
+            }
+            let reply = command
+                .and_then(|command| match command {
+                    Command::Hello(version) => Ok(hello(&mut protocol, id, version)),
+                    command if command.blocks_on().is_some() => {
+                        match keyspace.with_store(|db, now| arrive(db, command, now))? {
+                            Arrival::Served(reply) => Ok(reply),
+                            Arrival::Waits(command) => block(keyspace, &stream, &command),
+                        }
+                    }
+                    command => keyspace.execute(command),
+                })
+                .unwrap_or_else(|error| Reply::Error(format!("ERR {}", error)));
+            encode(&protocol.frame(reply), &mut out);
+            if out.len() >= MAX_PENDING_OUTPUT {
+                stream.write_all(&out)?;
+                out.clear();
+            }
+        }
+        stream.write_all(&out)?;
+        out.clear();
+    }
+}
+
+/// Blocks a client until its command is served or gives up.
+fn block(
+    keyspace: &Keyspace,
+    stream: &TcpStream,
+    command: &Command,
+) -> Result<Reply, ApplicationError> {
+    let (keys, timeout) = command.blocks_on().unwrap_or((&[], None));
+    keyspace.block(
+        keys,
+        timeout,
+        command.waits_in_line(),
+        || hung_up(stream),
+        |db, key, now| serve_blocked(db, command, key, now),
+    )
+}
+
+/// Decodes the next request, which is either a RESP array or, as with
+/// telnet and `nc`, a single line holding an inline command.
+fn next_request(decoder: &mut Decoder) -> Result<Option<Frame>, ApplicationError> {
+    if decoder.is_inline() {
+        decoder
+            .take_inline()?
+            .map(|line| tokenize(&line))
+            .transpose()
+    } else {
+        decoder.decode()
+    }
+}
+
+/// Whether the client has closed its end of the connection, which a blocked
+/// client can only find out by peeking at it.
+fn hung_up(stream: &TcpStream) -> bool {
+    if stream.set_nonblocking(true).is_err() {
+        return true;
+    }
+    let closed = match stream.peek(&mut [0]) {
+        Ok(read) => read == 0,
+        Err(error) => error.kind() != ErrorKind::WouldBlock,
+    };
+    stream.set_nonblocking(false).is_err() || closed
+}
+
+/// Switches the connection to the requested protocol version, replying with
+/// a summary of the server the way Redis does.
+fn hello(protocol: &mut Protocol, id: i64, version: Option<i64>) -> Reply {
+    match version {
+        None => {}
+        Some(2) => *protocol = Protocol::Resp2,
+        Some(3) => *protocol = Protocol::Resp3,
+        Some(_) => return Reply::Error(String::from("NOPROTO unsupported protocol version")),
+    }
+    let field = |name: &str| Reply::Bulk(name.into());
+    Reply::Map(vec![
+        (field("server"), field("ruddis")),
+        (field("version"), field(env!("CARGO_PKG_VERSION"))),
+        (field("proto"), Reply::Integer(protocol.version())),
+        (field("id"), Reply::Integer(id)),
+        (field("mode"), field("standalone")),
+        (field("role"), field("master")),
+        (field("modules"), Reply::Array(vec![])),
+    ])
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::clock::ManualClock;
+    use super::super::domain::Escaped;
+    use super::super::keyspace::Keyspace;
+    use super::super::resp::{encode, Decoder, Frame};
+    use super::{serve, serve_keyspace};
+    use std::io::{Read, Write};
+    use std::net::{SocketAddr, TcpListener, TcpStream};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    fn start() -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        thread::spawn(move || serve(listener));
+        address
+    }
+
+    fn start_keyspace(keyspace: Arc<Keyspace>) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        thread::spawn(move || serve_keyspace(listener, keyspace));
+        address
+    }
+
+    struct Client {
+        stream: TcpStream,
+        decoder: Decoder,
+    }
+
+    impl Client {
+        fn connect(address: SocketAddr) -> Self {
+            let stream = TcpStream::connect(address).unwrap();
+            stream
+                .set_read_timeout(Some(Duration::from_secs(10)))
+                .unwrap();
+            Client {
+                stream,
+                decoder: Decoder::new(),
+            }
+        }
+
+        fn send<A: AsRef<[u8]>>(&mut self, args: &[A]) {
+            let mut out = Vec::new();
+            let request = args.iter().map(|arg| Frame::Bulk(arg.as_ref().to_vec()));
+            encode(&Frame::Array(request.collect()), &mut out);
+            self.stream.write_all(&out).unwrap();
+        }
+
+        fn receive(&mut self) -> Frame {
+            let mut buffer = [0; 1024];
+            loop {
+                if let Some(frame) = self.decoder.decode().unwrap() {
+                    return frame;
+                }
+                let read = self.stream.read(&mut buffer).unwrap();
+                assert_ne!(read, 0, "server hung up");
+                self.decoder.extend(&buffer[..read]);
+            }
+        }
+
+        fn call<A: AsRef<[u8]>>(&mut self, args: &[A]) -> Frame {
+            self.send(args);
+            self.receive()
+        }
+    }
+
+    fn bulk(s: &str) -> Frame {
+        Frame::Bulk(s.as_bytes().to_vec())
+    }
+
+    #[test]
+    fn replies_over_tcp() {
+        let mut client = Client::connect(start());
+        assert_eq!(client.call(&["SADD", "s", "foo"]), Frame::Integer(1));
+        assert_eq!(client.call(&["get", "k"]), Frame::NullBulk);
+        assert_eq!(
+            client.call(&["SUNION", "s", "s"]),
+            Frame::Array(vec![bulk("foo")])
+        );
+    }
+
+    #[test]
+    fn hello_negotiates_resp3_per_connection() {
+        let address = start();
+        let mut client = Client::connect(address);
+        let mut other = Client::connect(address);
+        match client.call(&["HELLO", "3"]) {
+            Frame::Map(entries) => assert!(entries.contains(&(bulk("proto"), Frame::Integer(3)))),
+            unexpected => panic!("expected a map, got {:?}", unexpected),
+        }
+        client.call(&["SADD", "s", "a"]);
+        assert_eq!(
+            client.call(&["SUNION", "s", "s"]),
+            Frame::Set(vec![bulk("a")])
+        );
+        assert_eq!(client.call(&["GET", "k"]), Frame::Null);
+        assert_eq!(other.call(&["GET", "k"]), Frame::NullBulk);
+
+        match client.call(&["HELLO", "2"]) {
+            Frame::Array(items) => assert_eq!(items.len(), 14),
+            unexpected => panic!("expected an array, got {:?}", unexpected),
+        }
+        assert_eq!(client.call(&["GET", "k"]), Frame::NullBulk);
+        assert_eq!(
+            client.call(&["HELLO", "4"]),
+            Frame::Error(String::from("NOPROTO unsupported protocol version"))
+        );
+        client.call(&["HELLO", "3"]);
+        drop(client);
+        assert_eq!(
+            Client::connect(address).call(&["GET", "k"]),
+            Frame::NullBulk
+        );
+    }
+
+    #[test]
+    fn clients_share_the_keyspace() {
+        let address = start();
+        let mut writer = Client::connect(address);
+        let mut reader = Client::connect(address);
+        writer.call(&["SADD", "s", "a"]);
+        assert_eq!(reader.call(&["SCARD", "s"]), Frame::Integer(1));
+    }
+
+    #[test]
+    fn concurrent_incrs_are_never_lost() {
+        const CLIENTS: usize = 16;
+        const INCRS: usize = 250;
+        let address = start();
+        let workers: Vec<_> = (0..CLIENTS)
+            .map(|_| {
+                thread::spawn(move || {
+                    let mut client = Client::connect(address);
+                    (0..INCRS).for_each(|_| {
+                        client.call(&["INCR", "counter"]);
+                    });
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .for_each(|worker| worker.join().unwrap());
+        assert_eq!(
+            Client::connect(address).call(&["GET", "counter"]),
+            bulk(&format!("{}", CLIENTS * INCRS))
+        );
+    }
+
+    #[test]
+    fn pipelined_replies_come_back_in_order() {
+        const PIPELINE: usize = 1000;
+        let mut client = Client::connect(start());
+        let mut request = Vec::new();
+        (0..PIPELINE).for_each(|_| {
+            let incr = vec![bulk("INCR"), bulk("counter")];
+            encode(&Frame::Array(incr), &mut request)
+        });
+        let mut partial = Vec::new();
+        encode(
+            &Frame::Array(vec![bulk("GET"), bulk("counter")]),
+            &mut partial,
+        );
+        let (head, tail) = partial.split_at(partial.len() / 2);
+        request.extend(head);
+        client.stream.write_all(&request).unwrap();
+
+        (1..=PIPELINE).for_each(|n| assert_eq!(client.receive(), Frame::Integer(n as i64)));
+        client.stream.write_all(tail).unwrap();
+        assert_eq!(client.receive(), bulk(&format!("{}", PIPELINE)));
+    }
+
+    #[test]
+    fn inline_commands_share_the_listener() {
+        let mut client = Client::connect(start());
+        client
+            .stream
+            .write_all(b"SADD s \"a b\" 'c'\r\n\r\nSCARD s\n")
+            .unwrap();
+        assert_eq!(client.receive(), Frame::Integer(2));
+        assert_eq!(client.receive(), Frame::Integer(2));
+        assert_eq!(client.call(&["SISMEMBER", "s", "a b"]), Frame::Integer(1));
+        client.stream.write_all(b"ECHO \"unbalanced\r\n").unwrap();
+        match client.receive() {
+            Frame::Error(_) => {}
+            unexpected => panic!("expected an error, got {:?}", unexpected),
+        }
+    }
+
+    #[test]
+    fn keys_and_values_are_binary_safe() {
+        let mut client = Client::connect(start());
+        let key: &[u8] = b"\xff\x00key";
+        let value: &[u8] = b"\x89PNG\r\n\x1a\n";
+        assert_eq!(
+            client.call(&[b"SET", key, value]),
+            Frame::Simple("OK".into())
+        );
+        assert_eq!(client.call(&[b"GET", key]), Frame::Bulk(value.to_vec()));
+        client.call(&[b"SADD", key, value]);
+        assert_eq!(
+            client.call(&[b"SUNION", key, key]),
+            Frame::Error(format!("ERR Value at {} is not a set", Escaped(key)))
+        );
+    }
+
+    /// Gives a client that just sent a blocking command time to reach the
+    /// server and queue up.
+    fn let_it_block() {
+        thread::sleep(Duration::from_millis(200));
+    }
+
+    #[test]
+    fn blocked_pops_are_served_in_order() {
+        let address = start();
+        let mut first = Client::connect(address);
+        let mut second = Client::connect(address);
+        let mut pusher = Client::connect(address);
+        first.send(&["LLEN", "queue"]);
+        first.send(&["BLPOP", "empty", "queue", "0"]);
+        assert_eq!(first.receive(), Frame::Integer(0));
+        let_it_block();
+        second.send(&["BLPOP", "queue", "0"]);
+        let_it_block();
+        assert_eq!(pusher.call(&["SET", "k", "v"]), Frame::Simple("OK".into()));
+        assert_eq!(
+            pusher.call(&["RPUSH", "queue", "a", "b"]),
+            Frame::Integer(2)
+        );
+        assert_eq!(
+            first.receive(),
+            Frame::Array(vec![bulk("queue"), bulk("a")])
+        );
+        assert_eq!(
+            second.receive(),
+            Frame::Array(vec![bulk("queue"), bulk("b")])
+        );
+        assert_eq!(pusher.call(&["LLEN", "queue"]), Frame::Integer(0));
+    }
+
+    #[test]
+    fn blocking_pops_time_out() {
+        let mut client = Client::connect(start());
+        let started = Instant::now();
+        assert_eq!(client.call(&["BRPOP", "queue", "0.1"]), Frame::NullArray);
+        assert!(started.elapsed() >= Duration::from_millis(100));
+        client.call(&["RPUSH", "queue", "a", "b"]);
+        assert_eq!(
+            client.call(&["BRPOP", "queue", "0.1"]),
+            Frame::Array(vec![bulk("queue"), bulk("b")])
+        );
+    }
+
+    #[test]
+    fn blocking_timeouts_must_be_in_range() {
+        let mut client = Client::connect(start());
+        for timeout in &["1e300", "1e19"] {
+            assert_eq!(
+                client.call(&["BLPOP", "queue", timeout]),
+                Frame::Error(String::from("ERR Timeout is out of range"))
+            );
+        }
+    }
+
+    #[test]
+    fn blocking_moves_wait_for_the_source() {
+        let address = start();
+        let mut mover = Client::connect(address);
+        let mut pusher = Client::connect(address);
+        mover.send(&["BLMOVE", "source", "destination", "RIGHT", "LEFT", "5"]);
+        let_it_block();
+        pusher.call(&["LPUSH", "source", "x"]);
+        assert_eq!(mover.receive(), bulk("x"));
+        assert_eq!(
+            pusher.call(&["LRANGE", "destination", "0", "-1"]),
+            Frame::Array(vec![bulk("x")])
+        );
+    }
+
+    #[test]
+    fn clients_that_hang_up_stop_waiting() {
+        let address = start();
+        let mut quitter = Client::connect(address);
+        let mut waiter = Client::connect(address);
+        let mut pusher = Client::connect(address);
+        quitter.send(&["BLPOP", "queue", "0"]);
+        let_it_block();
+        waiter.send(&["BLPOP", "queue", "0"]);
+        let_it_block();
+        drop(quitter);
+        let_it_block();
+        pusher.call(&["RPUSH", "queue", "a"]);
+        assert_eq!(
+            waiter.receive(),
+            Frame::Array(vec![bulk("queue"), bulk("a")])
+        );
+    }
+
+    #[test]
+    fn blocked_stream_readers_all_get_new_entries() {
+        let address = start();
+        let mut far_ahead = Client::connect(address);
+        let mut first = Client::connect(address);
+        let mut second = Client::connect(address);
+        let mut writer = Client::connect(address);
+        far_ahead.send(&[
+            "XREAD",
+            "BLOCK",
+            "300",
+            "STREAMS",
+            "events",
+            "99999999999999",
+        ]);
+        let_it_block();
+        first.send(&["XREAD", "BLOCK", "0", "STREAMS", "events", "$"]);
+        second.send(&[
+            "XREAD", "COUNT", "5", "BLOCK", "0", "STREAMS", "other", "events", "0", "$",
+        ]);
+        let_it_block();
+        assert_eq!(
+            writer.call(&["XADD", "events", "5-1", "kind", "click"]),
+            bulk("5-1")
+        );
+        let read = Frame::Array(vec![Frame::Array(vec![
+            bulk("events"),
+            Frame::Array(vec![Frame::Array(vec![
+                bulk("5-1"),
+                Frame::Array(vec![bulk("kind"), bulk("click")]),
+            ])]),
+        ])]);
+        assert_eq!(first.receive(), read);
+        assert_eq!(second.receive(), read);
+        assert_eq!(far_ahead.receive(), Frame::NullArray);
+        assert_eq!(
+            writer.call(&["XREAD", "STREAMS", "events", "$"]),
+            Frame::NullArray
+        );
+        assert_eq!(
+            writer.call(&["XREAD", "BLOCK", "0", "STREAMS", "events", "0-0"]),
+            read
+        );
+    }
+
+    #[test]
+    fn blocked_consumers_share_out_new_entries() {
+        let address = start();
+        let mut first = Client::connect(address);
+        let mut second = Client::connect(address);
+        let mut writer = Client::connect(address);
+        assert_eq!(
+            writer.call(&["XGROUP", "CREATE", "jobs", "workers", "$", "MKSTREAM"]),
+            Frame::Simple("OK".into())
+        );
+        let read_group = |consumer| {
+            vec![
+                "XREADGROUP",
+                "GROUP",
+                "workers",
+                consumer,
+                "BLOCK",
+                "0",
+                "STREAMS",
+                "jobs",
+                ">",
+            ]
+        };
+        first.send(&read_group("first"));
+        let_it_block();
+        second.send(&read_group("second"));
+        let_it_block();
+        writer.call(&["XADD", "jobs", "1-1", "job", "a"]);
+        writer.call(&["XADD", "jobs", "1-2", "job", "b"]);
+        let job = |id, job| {
+            Frame::Array(vec![Frame::Array(vec![
+                bulk("jobs"),
+                Frame::Array(vec![Frame::Array(vec![
+                    bulk(id),
+                    Frame::Array(vec![bulk("job"), bulk(job)]),
+                ])]),
+            ])])
+        };
+        assert_eq!(first.receive(), job("1-1", "a"));
+        assert_eq!(second.receive(), job("1-2", "b"));
+        assert_eq!(
+            writer.call(&["XACK", "jobs", "workers", "1-1"]),
+            Frame::Integer(1)
+        );
+        assert_eq!(
+            writer.call(&["XPENDING", "jobs", "workers"]),
+            Frame::Array(vec![
+                Frame::Integer(1),
+                bulk("1-2"),
+                bulk("1-2"),
+                Frame::Array(vec![Frame::Array(vec![bulk("second"), bulk("1")])]),
+            ])
+        );
+    }
+
+    #[test]
+    fn keys_expire_by_the_keyspace_clock() {
+        let clock = Arc::new(ManualClock::new(1_000_000));
+        let mut client = Client::connect(start_keyspace(Arc::new(Keyspace::with_clock(
+            clock.clone(),
+        ))));
+        let ok = Frame::Simple("OK".into());
+        assert_eq!(client.call(&["SET", "k", "v", "PX", "1500"]), ok);
+        assert_eq!(client.call(&["TTL", "k"]), Frame::Integer(2));
+        clock.advance(Duration::from_millis(1000));
+        assert_eq!(client.call(&["GET", "k"]), bulk("v"));
+        assert_eq!(client.call(&["PTTL", "k"]), Frame::Integer(500));
+        clock.advance(Duration::from_millis(500));
+        assert_eq!(client.call(&["GET", "k"]), Frame::NullBulk);
+        assert_eq!(client.call(&["TTL", "k"]), Frame::Integer(-2));
+
+        assert_eq!(client.call(&["RPUSH", "l", "a"]), Frame::Integer(1));
+        assert_eq!(client.call(&["EXPIRE", "l", "10"]), Frame::Integer(1));
+        assert_eq!(client.call(&["EXPIRE", "l", "20", "LT"]), Frame::Integer(0));
+        assert_eq!(client.call(&["RPUSH", "l", "b"]), Frame::Integer(2));
+        assert_eq!(client.call(&["TTL", "l"]), Frame::Integer(10));
+        assert_eq!(client.call(&["SET", "l", "v", "KEEPTTL"]), ok);
+        assert_eq!(client.call(&["TTL", "l"]), Frame::Integer(10));
+        assert_eq!(client.call(&["PERSIST", "l"]), Frame::Integer(1));
+        assert_eq!(client.call(&["TTL", "l"]), Frame::Integer(-1));
+        assert_eq!(
+            client.call(&["PEXPIREAT", "l", "1001500"]),
+            Frame::Integer(1)
+        );
+        assert_eq!(client.call(&["GET", "l"]), Frame::NullBulk);
+    }
+}
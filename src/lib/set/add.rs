@@ -5,12 +5,12 @@ use std::collections::{HashMap, HashSet};
 fn insert_values(set: &mut HashSet<Primitive>, values: Vec<Primitive>) -> usize {
     values
         .into_iter()
-        .fold(0, |acc, val| (if set.insert(val) { acc + 1 } else { acc }))
+        .fold(0, |acc, val| if set.insert(val) { acc + 1 } else { acc })
 }
 
 pub fn command(
-    store: &mut HashMap<String, Data>,
-    key: &str,
+    store: &mut HashMap<Vec<u8>, Data>,
+    key: &[u8],
     values: Vec<Primitive>,
 ) -> Result<usize, ApplicationError> {
     match store.get_mut(key) {
@@ -18,7 +18,7 @@ pub fn command(
         None => {
             let mut s = HashSet::new();
             let added = insert_values(&mut s, values);
-            store.insert(key.to_string(), Data::Set(s));
+            store.insert(key.to_vec(), Data::Set(s));
             Ok(added)
         }
         Some(non_set_val) => Err(format!("{} is not a set", non_set_val).into()),
@@ -33,8 +33,8 @@ mod test {
 
     proptest! {
         #[test]
-        fn returns_n_added(key in "\".*\"", values in vec(any::<Primitive>(), 1..100)) {
-            let mut store: HashMap<String, Data> = HashMap::new();
+        fn returns_n_added(key in vec(any::<u8>(), 0..32), values in vec(any::<Primitive>(), 1..100)) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
             let size = values.iter().collect::<HashSet<&Primitive>>().drain().count();
 
             assert_eq!(command(&mut store, &key, values)?, size)
@@ -1,29 +1,35 @@
 use super::super::domain::Data;
-use super::super::errors::{ApplicationError, Fallible};
+use super::super::errors::ApplicationError;
+use super::utilities::get_set;
 use std::collections::HashMap;
 
-pub fn command(store: &mut HashMap<String, Data>, key: &str) -> Result<usize, ApplicationError> {
-    match store.get(key).fail_to(&format!("No value at {}", key))? {
-        Data::Set(s) => Ok(s.len()),
-        _ => Err(format!("Value at {} is not a set", key).into()),
-    }
+pub fn command(store: &mut HashMap<Vec<u8>, Data>, key: &[u8]) -> Result<usize, ApplicationError> {
+    Ok(get_set(store, key)?.map_or(0, |set| set.len()))
 }
 
 #[cfg(test)]
 mod test {
     use super::super::super::domain::Primitive;
     use super::{command, Data, HashMap};
-    use proptest::collection::hash_set;
+    use proptest::collection::{hash_set, vec};
     use proptest::prelude::*;
 
     proptest! {
         #[test]
-        fn returns_set_size(key in "\".*\"", values in hash_set(any::<Primitive>(), 1..100)) {
-            let mut store: HashMap<String, Data> = HashMap::new();
+        fn returns_set_size(key in vec(any::<u8>(), 0..32), values in hash_set(any::<Primitive>(), 1..100)) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
             let size = values.len();
             store.insert(key.clone(), values.into());
 
             assert_eq!(command(&mut store, &key)?, size)
         }
     }
+
+    proptest! {
+        #[test]
+        fn missing_set_is_empty(key in vec(any::<u8>(), 0..32)) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            assert_eq!(command(&mut store, &key)?, 0)
+        }
+    }
 }
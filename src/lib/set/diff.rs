@@ -1,15 +1,21 @@
 use super::super::domain::{Data, Primitive};
 use super::super::errors::ApplicationError;
-use super::utilities::{get_set, get_sets};
+use super::utilities::{get_set, get_sets, store_result};
 use std::collections::{HashMap, HashSet};
 
+/// The members of the set at `base_key` in none of the sets at `keys`,
+/// where missing keys count as empty sets.
 pub fn command(
-    store: &mut HashMap<String, Data>,
-    base_key: &str,
-    keys: &[String],
+    store: &mut HashMap<Vec<u8>, Data>,
+    base_key: &[u8],
+    keys: &[Vec<u8>],
 ) -> Result<HashSet<Primitive>, ApplicationError> {
-    let sets = get_sets(store, keys)?;
-    let result: HashSet<Primitive> = get_set(store, base_key)?
+    let sets: Vec<&HashSet<Primitive>> = get_sets(store, keys)?.into_iter().flatten().collect();
+    let base = match get_set(store, base_key)? {
+        Some(base) => base,
+        None => return Ok(HashSet::new()),
+    };
+    let result: HashSet<Primitive> = base
         .iter()
         .filter(|el| sets.iter().all(|set| !set.contains(el)))
         .cloned()
@@ -18,31 +24,25 @@ pub fn command(
 }
 
 pub fn store_command(
-    store: &mut HashMap<String, Data>,
-    destination: &str,
-    base_key: &str,
-    keys: &[String],
+    store: &mut HashMap<Vec<u8>, Data>,
+    destination: &[u8],
+    base_key: &[u8],
+    keys: &[Vec<u8>],
 ) -> Result<usize, ApplicationError> {
     let result: HashSet<Primitive> = command(store, base_key, keys)?;
-    let size = result.len();
-    store.insert(destination.to_string(), result.into());
-    Ok(size)
+    Ok(store_result(store, destination, result))
 }
 
 #[cfg(test)]
 mod test {
     use super::super::super::domain::Primitive;
     use super::{command, store_command, Data};
-    use proptest::collection::hash_set;
+    use proptest::collection::{hash_set, vec};
     use proptest::prelude::*;
-    use proptest::string::{string_regex, RegexGeneratorStrategy};
     use std::collections::{HashMap, HashSet};
 
-    fn valid_keys() -> RegexGeneratorStrategy<String> {
-        match string_regex("[^\\s]+") {
-            Ok(s) => s,
-            Err(_) => panic!("strategy failed"),
-        }
+    fn valid_keys() -> impl Strategy<Value = Vec<u8>> {
+        vec(any::<u8>(), 1..16)
     }
 
     proptest! {
@@ -53,7 +53,7 @@ mod test {
           a in (valid_keys(), hash_set(any::<Primitive>(), 1..100)),
           b in (valid_keys(), hash_set(any::<Primitive>(), 1..100))
         ) {
-            let mut store: HashMap<String, Data> = HashMap::new();
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
             let (a_key, a_set) = a;
             let (b_key, b_set) = b;
             store.insert(a_key.clone(), a_set.into());
@@ -70,9 +70,10 @@ mod test {
             a in (valid_keys(), hash_set(any::<Primitive>(), 1..100)),
             b in (valid_keys(), hash_set(any::<Primitive>(), 1..100))
         ) {
-            let mut store: HashMap<String, Data> = HashMap::new();
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
             let (a_key, a_set) = a;
             let (b_key, b_set) = b;
+            prop_assume!(a_key != b_key);
             let a_set = a_set.difference(&b_set).cloned().collect::<HashSet<Primitive>>();
             let a_set_len = a_set.len();
             store.insert(a_key.clone(), a_set.into());
@@ -88,7 +89,7 @@ mod test {
             a in (valid_keys(), hash_set(any::<Primitive>(), 1..100)),
             b in (valid_keys(), hash_set(any::<Primitive>(), 1..100))
         ) {
-            let mut store: HashMap<String, Data> = HashMap::new();
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
             let (a_key, a_set) = a;
             let (b_key, b_set) = b;
             let a_set_len = a_set.len();
@@ -104,7 +105,7 @@ mod test {
         fn self_difference_is_empty(
           a in (valid_keys(), hash_set(any::<Primitive>(), 1..100))
         ) {
-            let mut store: HashMap<String, Data> = HashMap::new();
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
             let (a_key, a_set) = a;
             store.insert(a_key.clone(), a_set.into());
             let set = command(&mut store, &a_key.clone(), &vec![a_key][..])?;
@@ -119,14 +120,37 @@ mod test {
           a in (valid_keys(), hash_set(any::<Primitive>(), 1..100)),
           b in (valid_keys(), hash_set(any::<Primitive>(), 1..100))
         ) {
-            let mut store: HashMap<String, Data> = HashMap::new();
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
             let (a_key, a_set) = a;
             let (b_key, b_set) = b;
             store.insert(a_key.clone(), a_set.into());
             store.insert(b_key.clone(), b_set.into());
             let resulting_size = store_command(&mut store, &dest, &a_key, &vec![b_key][..])?;
-            let resulting_set = store.get(&dest);
-            if let Some(Data::Set(set)) = resulting_set {assert_eq!(set.len(), resulting_size)} else { panic!("something odd found at key")}
+            match store.get(&dest) {
+                Some(Data::Set(set)) => assert_eq!(set.len(), resulting_size),
+                None => assert_eq!(resulting_size, 0),
+                _ => panic!("something odd found at key"),
+            }
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn missing_sets_are_empty(
+            dest in valid_keys(),
+            a in (valid_keys(), hash_set(any::<Primitive>(), 1..100)),
+            missing in valid_keys()
+        ) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            let (a_key, a_set) = a;
+            prop_assume!(a_key != missing);
+            store.insert(a_key.clone(), a_set.clone().into());
+            assert_eq!(command(&mut store, &a_key, &vec![missing.clone()][..])?, a_set);
+            assert_eq!(command(&mut store, &a_key, &[])?, a_set);
+            assert!(command(&mut store, &missing, &vec![a_key.clone()][..])?.is_empty());
+            store.insert(dest.clone(), Data::Primitive(Primitive::from("x")));
+            assert_eq!(store_command(&mut store, &dest, &missing, &vec![a_key][..])?, 0);
+            assert_eq!(store.get(&dest), None)
         }
     }
 }
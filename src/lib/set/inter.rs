@@ -1,20 +1,24 @@
 use super::super::domain::{Data, Primitive};
 use super::super::errors::ApplicationError;
-use super::utilities::get_sets;
+use super::utilities::{get_sets, store_result};
 use std::collections::{HashMap, HashSet};
 
+/// The members common to the sets at `keys`, which is empty as soon as one
+/// of them is missing.
 pub fn command(
-    store: &mut HashMap<String, Data>,
-    keys: &[String],
+    store: &mut HashMap<Vec<u8>, Data>,
+    keys: &[Vec<u8>],
 ) -> Result<HashSet<Primitive>, ApplicationError> {
-    if keys.len() < 2 {
-        return Err(String::from("Not enough sets to intersect").into());
+    let sets = match get_sets(store, keys)?
+        .into_iter()
+        .collect::<Option<Vec<_>>>()
+    {
+        Some(sets) => sets,
+        None => return Ok(HashSet::new()),
     };
-    let sets = get_sets(store, keys)?;
-    let minimal = if let Some(set) = sets.iter().min_by_key(|set| set.len()) {
-        set
-    } else {
-        return Err(String::from("Not enough sets to intersect").into());
+    let minimal = match sets.iter().min_by_key(|set| set.len()) {
+        Some(set) => set,
+        None => return Ok(HashSet::new()),
     };
     let result: HashSet<Primitive> = minimal
         .iter()
@@ -25,30 +29,24 @@ pub fn command(
 }
 
 pub fn store_command(
-    store: &mut HashMap<String, Data>,
-    destination: &str,
-    keys: &[String],
+    store: &mut HashMap<Vec<u8>, Data>,
+    destination: &[u8],
+    keys: &[Vec<u8>],
 ) -> Result<usize, ApplicationError> {
     let result: HashSet<Primitive> = command(store, keys)?;
-    let size = result.len();
-    store.insert(destination.to_string(), result.into());
-    Ok(size)
+    Ok(store_result(store, destination, result))
 }
 
 #[cfg(test)]
 mod test {
     use super::super::super::domain::Primitive;
     use super::{command, store_command, Data};
-    use proptest::collection::hash_set;
+    use proptest::collection::{hash_set, vec};
     use proptest::prelude::*;
-    use proptest::string::{string_regex, RegexGeneratorStrategy};
     use std::collections::{HashMap, HashSet};
 
-    fn valid_keys() -> RegexGeneratorStrategy<String> {
-        match string_regex("[^\\s]+") {
-            Ok(s) => s,
-            Err(_) => panic!("strategy failed"),
-        }
+    fn valid_keys() -> impl Strategy<Value = Vec<u8>> {
+        vec(any::<u8>(), 1..16)
     }
 
     proptest! {
@@ -59,7 +57,7 @@ mod test {
             a in (valid_keys(), hash_set(any::<Primitive>(), 1..100)),
             b in (valid_keys(), hash_set(any::<Primitive>(), 1..100))
         ) {
-            let mut store: HashMap<String, Data> = HashMap::new();
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
             let (a_key, a_set) = a;
             let (b_key, b_set) = b;
             store.insert(a_key.clone(), a_set.into());
@@ -76,9 +74,10 @@ mod test {
             a in (valid_keys(), hash_set(any::<Primitive>(), 1..100)),
             b in (valid_keys(), hash_set(any::<Primitive>(), 1..100))
         ) {
-            let mut store: HashMap<String, Data> = HashMap::new();
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
             let (a_key, a_set) = a;
             let (b_key, b_set) = b;
+            prop_assume!(a_key != b_key);
             store.insert(a_key.clone(), a_set.difference(&b_set).cloned().collect::<HashSet<Primitive>>().into());
             store.insert(b_key.clone(), b_set.into());
             let set = command(&mut store, &vec![a_key, b_key.clone()][..])?;
@@ -92,9 +91,10 @@ mod test {
             a in (valid_keys(), hash_set(any::<Primitive>(), 1..100)),
             b in (valid_keys(), hash_set(any::<Primitive>(), 1..100))
         ) {
-            let mut store: HashMap<String, Data> = HashMap::new();
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
             let (a_key, a_set) = a;
             let (b_key, b_set) = b;
+            prop_assume!(a_key != b_key);
             let a_set_len = a_set.len();
             let b_set_len = b_set.len();
             store.insert(a_key.clone(), a_set.into());
@@ -110,7 +110,7 @@ mod test {
         fn self_intersection_is_self(
             a in (valid_keys(), hash_set(any::<Primitive>(), 1..100))
         ) {
-            let mut store: HashMap<String, Data> = HashMap::new();
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
             let (a_key, a_set) = a;
             let a_len = a_set.len();
             store.insert(a_key.clone(), a_set.into());
@@ -126,14 +126,36 @@ mod test {
             a in (valid_keys(), hash_set(any::<Primitive>(), 1..100)),
             b in (valid_keys(), hash_set(any::<Primitive>(), 1..100))
         ) {
-            let mut store: HashMap<String, Data> = HashMap::new();
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
             let (a_key, a_set) = a;
             let (b_key, b_set) = b;
             store.insert(a_key.clone(), a_set.into());
             store.insert(b_key.clone(), b_set.into());
             let resulting_size = store_command(&mut store, &dest, &vec![a_key, b_key][..])?;
-            let resulting_set = store.get(&dest);
-            if let Some(Data::Set(set)) = resulting_set {assert_eq!(set.len(), resulting_size)} else { panic!("something odd found at key")}
+            match store.get(&dest) {
+                Some(Data::Set(set)) => assert_eq!(set.len(), resulting_size),
+                None => assert_eq!(resulting_size, 0),
+                _ => panic!("something odd found at key"),
+            }
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn missing_sets_are_empty(
+            dest in valid_keys(),
+            a in (valid_keys(), hash_set(any::<Primitive>(), 1..100)),
+            missing in valid_keys()
+        ) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            let (a_key, a_set) = a;
+            prop_assume!(a_key != missing && dest != missing);
+            store.insert(a_key.clone(), a_set.clone().into());
+            assert_eq!(command(&mut store, &vec![a_key.clone()][..])?, a_set);
+            assert!(command(&mut store, &vec![a_key.clone(), missing.clone()][..])?.is_empty());
+            store.insert(dest.clone(), Data::Primitive(Primitive::from("x")));
+            assert_eq!(store_command(&mut store, &dest, &vec![a_key, missing][..])?, 0);
+            assert_eq!(store.get(&dest), None)
         }
     }
 }
@@ -0,0 +1,57 @@
+use super::super::domain::Data;
+use super::super::errors::ApplicationError;
+use super::utilities::get_sets;
+use std::collections::HashMap;
+
+/// SINTERCARD: the size of the intersection, counting no further than
+/// `limit` unless it is 0.
+pub fn command(
+    store: &mut HashMap<Vec<u8>, Data>,
+    keys: &[Vec<u8>],
+    limit: usize,
+) -> Result<usize, ApplicationError> {
+    let sets = match get_sets(store, keys)?
+        .into_iter()
+        .collect::<Option<Vec<_>>>()
+    {
+        Some(sets) => sets,
+        None => return Ok(0),
+    };
+    let smallest = match sets.iter().min_by_key(|set| set.len()) {
+        Some(set) => set,
+        None => return Ok(0),
+    };
+    let limit = if limit == 0 { usize::MAX } else { limit };
+    Ok(smallest
+        .iter()
+        .filter(|member| sets.iter().all(|set| set.contains(member)))
+        .take(limit)
+        .count())
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::domain::Primitive;
+    use super::{command, Data};
+    use proptest::collection::hash_set;
+    use proptest::prelude::*;
+    use std::collections::HashMap;
+
+    proptest! {
+        #[test]
+        fn counts_the_intersection_up_to_the_limit(
+            a in hash_set(any::<Primitive>(), 1..100),
+            b in hash_set(any::<Primitive>(), 1..100),
+            limit in 0usize..20,
+        ) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            let b = b.union(&a.iter().take(10).cloned().collect()).cloned().collect::<std::collections::HashSet<_>>();
+            let size = a.intersection(&b).count();
+            store.insert(b"a".to_vec(), a.into());
+            store.insert(b"b".to_vec(), b.into());
+            let keys = vec![b"a".to_vec(), b"b".to_vec()];
+            let expected = if limit == 0 { size } else { size.min(limit) };
+            assert_eq!(command(&mut store, &keys, limit)?, expected);
+        }
+    }
+}
@@ -1,15 +1,12 @@
 use super::super::domain::{Data, Primitive};
 use super::super::errors::ApplicationError;
+use super::utilities::get_set;
 use std::collections::HashMap;
 
 pub fn command(
-    store: &mut HashMap<String, Data>,
-    key: &str,
+    store: &mut HashMap<Vec<u8>, Data>,
+    key: &[u8],
     member: &Primitive,
 ) -> Result<bool, ApplicationError> {
-    match store.get_mut(key) {
-        Some(Data::Set(s)) => Ok(s.contains(member)),
-        None => Err(format!("No value at key {}", key).into()),
-        Some(non_set_val) => Err(format!("{} is not a set", non_set_val).into()),
-    }
+    Ok(get_set(store, key)?.is_some_and(|set| set.contains(member)))
 }
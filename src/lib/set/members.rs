@@ -0,0 +1,28 @@
+use super::super::domain::{Data, Primitive};
+use super::super::errors::ApplicationError;
+use super::utilities::get_set;
+use std::collections::{HashMap, HashSet};
+
+pub fn command(
+    store: &mut HashMap<Vec<u8>, Data>,
+    key: &[u8],
+) -> Result<HashSet<Primitive>, ApplicationError> {
+    Ok(get_set(store, key)?.cloned().unwrap_or_default())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{command, Data, Primitive};
+    use proptest::collection::{hash_set, vec};
+    use proptest::prelude::*;
+    use std::collections::HashMap;
+
+    proptest! {
+        #[test]
+        fn returns_every_member(key in vec(any::<u8>(), 0..32), set in hash_set(any::<Primitive>(), 1..100)) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            store.insert(key.clone(), set.clone().into());
+            assert_eq!(command(&mut store, &key)?, set)
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use super::super::domain::{Data, Primitive};
+use super::super::errors::ApplicationError;
+use super::utilities::get_set;
+use std::collections::HashMap;
+
+/// SMISMEMBER: whether each of `members` is in the set.
+pub fn command(
+    store: &mut HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    members: &[Primitive],
+) -> Result<Vec<bool>, ApplicationError> {
+    let set = get_set(store, key)?;
+    Ok(members
+        .iter()
+        .map(|member| set.is_some_and(|set| set.contains(member)))
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::ismember;
+    use super::{command, Data, Primitive};
+    use proptest::collection::{hash_set, vec};
+    use proptest::prelude::*;
+    use std::collections::HashMap;
+
+    proptest! {
+        #[test]
+        fn agrees_with_sismember(
+            key in vec(any::<u8>(), 0..32),
+            set in hash_set(any::<Primitive>(), 1..100),
+            members in vec(any::<Primitive>(), 1..50),
+        ) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            let members: Vec<Primitive> = members.into_iter().chain(set.iter().take(5).cloned()).collect();
+            store.insert(key.clone(), set.into());
+            let found = command(&mut store, &key, &members)?;
+            for (member, found) in members.iter().zip(found) {
+                assert_eq!(ismember::command(&mut store, &key, member)?, found);
+            }
+        }
+    }
+}
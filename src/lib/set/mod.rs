@@ -2,6 +2,13 @@ pub mod add;
 pub mod card;
 pub mod diff;
 pub mod inter;
+pub mod intercard;
 pub mod ismember;
+pub mod members;
+pub mod mismember;
+pub mod pop;
+pub mod randmember;
+pub mod rem;
+pub mod smove;
 pub mod union;
 mod utilities;
@@ -0,0 +1,64 @@
+use super::super::domain::{Data, Primitive};
+use super::super::errors::ApplicationError;
+use super::super::random::Random;
+use super::utilities::{get_set_mut, remove_if_empty, sample};
+use std::collections::HashMap;
+
+/// SPOP: removes and returns up to `count` members picked at random.
+pub fn command(
+    store: &mut HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    count: usize,
+) -> Result<Vec<Primitive>, ApplicationError> {
+    let set = match get_set_mut(store, key)? {
+        Some(set) => set,
+        None => return Ok(Vec::new()),
+    };
+    let picked: Vec<Primitive> = sample(&mut Random::new(), set, count)
+        .into_iter()
+        .cloned()
+        .collect();
+    for member in &picked {
+        set.remove(member);
+    }
+    remove_if_empty(store, key);
+    Ok(picked)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{command, Data, Primitive};
+    use proptest::collection::{hash_set, vec};
+    use proptest::prelude::*;
+    use std::collections::{HashMap, HashSet};
+
+    proptest! {
+        #[test]
+        fn pops_distinct_members_until_none_are_left(
+            key in vec(any::<u8>(), 0..32),
+            set in hash_set(any::<Primitive>(), 1..100),
+            count in 0usize..30,
+        ) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            let mut left = set.clone();
+            store.insert(key.clone(), set.into());
+            loop {
+                let popped = command(&mut store, &key, count)?;
+                assert_eq!(popped.len(), count.min(left.len()));
+                let unique: HashSet<&Primitive> = popped.iter().collect();
+                assert_eq!(unique.len(), popped.len());
+                for member in &popped {
+                    assert!(left.remove(member));
+                }
+                if left.is_empty() {
+                    assert!(store.is_empty());
+                } else {
+                    assert_eq!(store.get(&key), Some(&Data::Set(left.clone())));
+                }
+                if popped.is_empty() {
+                    break;
+                }
+            }
+        }
+    }
+}
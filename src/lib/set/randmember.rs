@@ -0,0 +1,78 @@
+use super::super::domain::{Data, Primitive};
+use super::super::errors::ApplicationError;
+use super::super::random::Random;
+use super::utilities::{get_set, sample};
+use std::collections::HashMap;
+
+/// The most members a negative count can ask for. Every one of them goes
+/// into the reply, which is built while the keyspace is locked.
+const MAX_REPEATS: i64 = 1 << 24;
+
+/// SRANDMEMBER: with a positive `count`, up to that many distinct members
+/// picked at random; with a negative one, exactly that many, where the
+/// same member can come up more than once.
+pub fn command(
+    store: &HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    count: i64,
+) -> Result<Vec<Primitive>, ApplicationError> {
+    if count < -MAX_REPEATS {
+        return Err(String::from("Count is out of range").into());
+    }
+    let set = match get_set(store, key)? {
+        Some(set) => set,
+        None => return Ok(Vec::new()),
+    };
+    let mut random = Random::new();
+    if count >= 0 {
+        let picked = sample(&mut random, set, count as usize);
+        return Ok(picked.into_iter().cloned().collect());
+    }
+    let members: Vec<&Primitive> = set.iter().collect();
+    Ok((0..count.unsigned_abs())
+        .map(|_| members[random.below(members.len())].clone())
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{command, Data, Primitive, MAX_REPEATS};
+    use proptest::collection::{hash_set, vec};
+    use proptest::prelude::*;
+    use std::collections::{HashMap, HashSet};
+
+    proptest! {
+        #[test]
+        fn picks_members_without_removing_them(
+            key in vec(any::<u8>(), 0..32),
+            set in hash_set(any::<Primitive>(), 1..100),
+            count in -200i64..200,
+        ) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            store.insert(key.clone(), set.clone().into());
+            let picked = command(&store, &key, count)?;
+            assert!(picked.iter().all(|member| set.contains(member)));
+            let unique: HashSet<&Primitive> = picked.iter().collect();
+            if count >= 0 {
+                assert_eq!(picked.len(), (count as usize).min(set.len()));
+                assert_eq!(unique.len(), picked.len());
+            } else {
+                assert_eq!(picked.len(), count.unsigned_abs() as usize);
+            }
+            assert_eq!(store.get(&key), Some(&Data::Set(set)));
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn refuses_to_repeat_without_bound(
+            set in hash_set(any::<Primitive>(), 1..10),
+            count in i64::MIN..-MAX_REPEATS,
+        ) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            store.insert(b"s".to_vec(), set.into());
+            assert!(command(&store, b"s", count).is_err());
+            assert!(command(&store, b"missing", count).is_err());
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use super::super::domain::{Data, Primitive};
+use super::super::errors::ApplicationError;
+use super::utilities::{get_set_mut, remove_if_empty};
+use std::collections::HashMap;
+
+/// SREM: returns how many of `members` were in the set.
+pub fn command(
+    store: &mut HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    members: &[Primitive],
+) -> Result<usize, ApplicationError> {
+    let set = match get_set_mut(store, key)? {
+        Some(set) => set,
+        None => return Ok(0),
+    };
+    let removed = members.iter().filter(|member| set.remove(member)).count();
+    remove_if_empty(store, key);
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{command, Data, Primitive};
+    use proptest::collection::{hash_set, vec};
+    use proptest::prelude::*;
+    use std::collections::{HashMap, HashSet};
+
+    proptest! {
+        #[test]
+        fn removes_only_what_is_there(
+            key in vec(any::<u8>(), 0..32),
+            set in hash_set(any::<Primitive>(), 1..100),
+            members in vec(any::<Primitive>(), 1..50),
+            remove_existing in 0usize..100,
+        ) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            let members: Vec<Primitive> = members.into_iter().chain(set.iter().take(remove_existing).cloned()).collect();
+            let unique: HashSet<&Primitive> = members.iter().collect();
+            let present = unique.iter().filter(|member| set.contains(member)).count();
+            let expected: HashSet<Primitive> =
+                set.iter().filter(|member| !unique.contains(member)).cloned().collect();
+            store.insert(key.clone(), set.into());
+            assert_eq!(command(&mut store, &key, &members)?, present);
+            if expected.is_empty() {
+                assert!(store.is_empty());
+            } else {
+                assert_eq!(store.get(&key), Some(&Data::Set(expected)));
+            }
+        }
+    }
+}
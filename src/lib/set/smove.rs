@@ -0,0 +1,100 @@
+use super::super::domain::{Data, Primitive};
+use super::super::errors::ApplicationError;
+use super::add;
+use super::utilities::{get_set, get_set_mut, remove_if_empty};
+use std::collections::HashMap;
+
+/// SMOVE: moves `member` from one set to another, creating the destination
+/// if need be, and returns whether it was in the source. Both keys are
+/// checked to be sets before anything moves.
+pub fn command(
+    store: &mut HashMap<Vec<u8>, Data>,
+    source: &[u8],
+    destination: &[u8],
+    member: Primitive,
+) -> Result<bool, ApplicationError> {
+    get_set(store, destination)?;
+    let set = match get_set_mut(store, source)? {
+        Some(set) => set,
+        None => return Ok(false),
+    };
+    if source == destination {
+        return Ok(set.contains(&member));
+    }
+    if !set.remove(&member) {
+        return Ok(false);
+    }
+    remove_if_empty(store, source);
+    add::command(store, destination, vec![member])?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{command, Data, Primitive};
+    use proptest::collection::hash_set;
+    use proptest::prelude::*;
+    use std::collections::{HashMap, HashSet};
+
+    proptest! {
+        #[test]
+        fn moves_a_member_between_sets(
+            a in hash_set(any::<Primitive>(), 1..50),
+            b in hash_set(any::<Primitive>(), 0..50),
+            member in any::<Primitive>(),
+        ) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            store.insert(b"a".to_vec(), a.clone().into());
+            if !b.is_empty() {
+                store.insert(b"b".to_vec(), b.clone().into());
+            }
+            let moved = command(&mut store, b"a", b"b", member.clone())?;
+            assert_eq!(moved, a.contains(&member));
+            let mut expected_a = a.clone();
+            let mut expected_b = b.clone();
+            if moved {
+                expected_a.remove(&member);
+                expected_b.insert(member);
+            }
+            let a_now: HashSet<Primitive> = match store.get(&b"a"[..]) {
+                Some(Data::Set(set)) => set.clone(),
+                _ => HashSet::new(),
+            };
+            assert_eq!(a_now, expected_a);
+            let b_now: HashSet<Primitive> = match store.get(&b"b"[..]) {
+                Some(Data::Set(set)) => set.clone(),
+                _ => HashSet::new(),
+            };
+            assert_eq!(b_now, expected_b);
+        }
+    }
+
+    #[test]
+    fn refuses_to_move_into_a_non_set() {
+        let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+        let member = Primitive::from("m");
+        let set: HashSet<Primitive> = vec![member.clone()].into_iter().collect();
+        store.insert(b"a".to_vec(), set.clone().into());
+        store.insert(b"b".to_vec(), Data::Primitive(Primitive::from("x")));
+        assert!(command(&mut store, b"a", b"b", member).is_err());
+        assert_eq!(store.get(&b"a"[..]), Some(&Data::Set(set)));
+    }
+
+    #[test]
+    fn moving_from_a_missing_set_moves_nothing() {
+        let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+        assert!(!command(&mut store, b"a", b"b", Primitive::from("m")).unwrap());
+        assert!(store.is_empty());
+    }
+
+    #[test]
+    fn moving_the_last_member_deletes_the_source() {
+        let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+        let member = Primitive::from("m");
+        let set: HashSet<Primitive> = vec![member.clone()].into_iter().collect();
+        store.insert(b"a".to_vec(), set.clone().into());
+        assert!(command(&mut store, b"a", b"b", member).unwrap());
+        assert_eq!(store.get(&b"a"[..]), None);
+        assert_eq!(store.get(&b"b"[..]), Some(&Data::Set(set)));
+    }
+}
@@ -1,48 +1,36 @@
 use super::super::domain::{Data, Primitive};
 use super::super::errors::ApplicationError;
-use super::utilities::get_sets;
+use super::utilities::{get_sets, store_result};
 use std::collections::{HashMap, HashSet};
 
+/// The members of any of the sets at `keys`, skipping missing keys.
 pub fn command(
-    store: &mut HashMap<String, Data>,
-    keys: &[String],
+    store: &mut HashMap<Vec<u8>, Data>,
+    keys: &[Vec<u8>],
 ) -> Result<HashSet<Primitive>, ApplicationError> {
-    if keys.len() < 2 {
-        return Err(String::from("Not enough sets to union").into());
-    };
     let sets = get_sets(store, keys)?;
-    Ok(sets
-        .iter()
-        .flat_map(|set| set.into_iter())
-        .cloned()
-        .collect())
+    Ok(sets.into_iter().flatten().flatten().cloned().collect())
 }
 
 pub fn store_command(
-    store: &mut HashMap<String, Data>,
-    destination: &str,
-    keys: &[String],
+    store: &mut HashMap<Vec<u8>, Data>,
+    destination: &[u8],
+    keys: &[Vec<u8>],
 ) -> Result<usize, ApplicationError> {
     let union: HashSet<Primitive> = command(store, keys)?;
-    let size = union.len();
-    store.insert(destination.to_string(), union.into());
-    return Ok(size);
+    Ok(store_result(store, destination, union))
 }
 
 #[cfg(test)]
 mod test {
     use super::super::super::domain::{Data, Primitive};
-    use super::store_command;
-    use proptest::collection::hash_set;
+    use super::{command, store_command};
+    use proptest::collection::{hash_set, vec};
     use proptest::prelude::*;
-    use proptest::string::{string_regex, RegexGeneratorStrategy};
     use std::collections::{HashMap, HashSet};
 
-    fn valid_keys() -> RegexGeneratorStrategy<String> {
-        match string_regex("[^\\s]+") {
-            Ok(s) => s,
-            Err(_) => panic!("strategy failed"),
-        }
+    fn valid_keys() -> impl Strategy<Value = Vec<u8>> {
+        vec(any::<u8>(), 1..16)
     }
 
     proptest! {
@@ -53,7 +41,7 @@ mod test {
             a in (valid_keys(), hash_set(any::<Primitive>(), 1..100)),
             b in (valid_keys(), hash_set(any::<Primitive>(), 1..100))
         ) {
-            let mut store: HashMap<String, Data> = HashMap::new();
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
             let (a_key, a_set) = a;
             let (b_key, b_set) = b;
             store.insert(a_key.clone(), a_set.into());
@@ -71,9 +59,10 @@ mod test {
             a in (valid_keys(), hash_set(any::<Primitive>(), 1..100)),
             b in (valid_keys(), hash_set(any::<Primitive>(), 1..100))
         ) {
-            let mut store: HashMap<String, Data> = HashMap::new();
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
             let (a_key, a_set) = a;
             let (b_key, b_set) = b;
+            prop_assume!(a_key != b_key);
             let disjoint_to_b = a_set.difference(&b_set).cloned().collect::<HashSet<Primitive>>();
             let sum_size = b_set.len() + disjoint_to_b.len();
             store.insert(a_key.clone(), disjoint_to_b.into());
@@ -90,9 +79,10 @@ mod test {
             a in (valid_keys(), hash_set(any::<Primitive>(), 1..100)),
             b in (valid_keys(), hash_set(any::<Primitive>(), 1..100))
         ) {
-            let mut store: HashMap<String, Data> = HashMap::new();
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
             let (a_key, a_set) = a;
             let (b_key, b_set) = b;
+            prop_assume!(a_key != b_key);
             let a_len = a_set.len();
             let b_len = b_set.len();
             store.insert(a_key.clone(), a_set.into());
@@ -114,7 +104,7 @@ mod test {
             dest in valid_keys(),
             a in (valid_keys(), hash_set(any::<Primitive>(), 1..100))
         ) {
-            let mut store: HashMap<String, Data> = HashMap::new();
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
             let (a_key, a_set) = a;
             let a_len = a_set.len();
             store.insert(a_key.clone(), a_set.into());
@@ -131,7 +121,7 @@ mod test {
             a in (valid_keys(), hash_set(any::<Primitive>(), 1..100)),
             b in (valid_keys(), hash_set(any::<Primitive>(), 1..100))
         ) {
-            let mut store: HashMap<String, Data> = HashMap::new();
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
             let (a_key, a_set) = a;
             let (b_key, b_set) = b;
             store.insert(a_key.clone(), a_set.into());
@@ -141,4 +131,23 @@ mod test {
             if let Some(Data::Set(set)) = resulting_set {assert_eq!(set.len(), resulting_size)} else { panic!("something odd found at key")}
         }
     }
+
+    proptest! {
+        #[test]
+        fn missing_sets_are_empty(
+            dest in valid_keys(),
+            a in (valid_keys(), hash_set(any::<Primitive>(), 1..100)),
+            missing in valid_keys()
+        ) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            let (a_key, a_set) = a;
+            prop_assume!(a_key != missing && dest != missing);
+            store.insert(a_key.clone(), a_set.clone().into());
+            assert_eq!(command(&mut store, &vec![a_key.clone()][..])?, a_set.clone());
+            assert_eq!(command(&mut store, &vec![a_key, missing.clone()][..])?, a_set);
+            store.insert(dest.clone(), Data::Primitive(Primitive::from("x")));
+            assert_eq!(store_command(&mut store, &dest, &vec![missing][..])?, 0);
+            assert_eq!(store.get(&dest), None)
+        }
+    }
 }
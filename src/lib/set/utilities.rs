@@ -1,30 +1,82 @@
-use super::super::domain::{Data, Primitive};
+use super::super::domain::{Data, Escaped, Primitive};
 use super::super::errors::ApplicationError;
+use super::super::random::Random;
 use std::collections::{HashMap, HashSet};
 
 pub fn get_set<'a>(
-    store: &'a HashMap<String, Data>,
-    key: &str,
-) -> Result<&'a HashSet<Primitive>, ApplicationError> {
+    store: &'a HashMap<Vec<u8>, Data>,
+    key: &[u8],
+) -> Result<Option<&'a HashSet<Primitive>>, ApplicationError> {
     match store.get(key) {
-        Some(Data::Set(set)) => Ok(set),
-        Some(_) => Err(format!("Value at {} is not a set", key).into()),
-        None => Err(format!("No value at key {}", key).into()),
+        Some(Data::Set(set)) => Ok(Some(set)),
+        Some(_) => Err(format!("Value at {} is not a set", Escaped(key)).into()),
+        None => Ok(None),
     }
 }
 
+pub fn get_set_mut<'a>(
+    store: &'a mut HashMap<Vec<u8>, Data>,
+    key: &[u8],
+) -> Result<Option<&'a mut HashSet<Primitive>>, ApplicationError> {
+    match store.get_mut(key) {
+        Some(Data::Set(set)) => Ok(Some(set)),
+        Some(_) => Err(format!("Value at {} is not a set", Escaped(key)).into()),
+        None => Ok(None),
+    }
+}
+
+/// The inputs to set algebra, where a missing key is `None` and counts as
+/// an empty set.
 pub fn get_sets<'a>(
-    store: &'a HashMap<String, Data>,
-    keys: &[String],
-) -> Result<Vec<&'a HashSet<Primitive>>, ApplicationError> {
-    keys.iter().map(|key| get_set(&store, key)).fold(
-        Ok(Vec::with_capacity(keys.len())),
-        |acc, next| match (acc, next) {
-            (Err(e), _) | (_, Err(e)) => Err(e),
-            (Ok(mut acc), Ok(a)) => {
-                acc.push(a);
-                Ok(acc)
+    store: &'a HashMap<Vec<u8>, Data>,
+    keys: &[Vec<u8>],
+) -> Result<Vec<Option<&'a HashSet<Primitive>>>, ApplicationError> {
+    keys.iter().map(|key| get_set(store, key)).collect()
+}
+
+/// Redis never keeps an empty set around, so a command that empties one
+/// deletes its key.
+pub fn remove_if_empty(store: &mut HashMap<Vec<u8>, Data>, key: &[u8]) {
+    if let Some(Data::Set(set)) = store.get(key) {
+        if set.is_empty() {
+            store.remove(key);
+        }
+    }
+}
+
+/// Stores the result of set algebra, or deletes the destination if the
+/// result is empty, returning its size.
+pub fn store_result(
+    store: &mut HashMap<Vec<u8>, Data>,
+    destination: &[u8],
+    result: HashSet<Primitive>,
+) -> usize {
+    let size = result.len();
+    if size == 0 {
+        store.remove(destination);
+    } else {
+        store.insert(destination.to_vec(), result.into());
+    }
+    size
+}
+
+/// `count` members of `set` picked at random, without repeats, by
+/// reservoir sampling.
+pub fn sample<'a>(
+    random: &mut Random,
+    set: &'a HashSet<Primitive>,
+    count: usize,
+) -> Vec<&'a Primitive> {
+    let mut picked: Vec<&Primitive> = Vec::with_capacity(count.min(set.len()));
+    for (seen, member) in set.iter().enumerate() {
+        if picked.len() < count {
+            picked.push(member);
+        } else {
+            let slot = random.below(seen + 1);
+            if slot < count {
+                picked[slot] = member;
             }
-        },
-    )
+        }
+    }
+    picked
 }
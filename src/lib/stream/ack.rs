@@ -0,0 +1,52 @@
+use super::super::domain::Data;
+use super::super::errors::ApplicationError;
+use super::utilities::get_stream_mut;
+use super::StreamId;
+use std::collections::HashMap;
+
+/// Acknowledges messages, returning how many were pending.
+pub fn command(
+    store: &mut HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    group: &[u8],
+    ids: &[StreamId],
+) -> Result<usize, ApplicationError> {
+    Ok(
+        match get_stream_mut(store, key)?.and_then(|stream| stream.group_mut(group)) {
+            Some((group, _)) => ids.iter().filter(|id| group.ack(**id)).count(),
+            None => 0,
+        },
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::add::{self, NewId};
+    use super::super::group;
+    use super::super::read::After;
+    use super::super::readgroup::{self, Start};
+    use super::{command, Data, HashMap, StreamId};
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn acks_each_message_once(n in 0u64..30, acks in vec(0u64..40, 0..40)) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            assert_eq!(command(&mut store, b"s", b"g", &[StreamId::new(1, 0)])?, 0);
+            group::create_command(&mut store, b"s", b"g", After::Last, true)?;
+            for now in 1..=n {
+                add::command(&mut store, b"s", Default::default(), NewId::Auto, vec![], now)?;
+            }
+            let options = readgroup::Options { group: b"g".to_vec(), consumer: b"c".to_vec(), count: None, noack: false };
+            readgroup::command(&mut store, b"s", &options, Start::New, 0)?;
+            let mut acked = std::collections::HashSet::new();
+            for ms in acks {
+                let fresh = (1..=n).contains(&ms) && acked.insert(ms);
+                assert_eq!(command(&mut store, b"s", b"g", &[StreamId::new(ms, 0)])?, fresh as usize);
+            }
+            let history = readgroup::command(&mut store, b"s", &options, Start::History(StreamId::MIN), 0)?;
+            assert_eq!(history.len(), n as usize - acked.len())
+        }
+    }
+}
@@ -0,0 +1,167 @@
+use super::super::domain::{Data, Primitive};
+use super::super::errors::{ApplicationError, Fallible};
+use super::trim::Trim;
+use super::utilities::get_stream;
+use super::{Stream, StreamId};
+use std::collections::HashMap;
+
+/// The ID XADD gives the new entry.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum NewId {
+    /// `*`: the current time, with a sequence number to keep IDs increasing.
+    Auto,
+    /// `ms-*`: the given time with the next free sequence number.
+    AutoSequence(u64),
+    Explicit(StreamId),
+}
+
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub struct Options {
+    /// Don't create the stream if it doesn't exist.
+    pub no_mkstream: bool,
+    pub trim: Option<Trim>,
+}
+
+/// Appends an entry and returns its ID, where `now` is the current time in
+/// milliseconds.
+pub fn command(
+    store: &mut HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    options: Options,
+    id: NewId,
+    fields: Vec<(Primitive, Primitive)>,
+    now: u64,
+) -> Result<Option<StreamId>, ApplicationError> {
+    if get_stream(store, key)?.is_none() {
+        if options.no_mkstream {
+            return Ok(None);
+        }
+        // Check the ID first, so a failed XADD doesn't leave a stream behind.
+        next_id(StreamId::MIN, id, now)?;
+        store.insert(key.to_vec(), Data::Stream(Stream::new()));
+    }
+    let stream = match store.get_mut(key) {
+        Some(Data::Stream(stream)) => stream,
+        _ => unreachable!("the stream was found or created above"),
+    };
+    let id = next_id(stream.last_id(), id, now)?;
+    stream.append(id, fields);
+    if let Some(trim) = options.trim {
+        trim.apply(stream);
+    }
+    Ok(Some(id))
+}
+
+/// The ID to give a new entry in a stream whose last ID is `last`.
+fn next_id(last: StreamId, id: NewId, now: u64) -> Result<StreamId, ApplicationError> {
+    let too_small = "The ID specified in XADD is equal or smaller than the stream's last ID";
+    match id {
+        NewId::Auto if now > last.ms => Ok(StreamId::new(now, 0)),
+        NewId::Auto => last
+            .next()
+            .fail_to("The stream has exhausted the last possible ID"),
+        NewId::AutoSequence(ms) if ms == last.ms => last
+            .seq
+            .checked_add(1)
+            .map(|seq| StreamId::new(ms, seq))
+            .fail_to(too_small),
+        NewId::AutoSequence(ms) if ms > last.ms => Ok(StreamId::new(ms, 0)),
+        NewId::Explicit(StreamId::MIN) => {
+            Err(String::from("The ID specified in XADD must be greater than 0-0").into())
+        }
+        NewId::Explicit(id) if id > last => Ok(id),
+        _ => Err(String::from(too_small).into()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::domain::Primitive;
+    use super::super::StreamId;
+    use super::{command, Data, HashMap, NewId, Options};
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    fn new_ids() -> impl Strategy<Value = NewId> {
+        prop_oneof![
+            Just(NewId::Auto),
+            (0u64..5).prop_map(NewId::AutoSequence),
+            (0u64..5, 0u64..5).prop_map(|(ms, seq)| NewId::Explicit(StreamId::new(ms, seq))),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn ids_only_increase(ids in vec((new_ids(), 0u64..5), 1..50)) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            let mut last = StreamId::MIN;
+            let fields = vec![(Primitive::from("f"), Primitive::from("v"))];
+            for (id, now) in ids {
+                match command(&mut store, b"s", Options::default(), id, fields.clone(), now) {
+                    Ok(Some(added)) => {
+                        assert!(added > last);
+                        match id {
+                            NewId::Auto => assert!(added.ms >= now),
+                            NewId::AutoSequence(ms) => assert_eq!(added.ms, ms),
+                            NewId::Explicit(id) => assert_eq!(added, id),
+                        }
+                        last = added;
+                    }
+                    Ok(None) => panic!("XADD without NOMKSTREAM always adds"),
+                    Err(_) => assert!(!matches!(id, NewId::Auto)),
+                }
+            }
+            match store.get(b"s".as_slice()) {
+                Some(Data::Stream(stream)) => assert_eq!(stream.last_id(), last),
+                _ => assert_eq!(last, StreamId::MIN),
+            }
+        }
+    }
+
+    #[test]
+    fn sequences_start_over_each_millisecond() {
+        let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+        let mut add = |id, now| command(&mut store, b"s", Options::default(), id, vec![], now);
+        assert_eq!(
+            add(NewId::AutoSequence(0), 0).unwrap(),
+            Some(StreamId::new(0, 1))
+        );
+        assert_eq!(add(NewId::Auto, 0).unwrap(), Some(StreamId::new(0, 2)));
+        assert_eq!(add(NewId::Auto, 7).unwrap(), Some(StreamId::new(7, 0)));
+        assert_eq!(
+            add(NewId::AutoSequence(7), 3).unwrap(),
+            Some(StreamId::new(7, 1))
+        );
+        assert_eq!(
+            add(NewId::AutoSequence(9), 3).unwrap(),
+            Some(StreamId::new(9, 0))
+        );
+        add(NewId::AutoSequence(8), 3).unwrap_err();
+        add(NewId::Explicit(StreamId::new(9, 0)), 3).unwrap_err();
+    }
+
+    #[test]
+    fn failed_adds_create_nothing() {
+        let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+        command(
+            &mut store,
+            b"s",
+            Options::default(),
+            NewId::Explicit(StreamId::MIN),
+            vec![],
+            0,
+        )
+        .unwrap_err();
+        let options = Options {
+            no_mkstream: true,
+            trim: None,
+        };
+        assert_eq!(
+            command(&mut store, b"s", options, NewId::Auto, vec![], 0).unwrap(),
+            None
+        );
+        assert!(store.is_empty());
+        store.insert(b"s".to_vec(), Data::from(1));
+        command(&mut store, b"s", Options::default(), NewId::Auto, vec![], 0).unwrap_err();
+    }
+}
@@ -0,0 +1,292 @@
+use super::super::domain::Data;
+use super::super::errors::ApplicationError;
+use super::utilities::get_group_mut;
+use super::{Entry, StreamId};
+use std::collections::HashMap;
+
+/// How many pending messages XAUTOCLAIM looks at for every one it may claim.
+const ATTEMPTS_PER_CLAIM: usize = 10;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Options {
+    pub min_idle: u64,
+    /// Where to start scanning the pending entries list.
+    pub start: StreamId,
+    /// The most messages to claim.
+    pub count: usize,
+    /// Don't count the claim as a delivery.
+    pub just_id: bool,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct AutoClaimed {
+    /// Where the next scan should start, or 0-0 once it's done.
+    pub next: StreamId,
+    pub claimed: Vec<Entry>,
+    /// Messages dropped from the pending entries list because their entries
+    /// were deleted.
+    pub deleted: Vec<StreamId>,
+}
+
+/// Scans the pending entries list for messages idle for long enough and
+/// hands them over to `consumer`, like XCLAIM without having to know their
+/// IDs. The scan can be carried on from where it left off.
+pub fn command(
+    store: &mut HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    group: &[u8],
+    consumer: &[u8],
+    options: &Options,
+    now: u64,
+) -> Result<AutoClaimed, ApplicationError> {
+    let (group, entries) = get_group_mut(store, key, group)?;
+    group.create_consumer(consumer);
+    let attempts = options.count.saturating_mul(ATTEMPTS_PER_CLAIM);
+    let scanned: Vec<(StreamId, u64, u64)> = group
+        .pending_range(options.start, StreamId::MAX)
+        .take(attempts.saturating_add(1))
+        .map(|(id, pending)| (*id, pending.idle(now), pending.deliveries))
+        .collect();
+    let (mut claimed, mut deleted) = (Vec::new(), Vec::new());
+    let mut next = StreamId::MIN;
+    for (examined, (id, idle, deliveries)) in scanned.into_iter().enumerate() {
+        if examined == attempts || claimed.len() == options.count {
+            next = id;
+            break;
+        }
+        match entries.get(id) {
+            None => {
+                group.ack(id);
+                deleted.push(id);
+            }
+            Some(fields) if idle >= options.min_idle => {
+                let deliveries = if options.just_id {
+                    deliveries
+                } else {
+                    deliveries + 1
+                };
+                group.claim(id, consumer, now, deliveries);
+                claimed.push((id, fields.clone()));
+            }
+            Some(_) => {}
+        }
+    }
+    Ok(AutoClaimed {
+        next,
+        claimed,
+        deleted,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::add::{self, NewId};
+    use super::super::read::After;
+    use super::super::readgroup::{self, Start};
+    use super::super::trim::{Threshold, Trim};
+    use super::super::utilities::get_group;
+    use super::super::{ack, group};
+    use super::{command, AutoClaimed, Data, HashMap, Options, StreamId};
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+    use std::collections::{BTreeSet, HashSet};
+
+    /// Messages idle this long are taken to belong to a consumer that died.
+    const STALE: u64 = 50;
+
+    #[derive(Debug, Clone)]
+    enum Op {
+        Add,
+        Read(u8),
+        Process(u8),
+        Crash(u8),
+        Wait(u64),
+        Recover(u8),
+    }
+
+    fn ops() -> impl Strategy<Value = Vec<Op>> {
+        vec(
+            prop_oneof![
+                3 => Just(Op::Add),
+                3 => (0u8..4).prop_map(Op::Read),
+                2 => (0u8..4).prop_map(Op::Process),
+                1 => (1u8..4).prop_map(Op::Crash),
+                2 => (0u64..40).prop_map(Op::Wait),
+                1 => (0u8..4).prop_map(Op::Recover),
+            ],
+            0..150,
+        )
+    }
+
+    fn read(
+        store: &mut HashMap<Vec<u8>, Data>,
+        consumer: u8,
+        start: Start,
+        now: u64,
+    ) -> Vec<StreamId> {
+        let options = readgroup::Options {
+            group: b"g".to_vec(),
+            consumer: vec![consumer],
+            count: Some(2),
+            noack: false,
+        };
+        let read = readgroup::command(store, b"s", &options, start, now).unwrap();
+        read.into_iter().map(|(id, _)| id).collect()
+    }
+
+    /// A consumer acknowledges everything it has pending.
+    fn process(
+        store: &mut HashMap<Vec<u8>, Data>,
+        consumer: u8,
+        now: u64,
+        acked: &mut BTreeSet<StreamId>,
+    ) {
+        loop {
+            let pending = read(store, consumer, Start::History(StreamId::MIN), now);
+            if pending.is_empty() {
+                return;
+            }
+            assert_eq!(
+                ack::command(store, b"s", b"g", &pending).unwrap(),
+                pending.len()
+            );
+            acked.extend(pending);
+        }
+    }
+
+    fn recover(
+        store: &mut HashMap<Vec<u8>, Data>,
+        consumer: u8,
+        min_idle: u64,
+        now: u64,
+    ) -> Vec<StreamId> {
+        let mut start = StreamId::MIN;
+        let mut claimed = Vec::new();
+        loop {
+            let options = Options {
+                min_idle,
+                start,
+                count: 2,
+                just_id: false,
+            };
+            let AutoClaimed {
+                next,
+                claimed: more,
+                deleted,
+            } = command(store, b"s", b"g", &[consumer], &options, now).unwrap();
+            assert!(deleted.is_empty());
+            claimed.extend(more.into_iter().map(|(id, _)| id));
+            if next == StreamId::MIN {
+                return claimed;
+            }
+            start = next;
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn crashed_consumers_lose_nothing(ops in ops()) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            group::create_command(&mut store, b"s", b"g", After::Last, true)?;
+            let (mut now, mut added, mut delivered) = (0, BTreeSet::new(), BTreeSet::new());
+            let mut acked = BTreeSet::new();
+            let mut crashed = HashSet::new();
+            for op in ops {
+                match op {
+                    Op::Add => {
+                        let id = add::command(&mut store, b"s", Default::default(), NewId::Auto, vec![], now)?;
+                        added.insert(id.unwrap());
+                    }
+                    Op::Read(c) if !crashed.contains(&c) => delivered.extend(read(&mut store, c, Start::New, now)),
+                    Op::Process(c) if !crashed.contains(&c) => process(&mut store, c, now, &mut acked),
+                    Op::Crash(c) => {
+                        crashed.insert(c);
+                    }
+                    Op::Wait(ms) => now += ms,
+                    Op::Recover(c) if !crashed.contains(&c) => {
+                        let before = get_group(&store, b"s", b"g")?.clone();
+                        for id in recover(&mut store, c, STALE, now) {
+                            let pending = before.pending(id).unwrap();
+                            prop_assert!(pending.idle(now) >= STALE);
+                            prop_assert!(!acked.contains(&id));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            // Once every message has had time to go stale, the survivors can
+            // take over whatever the crashed consumers left behind.
+            now += STALE;
+            let survivors: Vec<u8> = (0..4).filter(|c| !crashed.contains(c)).collect();
+            for c in &survivors {
+                recover(&mut store, *c, STALE, now);
+                process(&mut store, *c, now, &mut acked);
+            }
+            assert_eq!(get_group(&store, b"s", b"g")?.pending_len(), 0);
+            assert_eq!(acked, delivered);
+            assert!(delivered.is_subset(&added))
+        }
+    }
+
+    #[test]
+    fn scans_carry_on_and_drop_deleted_entries() {
+        let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+        group::create_command(&mut store, b"s", b"g", After::Last, true).unwrap();
+        for now in 1..=5 {
+            add::command(
+                &mut store,
+                b"s",
+                Default::default(),
+                NewId::Auto,
+                vec![],
+                now,
+            )
+            .unwrap();
+        }
+        read(&mut store, 0, Start::New, 0);
+        read(&mut store, 0, Start::New, 0);
+        read(&mut store, 0, Start::New, 0);
+        let trim = Trim {
+            threshold: Threshold::MaxLen(4),
+            limit: None,
+        };
+        super::super::trim::command(&mut store, b"s", trim).unwrap();
+        let options = Options {
+            min_idle: 0,
+            start: StreamId::MIN,
+            count: 2,
+            just_id: true,
+        };
+        let first = command(&mut store, b"s", b"g", b"b", &options, 10).unwrap();
+        assert_eq!(first.deleted, vec![StreamId::new(1, 0)]);
+        assert_eq!(
+            first
+                .claimed
+                .iter()
+                .map(|(id, _)| id.ms)
+                .collect::<Vec<_>>(),
+            vec![2, 3]
+        );
+        assert_eq!(first.next, StreamId::new(4, 0));
+        let rest = Options {
+            start: first.next,
+            ..options
+        };
+        let second = command(&mut store, b"s", b"g", b"b", &rest, 10).unwrap();
+        assert_eq!(
+            second
+                .claimed
+                .iter()
+                .map(|(id, _)| id.ms)
+                .collect::<Vec<_>>(),
+            vec![4, 5]
+        );
+        assert_eq!(second.next, StreamId::MIN);
+        let pending = get_group(&store, b"s", b"g")
+            .unwrap()
+            .pending(StreamId::new(5, 0))
+            .cloned()
+            .unwrap();
+        assert_eq!((pending.consumer, pending.deliveries), (b"b".to_vec(), 1));
+    }
+}
@@ -0,0 +1,163 @@
+use super::super::domain::Data;
+use super::super::errors::ApplicationError;
+use super::utilities::get_group_mut;
+use super::{Entry, StreamId};
+use std::collections::HashMap;
+
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct Options {
+    /// Only claim messages idle for at least this many milliseconds.
+    pub min_idle: u64,
+    /// Leave claimed messages idle for this long rather than not at all.
+    pub idle: Option<u64>,
+    /// Leave claimed messages as if delivered at this time.
+    pub time: Option<u64>,
+    pub retry_count: Option<u64>,
+    /// Claim entries nobody has pending too.
+    pub force: bool,
+    /// Don't count the claim as a delivery.
+    pub just_id: bool,
+    /// Move the group's last delivered ID up to this one.
+    pub last_id: Option<StreamId>,
+}
+
+/// Hands pending messages that have been idle long enough over to
+/// `consumer`, returning the entries it got. Messages whose entries were
+/// deleted are dropped rather than claimed.
+pub fn command(
+    store: &mut HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    group: &[u8],
+    consumer: &[u8],
+    ids: &[StreamId],
+    options: &Options,
+    now: u64,
+) -> Result<Vec<Entry>, ApplicationError> {
+    let (group, entries) = get_group_mut(store, key, group)?;
+    group.create_consumer(consumer);
+    if let Some(last_id) = options.last_id {
+        if last_id > group.last_delivered() {
+            group.set_last_delivered(last_id);
+        }
+    }
+    let delivered_at = match (options.time, options.idle) {
+        (Some(time), _) => time,
+        (None, Some(idle)) => now.saturating_sub(idle),
+        (None, None) => now,
+    };
+    let mut claimed = Vec::new();
+    for id in ids {
+        let fields = match entries.get(*id) {
+            Some(fields) => fields,
+            None => {
+                group.ack(*id);
+                continue;
+            }
+        };
+        let deliveries = match group.pending(*id) {
+            Some(pending) if pending.idle(now) >= options.min_idle => pending.deliveries,
+            None if options.force => 0,
+            _ => continue,
+        };
+        let deliveries = options.retry_count.unwrap_or(if options.just_id {
+            deliveries
+        } else {
+            deliveries + 1
+        });
+        group.claim(*id, consumer, delivered_at, deliveries);
+        claimed.push((*id, fields.clone()));
+    }
+    Ok(claimed)
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::add::{self, NewId};
+    use super::super::group;
+    use super::super::read::After;
+    use super::super::readgroup::{self, Start};
+    use super::super::utilities::get_group;
+    use super::{command, Data, HashMap, Options, StreamId};
+    use proptest::prelude::*;
+
+    fn setup(n: u64) -> HashMap<Vec<u8>, Data> {
+        let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+        group::create_command(&mut store, b"s", b"g", After::Last, true).unwrap();
+        for now in 1..=n {
+            add::command(
+                &mut store,
+                b"s",
+                Default::default(),
+                NewId::Auto,
+                vec![],
+                now,
+            )
+            .unwrap();
+        }
+        let options = readgroup::Options {
+            group: b"g".to_vec(),
+            consumer: b"a".to_vec(),
+            count: None,
+            noack: false,
+        };
+        readgroup::command(&mut store, b"s", &options, Start::New, 0).unwrap();
+        store
+    }
+
+    proptest! {
+        #[test]
+        fn claims_only_idle_messages(n in 1u64..20, min_idle in 0u64..200, now in 0u64..200) {
+            let mut store = setup(n);
+            let ids: Vec<StreamId> = (1..=n).map(|ms| StreamId::new(ms, 0)).collect();
+            let options = Options { min_idle, ..Default::default() };
+            let claimed = command(&mut store, b"s", b"g", b"b", &ids, &options, now)?;
+            assert_eq!(claimed.len(), if now >= min_idle { n as usize } else { 0 });
+            let group = get_group(&store, b"s", b"g")?;
+            assert!(claimed.iter().all(|(id, _)| {
+                let pending = group.pending(*id).unwrap();
+                pending.consumer == b"b" && pending.deliveries == 2 && pending.delivered_at == now
+            }))
+        }
+    }
+
+    #[test]
+    fn options_shape_the_claim() {
+        let mut store = setup(3);
+        let (one, four) = (StreamId::new(1, 0), StreamId::new(4, 0));
+        let options = Options {
+            idle: Some(30),
+            retry_count: Some(7),
+            just_id: true,
+            last_id: Some(four),
+            ..Default::default()
+        };
+        command(&mut store, b"s", b"g", b"b", &[one, four], &options, 100).unwrap();
+        let group = get_group(&store, b"s", b"g").unwrap();
+        let pending = group.pending(one).unwrap();
+        assert_eq!((pending.idle(100), pending.deliveries), (30, 7));
+        assert_eq!(group.pending(four), None);
+        assert_eq!(group.last_delivered(), four);
+
+        add::command(&mut store, b"s", Default::default(), NewId::Auto, vec![], 4).unwrap();
+        let force = Options {
+            force: true,
+            time: Some(5),
+            ..Default::default()
+        };
+        assert_eq!(
+            command(&mut store, b"s", b"g", b"c", &[four], &force, 100)
+                .unwrap()
+                .len(),
+            1
+        );
+        let pending = get_group(&store, b"s", b"g")
+            .unwrap()
+            .pending(four)
+            .cloned()
+            .unwrap();
+        assert_eq!(
+            (pending.consumer, pending.delivered_at, pending.deliveries),
+            (b"c".to_vec(), 5, 1)
+        );
+    }
+}
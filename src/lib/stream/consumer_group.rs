@@ -0,0 +1,226 @@
+use super::StreamId;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// A message delivered to a consumer that it has yet to acknowledge.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Pending {
+    pub consumer: Vec<u8>,
+    /// When it was last delivered, in milliseconds.
+    pub delivered_at: u64,
+    pub deliveries: u64,
+}
+
+impl Pending {
+    /// How long it has gone unacknowledged since it was last delivered.
+    pub fn idle(&self, now: u64) -> u64 {
+        now.saturating_sub(self.delivered_at)
+    }
+}
+
+/// The state of a group of consumers sharing out a stream: the last entry
+/// the group was given, and the messages each consumer has been given but
+/// not yet acknowledged. Every pending message is in the group's pending
+/// entries list and in its consumer's.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ConsumerGroup {
+    last_delivered: StreamId,
+    pending: BTreeMap<StreamId, Pending>,
+    consumers: BTreeMap<Vec<u8>, BTreeSet<StreamId>>,
+}
+
+impl ConsumerGroup {
+    pub fn new(last_delivered: StreamId) -> Self {
+        ConsumerGroup {
+            last_delivered,
+            pending: BTreeMap::new(),
+            consumers: BTreeMap::new(),
+        }
+    }
+
+    pub fn last_delivered(&self) -> StreamId {
+        self.last_delivered
+    }
+
+    pub fn set_last_delivered(&mut self, id: StreamId) {
+        self.last_delivered = id;
+    }
+
+    /// Adds a consumer, returning whether it is a new one. Consumers are
+    /// also added as soon as they read or claim anything.
+    pub fn create_consumer(&mut self, name: &[u8]) -> bool {
+        if self.consumers.contains_key(name) {
+            return false;
+        }
+        self.consumers.insert(name.to_vec(), BTreeSet::new());
+        true
+    }
+
+    /// Removes a consumer and drops its pending messages, returning how many
+    /// it had.
+    pub fn delete_consumer(&mut self, name: &[u8]) -> Option<usize> {
+        let pending = self.consumers.remove(name)?;
+        pending.iter().for_each(|id| {
+            self.pending.remove(id);
+        });
+        Some(pending.len())
+    }
+
+    /// The consumers by name, with how many messages each has pending.
+    pub fn consumers(&self) -> impl Iterator<Item = (&[u8], usize)> {
+        self.consumers
+            .iter()
+            .map(|(name, pending)| (name.as_slice(), pending.len()))
+    }
+
+    /// Gives a consumer the next entry, which stays pending until it is
+    /// acknowledged unless `noack` is set.
+    pub fn deliver(&mut self, id: StreamId, consumer: &[u8], now: u64, noack: bool) {
+        self.last_delivered = self.last_delivered.max(id);
+        self.create_consumer(consumer);
+        if !noack {
+            self.claim(id, consumer, now, 1);
+        }
+    }
+
+    /// Gives a consumer a message it already has pending once more.
+    pub fn redeliver(&mut self, id: StreamId, now: u64) {
+        if let Some(pending) = self.pending.get_mut(&id) {
+            pending.delivered_at = now;
+            pending.deliveries += 1;
+        }
+    }
+
+    /// Hands a message over to `consumer`, whoever had it before, as if it
+    /// were last delivered at `delivered_at`.
+    pub fn claim(&mut self, id: StreamId, consumer: &[u8], delivered_at: u64, deliveries: u64) {
+        self.ack(id);
+        self.consumers
+            .entry(consumer.to_vec())
+            .or_default()
+            .insert(id);
+        let pending = Pending {
+            consumer: consumer.to_vec(),
+            delivered_at,
+            deliveries,
+        };
+        self.pending.insert(id, pending);
+    }
+
+    /// Removes a message from the pending entries lists, returning whether
+    /// it was there.
+    pub fn ack(&mut self, id: StreamId) -> bool {
+        match self.pending.remove(&id) {
+            Some(pending) => {
+                if let Some(owned) = self.consumers.get_mut(&pending.consumer) {
+                    owned.remove(&id);
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn pending(&self, id: StreamId) -> Option<&Pending> {
+        self.pending.get(&id)
+    }
+
+    /// The group's pending messages with IDs from `start` to `end`.
+    pub fn pending_range(
+        &self,
+        start: StreamId,
+        end: StreamId,
+    ) -> impl DoubleEndedIterator<Item = (&StreamId, &Pending)> {
+        let range = if start <= end {
+            Some(self.pending.range(start..=end))
+        } else {
+            None
+        };
+        range.into_iter().flatten()
+    }
+
+    /// The messages pending for one consumer with IDs after `after`.
+    pub fn pending_for(
+        &self,
+        consumer: &[u8],
+        after: StreamId,
+    ) -> impl Iterator<Item = StreamId> + '_ {
+        let pending = match after.next() {
+            Some(start) => self
+                .consumers
+                .get(consumer)
+                .map(|pending| pending.range(start..)),
+            None => None,
+        };
+        pending.into_iter().flatten().copied()
+    }
+
+    pub fn pending_len(&self) -> usize {
+        self.pending.len()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ConsumerGroup, StreamId};
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+    use std::collections::{BTreeMap, HashMap};
+
+    #[derive(Debug, Clone)]
+    enum Op {
+        Deliver(u64, u8),
+        Claim(u64, u8),
+        Ack(u64),
+        DeleteConsumer(u8),
+    }
+
+    fn ops() -> impl Strategy<Value = Vec<Op>> {
+        vec(
+            prop_oneof![
+                (0u64..20, 0u8..4).prop_map(|(id, c)| Op::Deliver(id, c)),
+                (0u64..20, 0u8..4).prop_map(|(id, c)| Op::Claim(id, c)),
+                (0u64..20).prop_map(Op::Ack),
+                (0u8..4).prop_map(Op::DeleteConsumer),
+            ],
+            0..200,
+        )
+    }
+
+    proptest! {
+        #[test]
+        fn consumer_lists_agree_with_the_group(ops in ops()) {
+            let mut group = ConsumerGroup::new(StreamId::MIN);
+            let mut model: BTreeMap<u64, u8> = BTreeMap::new();
+            for op in ops {
+                match op {
+                    Op::Deliver(ms, c) => {
+                        group.deliver(StreamId::new(ms, 0), &[c], 0, false);
+                        model.insert(ms, c);
+                    }
+                    Op::Claim(ms, c) => {
+                        group.claim(StreamId::new(ms, 0), &[c], 0, 1);
+                        model.insert(ms, c);
+                    }
+                    Op::Ack(ms) => assert_eq!(group.ack(StreamId::new(ms, 0)), model.remove(&ms).is_some()),
+                    Op::DeleteConsumer(c) => {
+                        let had = model.values().filter(|owner| **owner == c).count();
+                        let deleted = group.delete_consumer(&[c]);
+                        assert!(deleted.is_none() || deleted == Some(had));
+                        model.retain(|_, owner| *owner != c);
+                    }
+                }
+            }
+            assert_eq!(group.pending_len(), model.len());
+            let mut owned: HashMap<u8, Vec<u64>> = HashMap::new();
+            model.iter().for_each(|(ms, c)| owned.entry(*c).or_default().push(*ms));
+            group.consumers().try_for_each(|(name, count)| {
+                let expected: Vec<u64> = owned.remove(&name[0]).unwrap_or_default();
+                prop_assert_eq!(count, expected.len());
+                let pending: Vec<u64> = group.pending_for(name, StreamId::MIN).map(|id| id.ms).collect();
+                prop_assert_eq!(pending, expected.into_iter().filter(|ms| *ms > 0).collect::<Vec<_>>());
+                Ok(())
+            })?;
+            assert!(owned.is_empty())
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use super::super::domain::Primitive;
+use super::{ConsumerGroup, StreamId};
+use std::collections::BTreeMap;
+
+/// An entry's fields and values, in the order they were given.
+pub type Fields = Vec<(Primitive, Primitive)>;
+
+/// An entry's ID and its fields.
+pub type Entry = (StreamId, Fields);
+
+/// An append-only log of entries ordered by ID, along with the consumer
+/// groups reading it. The entries live in a B-tree, so that ranges of IDs
+/// are found in logarithmic time however long the stream grows. A stream
+/// remembers the last ID it handed out even once that entry is trimmed, so
+/// IDs never go backwards.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Stream {
+    entries: Entries,
+    last_id: StreamId,
+    groups: BTreeMap<Vec<u8>, ConsumerGroup>,
+}
+
+/// The entries of a stream, kept apart from its groups so that a group can
+/// change while it reads them.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Entries(BTreeMap<StreamId, Fields>);
+
+impl Entries {
+    pub fn get(&self, id: StreamId) -> Option<&Fields> {
+        self.0.get(&id)
+    }
+
+    /// The entries with IDs from `start` to `end`, both included.
+    pub fn range(
+        &self,
+        start: StreamId,
+        end: StreamId,
+    ) -> impl DoubleEndedIterator<Item = (&StreamId, &Fields)> {
+        let range = if start <= end {
+            Some(self.0.range(start..=end))
+        } else {
+            None
+        };
+        range.into_iter().flatten()
+    }
+}
+
+impl Stream {
+    pub fn new() -> Self {
+        Stream::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.0.is_empty()
+    }
+
+    pub fn last_id(&self) -> StreamId {
+        self.last_id
+    }
+
+    /// Appends an entry. Its ID must be greater than any the stream has had.
+    pub fn append(&mut self, id: StreamId, fields: Fields) {
+        debug_assert!(id > self.last_id || (id == StreamId::MIN && self.is_empty()));
+        self.entries.0.insert(id, fields);
+        self.last_id = id;
+    }
+
+    pub fn entries(&self) -> &Entries {
+        &self.entries
+    }
+
+    /// The entries with IDs from `start` to `end`, both included.
+    pub fn range(
+        &self,
+        start: StreamId,
+        end: StreamId,
+    ) -> impl DoubleEndedIterator<Item = (&StreamId, &Fields)> {
+        self.entries.range(start, end)
+    }
+
+    /// Removes entries from the start of the stream for as long as `trim`
+    /// holds for the stream and the next entry's ID, but no more than
+    /// `limit` of them. Returns how many went.
+    pub fn trim_while<F>(&mut self, limit: Option<usize>, trim: F) -> usize
+    where
+        F: Fn(usize, StreamId) -> bool,
+    {
+        let entries = &mut self.entries.0;
+        let mut trimmed = 0;
+        while limit.is_none_or(|limit| trimmed < limit) {
+            match entries.first_key_value() {
+                Some((id, _)) if trim(entries.len(), *id) => {
+                    entries.pop_first();
+                    trimmed += 1;
+                }
+                _ => break,
+            }
+        }
+        trimmed
+    }
+
+    pub fn group(&self, name: &[u8]) -> Option<&ConsumerGroup> {
+        self.groups.get(name)
+    }
+
+    /// A consumer group along with the entries it reads.
+    pub fn group_mut(&mut self, name: &[u8]) -> Option<(&mut ConsumerGroup, &Entries)> {
+        let group = self.groups.get_mut(name)?;
+        Some((group, &self.entries))
+    }
+
+    /// Adds a group, returning whether there wasn't one by that name yet.
+    pub fn create_group(&mut self, name: &[u8], group: ConsumerGroup) -> bool {
+        if self.groups.contains_key(name) {
+            return false;
+        }
+        self.groups.insert(name.to_vec(), group);
+        true
+    }
+
+    pub fn destroy_group(&mut self, name: &[u8]) -> bool {
+        self.groups.remove(name).is_some()
+    }
+}
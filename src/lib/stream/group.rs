@@ -0,0 +1,151 @@
+use super::super::domain::{Data, Escaped};
+use super::super::errors::ApplicationError;
+use super::read::After;
+use super::utilities::{get_group_mut, get_stream, get_stream_mut, no_group};
+use super::{ConsumerGroup, Stream, StreamId};
+use std::collections::HashMap;
+
+/// The ID a group starts after: a given one, or `$` for the stream's last.
+fn start_id(stream: &Stream, start: After) -> StreamId {
+    match start {
+        After::Id(id) => id,
+        After::Last => stream.last_id(),
+    }
+}
+
+/// XGROUP CREATE. Unless `mkstream` is set, the stream has to exist.
+pub fn create_command(
+    store: &mut HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    group: &[u8],
+    start: After,
+    mkstream: bool,
+) -> Result<(), ApplicationError> {
+    if get_stream(store, key)?.is_none() {
+        if !mkstream {
+            return Err(format!(
+                "There is no stream at {}; use MKSTREAM to create one",
+                Escaped(key)
+            )
+            .into());
+        }
+        store.insert(key.to_vec(), Data::Stream(Stream::new()));
+    }
+    let stream = match store.get_mut(key) {
+        Some(Data::Stream(stream)) => stream,
+        _ => unreachable!("the stream was found or created above"),
+    };
+    let id = start_id(stream, start);
+    if stream.create_group(group, ConsumerGroup::new(id)) {
+        Ok(())
+    } else {
+        Err(format!("Consumer group {} already exists", Escaped(group)).into())
+    }
+}
+
+/// XGROUP SETID, which moves the group's last delivered ID.
+pub fn set_id_command(
+    store: &mut HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    group: &[u8],
+    start: After,
+) -> Result<(), ApplicationError> {
+    let stream = get_stream_mut(store, key)?.ok_or_else(|| no_group(key, group))?;
+    let id = start_id(stream, start);
+    let (group, _) = stream
+        .group_mut(group)
+        .ok_or_else(|| no_group(key, group))?;
+    group.set_last_delivered(id);
+    Ok(())
+}
+
+/// XGROUP DESTROY, which says whether there was a group to destroy.
+pub fn destroy_command(
+    store: &mut HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    group: &[u8],
+) -> Result<bool, ApplicationError> {
+    match get_stream_mut(store, key)? {
+        Some(stream) => Ok(stream.destroy_group(group)),
+        None => Err(format!("There is no stream at {}", Escaped(key)).into()),
+    }
+}
+
+/// XGROUP CREATECONSUMER, which says whether the consumer is new.
+pub fn create_consumer_command(
+    store: &mut HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    group: &[u8],
+    consumer: &[u8],
+) -> Result<bool, ApplicationError> {
+    let (group, _) = get_group_mut(store, key, group)?;
+    Ok(group.create_consumer(consumer))
+}
+
+/// XGROUP DELCONSUMER, which returns how many messages the consumer had
+/// pending. They are dropped with it.
+pub fn delete_consumer_command(
+    store: &mut HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    group: &[u8],
+    consumer: &[u8],
+) -> Result<usize, ApplicationError> {
+    let (group, _) = get_group_mut(store, key, group)?;
+    Ok(group.delete_consumer(consumer).unwrap_or(0))
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::add::{self, NewId, Options};
+    use super::super::read::After;
+    use super::super::utilities::get_group;
+    use super::super::StreamId;
+    use super::{
+        create_command, create_consumer_command, delete_consumer_command, destroy_command,
+        set_id_command, Data, HashMap,
+    };
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn groups_start_where_asked(key in vec(any::<u8>(), 0..8), n in 0u64..20, at in 0u64..20) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            create_command(&mut store, &key, b"g", After::Last, false).unwrap_err();
+            create_command(&mut store, &key, b"g", After::Last, true)?;
+            for now in 1..=n {
+                add::command(&mut store, &key, Options::default(), NewId::Auto, vec![], now)?;
+            }
+            assert_eq!(get_group(&store, &key, b"g")?.last_delivered(), StreamId::MIN);
+            create_command(&mut store, &key, b"g", After::Last, false).unwrap_err();
+            create_command(&mut store, &key, b"h", After::Last, false)?;
+            assert_eq!(get_group(&store, &key, b"h")?.last_delivered(), StreamId::new(n, 0));
+            set_id_command(&mut store, &key, b"g", After::Id(StreamId::new(at, 0)))?;
+            assert_eq!(get_group(&store, &key, b"g")?.last_delivered(), StreamId::new(at, 0));
+            assert!(destroy_command(&mut store, &key, b"g")?);
+            assert!(!destroy_command(&mut store, &key, b"g")?);
+            get_group(&store, &key, b"g").unwrap_err();
+        }
+    }
+
+    #[test]
+    fn consumers_come_and_go() {
+        let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+        create_consumer_command(&mut store, b"s", b"g", b"c").unwrap_err();
+        create_command(&mut store, b"s", b"g", After::Last, true).unwrap();
+        assert!(create_consumer_command(&mut store, b"s", b"g", b"c").unwrap());
+        assert!(!create_consumer_command(&mut store, b"s", b"g", b"c").unwrap());
+        assert_eq!(
+            delete_consumer_command(&mut store, b"s", b"g", b"c").unwrap(),
+            0
+        );
+        assert_eq!(
+            delete_consumer_command(&mut store, b"s", b"g", b"c").unwrap(),
+            0
+        );
+        assert_eq!(
+            get_group(&store, b"s", b"g").unwrap().consumers().count(),
+            0
+        );
+    }
+}
@@ -0,0 +1,104 @@
+/// An entry ID: the millisecond the entry was added in and a sequence
+/// number that tells apart the entries added in the same millisecond.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Default)]
+pub struct StreamId {
+    pub ms: u64,
+    pub seq: u64,
+}
+
+impl StreamId {
+    pub const MIN: StreamId = StreamId { ms: 0, seq: 0 };
+    pub const MAX: StreamId = StreamId {
+        ms: u64::MAX,
+        seq: u64::MAX,
+    };
+
+    pub fn new(ms: u64, seq: u64) -> Self {
+        StreamId { ms, seq }
+    }
+
+    /// Parses `ms-seq`, or a bare `ms` with the sequence number defaulting
+    /// to `seq`.
+    pub fn parse(arg: &[u8], seq: u64) -> Option<Self> {
+        let arg = std::str::from_utf8(arg).ok()?;
+        let number = |part: &str| match part.bytes().all(|b| b.is_ascii_digit()) {
+            true => part.parse::<u64>().ok(),
+            false => None,
+        };
+        match arg.split_once('-') {
+            Some((ms, seq)) => Some(StreamId::new(number(ms)?, number(seq)?)),
+            None => Some(StreamId::new(number(arg)?, seq)),
+        }
+    }
+
+    /// The smallest ID greater than this one.
+    pub fn next(self) -> Option<Self> {
+        match self.seq.checked_add(1) {
+            Some(seq) => Some(StreamId::new(self.ms, seq)),
+            None => Some(StreamId::new(self.ms.checked_add(1)?, 0)),
+        }
+    }
+
+    /// The greatest ID smaller than this one.
+    pub fn prev(self) -> Option<Self> {
+        match self.seq.checked_sub(1) {
+            Some(seq) => Some(StreamId::new(self.ms, seq)),
+            None => Some(StreamId::new(self.ms.checked_sub(1)?, u64::MAX)),
+        }
+    }
+}
+
+impl std::fmt::Display for StreamId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{}", self.ms, self.seq)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::StreamId;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn ids_round_trip(ms in any::<u64>(), seq in any::<u64>()) {
+            let id = StreamId::new(ms, seq);
+            assert_eq!(StreamId::parse(id.to_string().as_bytes(), 0), Some(id));
+            assert_eq!(StreamId::parse(ms.to_string().as_bytes(), seq), Some(id))
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn neighbours_are_adjacent(ms in any::<u64>(), seq in any::<u64>()) {
+            let id = StreamId::new(ms, seq);
+            if let Some(next) = id.next() {
+                assert!(next > id);
+                assert_eq!(next.prev(), Some(id));
+            }
+            if let Some(prev) = id.prev() {
+                assert!(prev < id);
+                assert_eq!(prev.next(), Some(id));
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_ids() {
+        [
+            "",
+            "-",
+            "1-",
+            "-1",
+            "+1",
+            "1-+1",
+            "1-2-3",
+            "a",
+            "18446744073709551616",
+        ]
+        .iter()
+        .for_each(|id| assert_eq!(StreamId::parse(id.as_bytes(), 0), None));
+        assert_eq!(StreamId::MAX.next(), None);
+        assert_eq!(StreamId::MIN.prev(), None);
+    }
+}
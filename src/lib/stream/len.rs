@@ -0,0 +1,27 @@
+use super::super::domain::Data;
+use super::super::errors::ApplicationError;
+use super::utilities::get_stream;
+use std::collections::HashMap;
+
+pub fn command(store: &HashMap<Vec<u8>, Data>, key: &[u8]) -> Result<usize, ApplicationError> {
+    Ok(get_stream(store, key)?.map_or(0, |stream| stream.len()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::add::{self, NewId, Options};
+    use super::{command, Data, HashMap};
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn counts_entries(key in vec(any::<u8>(), 0..32), n in 0u64..100) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            for now in 0..n {
+                add::command(&mut store, &key, Options::default(), NewId::Auto, vec![], now)?;
+            }
+            assert_eq!(command(&store, &key)?, n as usize)
+        }
+    }
+}
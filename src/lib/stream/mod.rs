@@ -0,0 +1,19 @@
+pub mod ack;
+pub mod add;
+pub mod autoclaim;
+pub mod claim;
+mod consumer_group;
+mod entries;
+pub mod group;
+mod id;
+pub mod len;
+pub mod pending;
+pub mod range;
+pub mod read;
+pub mod readgroup;
+pub mod trim;
+mod utilities;
+
+pub use consumer_group::{ConsumerGroup, Pending};
+pub use entries::{Entries, Entry, Fields, Stream};
+pub use id::StreamId;
@@ -0,0 +1,111 @@
+use super::super::domain::Data;
+use super::super::errors::ApplicationError;
+use super::utilities::get_group;
+use super::{Pending, StreamId};
+use std::collections::HashMap;
+
+/// What XPENDING says about a group without a range: how many messages
+/// are pending, the lowest and highest of their IDs, and how many each
+/// consumer with any has.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Summary {
+    pub count: usize,
+    pub first: Option<StreamId>,
+    pub last: Option<StreamId>,
+    pub consumers: Vec<(Vec<u8>, usize)>,
+}
+
+/// Which pending messages XPENDING lists.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Filter {
+    /// Only messages idle for at least this many milliseconds.
+    pub min_idle: u64,
+    pub start: StreamId,
+    pub end: StreamId,
+    pub count: usize,
+    pub consumer: Option<Vec<u8>>,
+}
+
+pub fn summary_command(
+    store: &HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    group: &[u8],
+) -> Result<Summary, ApplicationError> {
+    let group = get_group(store, key, group)?;
+    let pending = || group.pending_range(StreamId::MIN, StreamId::MAX);
+    Ok(Summary {
+        count: group.pending_len(),
+        first: pending().next().map(|(id, _)| *id),
+        last: pending().next_back().map(|(id, _)| *id),
+        consumers: group
+            .consumers()
+            .filter(|(_, pending)| *pending > 0)
+            .map(|(name, pending)| (name.to_vec(), pending))
+            .collect(),
+    })
+}
+
+/// The pending messages that pass the filter, where `now` is the current
+/// time in milliseconds.
+pub fn command(
+    store: &HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    group: &[u8],
+    filter: &Filter,
+    now: u64,
+) -> Result<Vec<(StreamId, Pending)>, ApplicationError> {
+    let group = get_group(store, key, group)?;
+    Ok(group
+        .pending_range(filter.start, filter.end)
+        .filter(|(_, pending)| pending.idle(now) >= filter.min_idle)
+        .filter(|(_, pending)| {
+            filter
+                .consumer
+                .as_ref()
+                .is_none_or(|consumer| pending.consumer == *consumer)
+        })
+        .take(filter.count)
+        .map(|(id, pending)| (*id, pending.clone()))
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::add::{self, NewId};
+    use super::super::group;
+    use super::super::read::After;
+    use super::super::readgroup::{self, Start};
+    use super::{command, summary_command, Data, Filter, HashMap, StreamId, Summary};
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn lists_what_summaries_count(reads in vec((0u8..3, 0u64..100), 0..20), min_idle in 0u64..100) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            group::create_command(&mut store, b"s", b"g", After::Last, true)?;
+            for now in 1..=30 {
+                add::command(&mut store, b"s", Default::default(), NewId::Auto, vec![], now)?;
+            }
+            for (consumer, now) in reads {
+                let options = readgroup::Options { group: b"g".to_vec(), consumer: vec![consumer], count: Some(2), noack: false };
+                readgroup::command(&mut store, b"s", &options, Start::New, now)?;
+            }
+            let Summary { count, first, last, consumers } = summary_command(&store, b"s", b"g")?;
+            let all = Filter { min_idle: 0, start: StreamId::MIN, end: StreamId::MAX, count: usize::MAX, consumer: None };
+            let listed = command(&store, b"s", b"g", &all, 100)?;
+            assert_eq!(listed.len(), count);
+            assert_eq!(listed.first().map(|(id, _)| *id), first);
+            assert_eq!(listed.last().map(|(id, _)| *id), last);
+            assert_eq!(consumers.iter().map(|(_, n)| n).sum::<usize>(), count);
+            consumers.iter().try_for_each(|(consumer, n)| {
+                let theirs = Filter { consumer: Some(consumer.clone()), ..all.clone() };
+                prop_assert_eq!(command(&store, b"s", b"g", &theirs, 100)?.len(), *n);
+                Ok(())
+            })?;
+            let idle = Filter { min_idle, ..all };
+            let expected = listed.iter().filter(|(_, pending)| 100 - pending.delivered_at >= min_idle).count();
+            assert_eq!(command(&store, b"s", b"g", &idle, 100)?.len(), expected)
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use super::super::domain::Data;
+use super::super::errors::ApplicationError;
+use super::utilities::get_stream;
+use super::{Entry, StreamId};
+use std::collections::HashMap;
+
+/// The entries with IDs from `start` to `end`, both included, up to
+/// `count` of them, last first when `reverse` is set.
+pub fn command(
+    store: &HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    start: StreamId,
+    end: StreamId,
+    count: Option<usize>,
+    reverse: bool,
+) -> Result<Vec<Entry>, ApplicationError> {
+    let stream = match get_stream(store, key)? {
+        Some(stream) => stream,
+        None => return Ok(Vec::new()),
+    };
+    let range = stream.range(start, end);
+    let range: Box<dyn Iterator<Item = _>> = if reverse {
+        Box::new(range.rev())
+    } else {
+        Box::new(range)
+    };
+    Ok(range
+        .take(count.unwrap_or(usize::MAX))
+        .map(|(id, fields)| (*id, fields.clone()))
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::add::{self, NewId, Options};
+    use super::{command, Data, HashMap, StreamId};
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn returns_ids_in_range(
+            times in vec(0u64..20, 0..100),
+            bounds in (0u64..25, 0u64..25, 0u64..3, 0u64..3),
+            count in proptest::option::of(0usize..20),
+            reverse in any::<bool>()
+        ) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            let mut ids = Vec::new();
+            for now in times {
+                let id = add::command(&mut store, b"s", Options::default(), NewId::Auto, vec![], now)?;
+                ids.push(id.unwrap());
+            }
+            let (start, end) = (StreamId::new(bounds.0, bounds.2), StreamId::new(bounds.1, bounds.3));
+            let mut expected: Vec<StreamId> = ids.into_iter().filter(|id| start <= *id && *id <= end).collect();
+            if reverse {
+                expected.reverse();
+            }
+            expected.truncate(count.unwrap_or(usize::MAX));
+            let range = command(&store, b"s", start, end, count, reverse)?;
+            assert_eq!(range.into_iter().map(|(id, _)| id).collect::<Vec<_>>(), expected)
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use super::super::domain::Data;
+use super::super::errors::ApplicationError;
+use super::utilities::get_stream;
+use super::{Entry, StreamId};
+use std::collections::HashMap;
+
+/// Where XREAD reads a stream from.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum After {
+    Id(StreamId),
+    /// `$`: only entries added from now on.
+    Last,
+}
+
+/// A stream's key and the entries read from it.
+pub type Read = (Vec<u8>, Vec<Entry>);
+
+/// The entries of one stream that come after `after`, up to `count` of them.
+pub fn command(
+    store: &HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    after: After,
+    count: Option<usize>,
+) -> Result<Vec<Entry>, ApplicationError> {
+    let stream = match get_stream(store, key)? {
+        Some(stream) => stream,
+        None => return Ok(Vec::new()),
+    };
+    let start = match after {
+        After::Id(id) => id.next(),
+        After::Last => None,
+    };
+    Ok(start
+        .into_iter()
+        .flat_map(|start| stream.range(start, StreamId::MAX))
+        .take(count.unwrap_or(usize::MAX))
+        .map(|(id, fields)| (*id, fields.clone()))
+        .collect())
+}
+
+/// The streams that have entries after their IDs, with those entries.
+pub fn multi_command(
+    store: &HashMap<Vec<u8>, Data>,
+    keys: &[Vec<u8>],
+    afters: &[After],
+    count: Option<usize>,
+) -> Result<Vec<Read>, ApplicationError> {
+    keys.iter()
+        .zip(afters)
+        .map(|(key, after)| Ok((key.clone(), command(store, key, *after, count)?)))
+        .filter(|read| !matches!(read, Ok((_, entries)) if entries.is_empty()))
+        .collect()
+}
+
+/// Pins `$` down to the ID each stream has last had, so a blocked reader
+/// gets whatever is added while it waits.
+pub fn resolve(
+    store: &HashMap<Vec<u8>, Data>,
+    keys: &[Vec<u8>],
+    afters: &[After],
+) -> Result<Vec<After>, ApplicationError> {
+    keys.iter()
+        .zip(afters)
+        .map(|(key, after)| match after {
+            After::Id(id) => Ok(After::Id(*id)),
+            After::Last => Ok(After::Id(
+                get_stream(store, key)?.map_or(StreamId::MIN, |stream| stream.last_id()),
+            )),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::add::{self, NewId, Options};
+    use super::{command, multi_command, resolve, After, Data, HashMap, StreamId};
+    use proptest::collection::{hash_set, vec};
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn reads_what_comes_after(n in 0u64..50, after in 0u64..60, count in proptest::option::of(1usize..20)) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            for now in 1..=n {
+                add::command(&mut store, b"s", Options::default(), NewId::Auto, vec![], now)?;
+            }
+            let read = command(&store, b"s", After::Id(StreamId::new(after, 0)), count)?;
+            let expected: Vec<u64> = (after + 1..=n).take(count.unwrap_or(usize::MAX)).collect();
+            assert_eq!(read.into_iter().map(|(id, _)| id.ms).collect::<Vec<_>>(), expected)
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn last_reads_only_new_entries(keys in hash_set(vec(any::<u8>(), 1..4), 1..5), n in 0u64..10) {
+            let keys: Vec<Vec<u8>> = keys.into_iter().collect();
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            for (i, key) in keys.iter().enumerate() {
+                for now in 0..n * i as u64 {
+                    add::command(&mut store, key, Options::default(), NewId::Auto, vec![], now)?;
+                }
+            }
+            let lasts = vec![After::Last; keys.len()];
+            assert_eq!(multi_command(&store, &keys, &lasts, None)?, vec![]);
+            let resolved = resolve(&store, &keys, &lasts)?;
+            assert_eq!(multi_command(&store, &keys, &resolved, None)?, vec![]);
+            let added = add::command(&mut store, &keys[0], Options::default(), NewId::Auto, vec![], 100)?.unwrap();
+            let read = multi_command(&store, &keys, &resolved, None)?;
+            assert_eq!(read, vec![(keys[0].clone(), vec![(added, vec![])])])
+        }
+    }
+}
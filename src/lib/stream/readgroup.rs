@@ -0,0 +1,204 @@
+use super::super::domain::Data;
+use super::super::errors::ApplicationError;
+use super::utilities::get_group_mut;
+use super::{Fields, StreamId};
+use std::collections::HashMap;
+
+/// Where XREADGROUP reads a stream from.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Start {
+    /// `>`: entries never delivered to the group.
+    New,
+    /// The consumer's own pending messages after the given ID.
+    History(StreamId),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Options {
+    pub group: Vec<u8>,
+    pub consumer: Vec<u8>,
+    pub count: Option<usize>,
+    /// Deliver new entries without waiting for them to be acknowledged.
+    pub noack: bool,
+}
+
+/// A delivered message, without fields if it was deleted from the stream
+/// while it was pending.
+pub type Delivered = (StreamId, Option<Fields>);
+
+/// A stream's key and the messages read from it.
+pub type Read = (Vec<u8>, Vec<Delivered>);
+
+/// Reads one stream on behalf of a consumer, where `now` is the current
+/// time in milliseconds.
+pub fn command(
+    store: &mut HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    options: &Options,
+    start: Start,
+    now: u64,
+) -> Result<Vec<Delivered>, ApplicationError> {
+    let (group, entries) = get_group_mut(store, key, &options.group)?;
+    let count = options.count.unwrap_or(usize::MAX);
+    match start {
+        Start::New => {
+            let delivered: Vec<Delivered> = match group.last_delivered().next() {
+                Some(start) => entries
+                    .range(start, StreamId::MAX)
+                    .take(count)
+                    .map(|(id, fields)| (*id, Some(fields.clone())))
+                    .collect(),
+                None => Vec::new(),
+            };
+            group.create_consumer(&options.consumer);
+            delivered
+                .iter()
+                .for_each(|(id, _)| group.deliver(*id, &options.consumer, now, options.noack));
+            Ok(delivered)
+        }
+        Start::History(after) => {
+            let ids: Vec<StreamId> = group
+                .pending_for(&options.consumer, after)
+                .take(count)
+                .collect();
+            group.create_consumer(&options.consumer);
+            Ok(ids
+                .into_iter()
+                .map(|id| {
+                    group.redeliver(id, now);
+                    (id, entries.get(id).cloned())
+                })
+                .collect())
+        }
+    }
+}
+
+/// Reads every stream, leaving out those with no new entries. A consumer's
+/// history is given even when it is empty.
+pub fn multi_command(
+    store: &mut HashMap<Vec<u8>, Data>,
+    keys: &[Vec<u8>],
+    starts: &[Start],
+    options: &Options,
+    now: u64,
+) -> Result<Vec<Read>, ApplicationError> {
+    let mut read = Vec::new();
+    for (key, start) in keys.iter().zip(starts) {
+        let delivered = command(store, key, options, *start, now)?;
+        if !delivered.is_empty() || *start != Start::New {
+            read.push((key.clone(), delivered));
+        }
+    }
+    Ok(read)
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::add::{self, NewId};
+    use super::super::group;
+    use super::super::read::After;
+    use super::super::trim::{Threshold, Trim};
+    use super::super::utilities::get_group;
+    use super::{command, Data, HashMap, Options, Start, StreamId};
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    fn options(consumer: u8, noack: bool) -> Options {
+        Options {
+            group: b"g".to_vec(),
+            consumer: vec![consumer],
+            count: Some(3),
+            noack,
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn every_entry_goes_to_one_consumer(
+            reads in vec((0u8..3, any::<bool>()), 0..40),
+            n in 0u64..60
+        ) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            group::create_command(&mut store, b"s", b"g", After::Last, true)?;
+            for now in 1..=n {
+                add::command(&mut store, b"s", Default::default(), NewId::Auto, vec![], now)?;
+            }
+            let mut delivered = Vec::new();
+            let mut pending = 0;
+            for (consumer, noack) in reads {
+                let read = command(&mut store, b"s", &options(consumer, noack), Start::New, 0)?;
+                if !noack {
+                    pending += read.len();
+                }
+                delivered.extend(read.into_iter().map(|(id, _)| id.ms));
+            }
+            let expected: Vec<u64> = (1..=n).take(delivered.len()).collect();
+            assert_eq!(delivered, expected);
+            assert_eq!(get_group(&store, b"s", b"g")?.pending_len(), pending)
+        }
+    }
+
+    #[test]
+    fn history_shows_deleted_entries() {
+        let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+        group::create_command(&mut store, b"s", b"g", After::Last, true).unwrap();
+        for now in 1..=3 {
+            add::command(
+                &mut store,
+                b"s",
+                Default::default(),
+                NewId::Auto,
+                vec![],
+                now,
+            )
+            .unwrap();
+        }
+        let options = options(0, false);
+        assert_eq!(
+            command(&mut store, b"s", &options, Start::New, 10)
+                .unwrap()
+                .len(),
+            3
+        );
+        assert_eq!(
+            command(&mut store, b"s", &options, Start::New, 10).unwrap(),
+            vec![]
+        );
+        let trim = Trim {
+            threshold: Threshold::MaxLen(2),
+            limit: None,
+        };
+        super::super::trim::command(&mut store, b"s", trim).unwrap();
+        let history = command(
+            &mut store,
+            b"s",
+            &options,
+            Start::History(StreamId::MIN),
+            20,
+        )
+        .unwrap();
+        assert_eq!(
+            history,
+            vec![
+                (StreamId::new(1, 0), None),
+                (StreamId::new(2, 0), Some(vec![])),
+                (StreamId::new(3, 0), Some(vec![])),
+            ]
+        );
+        let pending = get_group(&store, b"s", b"g")
+            .unwrap()
+            .pending(StreamId::new(1, 0))
+            .cloned()
+            .unwrap();
+        assert_eq!((pending.delivered_at, pending.deliveries), (20, 2));
+        let other = super::Options {
+            consumer: b"other".to_vec(),
+            ..options
+        };
+        assert_eq!(
+            command(&mut store, b"s", &other, Start::History(StreamId::MIN), 20).unwrap(),
+            vec![]
+        );
+        command(&mut store, b"missing", &other, Start::New, 20).unwrap_err();
+    }
+}
@@ -0,0 +1,88 @@
+use super::super::domain::Data;
+use super::super::errors::ApplicationError;
+use super::utilities::get_stream_mut;
+use super::{Stream, StreamId};
+use std::collections::HashMap;
+
+/// What XTRIM, or XADD, cuts a stream down to.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Threshold {
+    /// At most this many entries.
+    MaxLen(usize),
+    /// Only entries with at least this ID.
+    MinId(StreamId),
+}
+
+/// A threshold along with the most entries one trim may remove. Streams
+/// are always trimmed exactly, which an approximate (`~`) trim allows for.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Trim {
+    pub threshold: Threshold,
+    pub limit: Option<usize>,
+}
+
+impl Trim {
+    pub fn apply(&self, stream: &mut Stream) -> usize {
+        match self.threshold {
+            Threshold::MaxLen(max) => stream.trim_while(self.limit, |len, _| len > max),
+            Threshold::MinId(min) => stream.trim_while(self.limit, |_, id| id < min),
+        }
+    }
+}
+
+pub fn command(
+    store: &mut HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    trim: Trim,
+) -> Result<usize, ApplicationError> {
+    Ok(get_stream_mut(store, key)?.map_or(0, |stream| trim.apply(stream)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::domain::Primitive;
+    use super::super::{Stream, StreamId};
+    use super::{command, Data, HashMap, Threshold, Trim};
+    use proptest::prelude::*;
+
+    fn stream(len: u64) -> Stream {
+        let mut stream = Stream::new();
+        (1..=len).for_each(|ms| {
+            stream.append(
+                StreamId::new(ms, 0),
+                vec![(Primitive::from("n"), Primitive::from(ms as i64))],
+            )
+        });
+        stream
+    }
+
+    proptest! {
+        #[test]
+        fn trims_to_max_len(len in 0u64..200, max in 0usize..200, limit in proptest::option::of(0usize..200)) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            store.insert(b"s".to_vec(), Data::Stream(stream(len)));
+            let excess = (len as usize).saturating_sub(max);
+            let expected = limit.map_or(excess, |limit| excess.min(limit));
+            let trim = Trim { threshold: Threshold::MaxLen(max), limit };
+            assert_eq!(command(&mut store, b"s", trim)?, expected);
+            match store.get(b"s".as_slice()) {
+                Some(Data::Stream(stream)) => {
+                    assert_eq!(stream.len(), len as usize - expected);
+                    assert_eq!(stream.range(StreamId::MIN, StreamId::MAX).next().map(|(id, _)| id.ms), Some(expected as u64 + 1).filter(|_| expected < len as usize));
+                    assert_eq!(stream.last_id(), StreamId::new(len, 0));
+                }
+                _ => panic!("trimming removed the stream"),
+            }
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn trims_to_min_id(len in 0u64..200, min in 0u64..200) {
+            let mut stream = stream(len);
+            let trim = Trim { threshold: Threshold::MinId(StreamId::new(min, 0)), limit: None };
+            assert_eq!(trim.apply(&mut stream), min.saturating_sub(1).min(len) as usize);
+            assert!(stream.range(StreamId::MIN, StreamId::MAX).all(|(id, _)| id.ms >= min))
+        }
+    }
+}
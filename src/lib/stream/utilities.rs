@@ -0,0 +1,52 @@
+use super::super::domain::{Data, Escaped};
+use super::super::errors::ApplicationError;
+use super::{ConsumerGroup, Entries, Stream};
+use std::collections::HashMap;
+
+pub fn get_stream<'a>(
+    store: &'a HashMap<Vec<u8>, Data>,
+    key: &[u8],
+) -> Result<Option<&'a Stream>, ApplicationError> {
+    match store.get(key) {
+        Some(Data::Stream(stream)) => Ok(Some(stream)),
+        Some(_) => Err(format!("Value at {} is not a stream", Escaped(key)).into()),
+        None => Ok(None),
+    }
+}
+
+pub fn get_stream_mut<'a>(
+    store: &'a mut HashMap<Vec<u8>, Data>,
+    key: &[u8],
+) -> Result<Option<&'a mut Stream>, ApplicationError> {
+    match store.get_mut(key) {
+        Some(Data::Stream(stream)) => Ok(Some(stream)),
+        Some(_) => Err(format!("Value at {} is not a stream", Escaped(key)).into()),
+        None => Ok(None),
+    }
+}
+
+/// The error for commands that need a consumer group that isn't there.
+pub fn no_group(key: &[u8], group: &[u8]) -> ApplicationError {
+    format!("No consumer group {} at {}", Escaped(group), Escaped(key)).into()
+}
+
+pub fn get_group<'a>(
+    store: &'a HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    group: &[u8],
+) -> Result<&'a ConsumerGroup, ApplicationError> {
+    get_stream(store, key)?
+        .and_then(|stream| stream.group(group))
+        .ok_or_else(|| no_group(key, group))
+}
+
+/// A consumer group for writing, along with the entries of its stream.
+pub fn get_group_mut<'a>(
+    store: &'a mut HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    group: &[u8],
+) -> Result<(&'a mut ConsumerGroup, &'a Entries), ApplicationError> {
+    get_stream_mut(store, key)?
+        .and_then(|stream| stream.group_mut(group))
+        .ok_or_else(|| no_group(key, group))
+}
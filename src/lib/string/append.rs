@@ -0,0 +1,49 @@
+use super::super::domain::Data;
+use super::super::errors::ApplicationError;
+use super::utilities::{check_len, get_or_create_string, get_string};
+use std::collections::HashMap;
+
+/// APPEND, which returns the string's new length. A missing string counts
+/// as an empty one.
+pub fn command(
+    store: &mut HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    value: &[u8],
+) -> Result<usize, ApplicationError> {
+    let len = get_string(store, key)?.map_or(0, |p| p.len());
+    check_len(len + value.len())?;
+    let string = get_or_create_string(store, key)?;
+    Ok(string.edit(|bytes| {
+        bytes.extend_from_slice(value);
+        bytes.len()
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::get;
+    use super::{command, Data, HashMap};
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn appends_concatenate(key in vec(any::<u8>(), 0..8), values in vec(vec(any::<u8>(), 0..8), 1..10)) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            for value in &values {
+                command(&mut store, &key, value)?;
+            }
+            let expected = values.concat();
+            assert_eq!(get::command(&store, &key)?.map(|p| p.into_bytes()), Some(expected))
+        }
+    }
+
+    #[test]
+    fn appended_digits_are_numbers() {
+        let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+        command(&mut store, b"k", b"1").unwrap();
+        assert_eq!(command(&mut store, b"k", b"2").unwrap(), 2);
+        let value = get::command(&store, b"k").unwrap().unwrap();
+        assert_eq!(value.as_integer(), Some(12));
+    }
+}
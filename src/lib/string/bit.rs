@@ -0,0 +1,67 @@
+use super::super::domain::Data;
+use super::super::errors::ApplicationError;
+use super::bits;
+use super::utilities::{check_len, get_or_create_string, get_string};
+use std::collections::HashMap;
+
+/// GETBIT: the bit at `offset`, where bits past the end read as clear.
+pub fn command(
+    store: &HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    offset: u64,
+) -> Result<bool, ApplicationError> {
+    Ok(get_string(store, key)?.is_some_and(|string| bits::get(&string.as_bytes(), offset)))
+}
+
+/// SETBIT, which grows the string with zero bytes to reach `offset` and
+/// returns the bit that was there before.
+pub fn set_command(
+    store: &mut HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    offset: u64,
+    bit: bool,
+) -> Result<bool, ApplicationError> {
+    let byte = (offset / 8) as usize;
+    check_len(byte + 1)?;
+    let string = get_or_create_string(store, key)?;
+    Ok(string.edit(|bytes| {
+        if bytes.len() <= byte {
+            bytes.resize(byte + 1, 0);
+        }
+        let mask = 0x80 >> (offset % 8);
+        let previous = bytes[byte] & mask != 0;
+        if bit {
+            bytes[byte] |= mask;
+        } else {
+            bytes[byte] &= !mask;
+        }
+        previous
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{command, set_command, Data};
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+    use std::collections::HashMap;
+
+    proptest! {
+        #[test]
+        fn bits_read_back_as_they_were_set(
+            writes in vec((0u64..256, any::<bool>()), 1..64),
+            reads in vec(0u64..512, 1..16),
+        ) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            let mut model = vec![false; 512];
+            for (offset, bit) in writes {
+                let previous = set_command(&mut store, b"bits", offset, bit)?;
+                assert_eq!(previous, model[offset as usize]);
+                model[offset as usize] = bit;
+            }
+            for offset in reads {
+                assert_eq!(command(&store, b"bits", offset)?, model[offset as usize]);
+            }
+        }
+    }
+}
@@ -0,0 +1,40 @@
+use super::super::domain::Data;
+use super::super::errors::ApplicationError;
+use super::bits::{self, Range};
+use super::utilities::get_string;
+use std::collections::HashMap;
+
+/// BITCOUNT: how many bits are set in `range`.
+pub fn command(
+    store: &HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    range: Range,
+) -> Result<u64, ApplicationError> {
+    let string = match get_string(store, key)? {
+        Some(string) => string.as_bytes(),
+        None => return Ok(0),
+    };
+    Ok(range
+        .bits(string.len())
+        .map_or(0, |(first, last)| bits::count(&string, first, last)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::domain::Primitive;
+    use super::{command, Data, Range};
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+    use std::collections::HashMap;
+
+    proptest! {
+        #[test]
+        fn counting_everything_counts_every_byte(bytes in vec(any::<u8>(), 0..2048)) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            let expected: u64 = bytes.iter().map(|byte| u64::from(byte.count_ones())).sum();
+            store.insert(b"bits".to_vec(), Data::Primitive(Primitive::from(bytes)));
+            assert_eq!(command(&store, b"bits", Range::ALL)?, expected);
+            assert_eq!(command(&store, b"missing", Range::ALL)?, 0);
+        }
+    }
+}
@@ -0,0 +1,305 @@
+use super::super::domain::Data;
+use super::super::errors::ApplicationError;
+use super::bits;
+use super::utilities::{check_len, get_or_create_string, get_string};
+use std::collections::HashMap;
+
+/// An integer of 1 to 64 bits, or 63 if unsigned, stored most significant
+/// bit first from a bit offset of the string.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Field {
+    pub signed: bool,
+    pub bits: u32,
+    pub offset: u64,
+}
+
+/// What SET and INCRBY do when a value doesn't fit its field: wrap around,
+/// saturate at the nearest limit or fail, leaving the field as it was.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Overflow {
+    Wrap,
+    Sat,
+    Fail,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Op {
+    Get(Field),
+    Set(Field, i64, Overflow),
+    Incrby(Field, i64, Overflow),
+}
+
+impl Field {
+    fn last_bit(&self) -> u64 {
+        self.offset + u64::from(self.bits) - 1
+    }
+
+    fn min(&self) -> i128 {
+        if self.signed {
+            -(1 << (self.bits - 1))
+        } else {
+            0
+        }
+    }
+
+    fn max(&self) -> i128 {
+        if self.signed {
+            (1 << (self.bits - 1)) - 1
+        } else {
+            (1 << self.bits) - 1
+        }
+    }
+
+    fn get(&self, bytes: &[u8]) -> i64 {
+        let raw = (self.offset..=self.last_bit()).fold(0u64, |raw, offset| {
+            raw << 1 | u64::from(bits::get(bytes, offset))
+        });
+        self.truncate(raw)
+    }
+
+    /// Reads the low bits of `raw` as a value of the field's type.
+    fn truncate(&self, raw: u64) -> i64 {
+        let unused = 64 - self.bits;
+        if self.signed {
+            ((raw << unused) as i64) >> unused
+        } else {
+            ((raw << unused) >> unused) as i64
+        }
+    }
+
+    fn set(&self, bytes: &mut [u8], value: i64) {
+        for (at, offset) in (self.offset..=self.last_bit()).enumerate() {
+            let bit = (value as u64 >> (self.bits - 1 - at as u32)) & 1 == 1;
+            let (byte, mask) = ((offset / 8) as usize, 0x80 >> (offset % 8));
+            if bit {
+                bytes[byte] |= mask;
+            } else {
+                bytes[byte] &= !mask;
+            }
+        }
+    }
+
+    /// Adds `by` to `value`, returning the sum, or what `overflow` makes of
+    /// it if it doesn't fit, and whether it did. As in Redis, the value
+    /// SET gives an unsigned field is read as unsigned itself, so -1 is
+    /// too big rather than too small.
+    fn add(&self, value: i64, by: i64, overflow: Overflow) -> (i64, bool) {
+        let value = if self.signed {
+            i128::from(value)
+        } else {
+            i128::from(value as u64)
+        };
+        let sum = value + i128::from(by);
+        if (self.min()..=self.max()).contains(&sum) {
+            return (sum as i64, true);
+        }
+        let limited = match overflow {
+            Overflow::Sat if sum > self.max() => self.max() as i64,
+            Overflow::Sat => self.min() as i64,
+            Overflow::Wrap | Overflow::Fail => self.truncate(sum as u64),
+        };
+        (limited, false)
+    }
+}
+
+/// BITFIELD, which runs `ops` in order and replies to each with the value
+/// it read, what SET found before writing or what INCRBY left behind, or
+/// nothing for a write that failed to fit. A string is grown, or created,
+/// up front to hold every field written to, even by writes that fail.
+pub fn command(
+    store: &mut HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    ops: &[Op],
+) -> Result<Vec<Option<i64>>, ApplicationError> {
+    let last_written = ops
+        .iter()
+        .filter_map(|op| match op {
+            Op::Get(_) => None,
+            Op::Set(field, ..) | Op::Incrby(field, ..) => Some(field.last_bit()),
+        })
+        .max();
+    let len = match last_written {
+        Some(last_bit) => (last_bit / 8 + 1) as usize,
+        None => {
+            let string = get_string(store, key)?.map(|string| string.as_bytes());
+            let bytes = string.as_deref().unwrap_or(&[]);
+            return Ok(ops
+                .iter()
+                .map(|op| match op {
+                    Op::Get(field) => Some(field.get(bytes)),
+                    _ => None,
+                })
+                .collect());
+        }
+    };
+    check_len(len)?;
+    let string = get_or_create_string(store, key)?;
+    Ok(string.edit(|bytes| {
+        if bytes.len() < len {
+            bytes.resize(len, 0);
+        }
+        ops.iter()
+            .map(|op| match *op {
+                Op::Get(field) => Some(field.get(bytes)),
+                Op::Set(field, value, overflow) => {
+                    let old = field.get(bytes);
+                    let (new, fits) = field.add(value, 0, overflow);
+                    if !fits && overflow == Overflow::Fail {
+                        return None;
+                    }
+                    field.set(bytes, new);
+                    Some(old)
+                }
+                Op::Incrby(field, by, overflow) => {
+                    let (new, fits) = field.add(field.get(bytes), by, overflow);
+                    if !fits && overflow == Overflow::Fail {
+                        return None;
+                    }
+                    field.set(bytes, new);
+                    Some(new)
+                }
+            })
+            .collect()
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{command, Data, Field, Op, Overflow};
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+    use std::collections::HashMap;
+
+    fn fields() -> impl Strategy<Value = Field> {
+        prop_oneof![
+            (1u32..=64).prop_map(|bits| (true, bits)),
+            (1u32..=63).prop_map(|bits| (false, bits)),
+        ]
+        .prop_flat_map(|(signed, bits)| {
+            (0u64..200).prop_map(move |offset| Field {
+                signed,
+                bits,
+                offset,
+            })
+        })
+    }
+
+    fn overflows() -> impl Strategy<Value = Overflow> {
+        prop_oneof![
+            Just(Overflow::Wrap),
+            Just(Overflow::Sat),
+            Just(Overflow::Fail)
+        ]
+    }
+
+    fn values() -> impl Strategy<Value = i64> {
+        prop_oneof![any::<i64>(), -300i64..300]
+    }
+
+    fn ops() -> impl Strategy<Value = Vec<Op>> {
+        vec(
+            prop_oneof![
+                fields().prop_map(Op::Get),
+                (fields(), values(), overflows()).prop_map(|(f, v, o)| Op::Set(f, v, o)),
+                (fields(), values(), overflows()).prop_map(|(f, v, o)| Op::Incrby(f, v, o)),
+            ],
+            0..20,
+        )
+    }
+
+    /// A bitmap kept one bool per bit, with fields worked out in 128 bits.
+    struct Model(Vec<bool>);
+
+    impl Model {
+        fn get(&self, field: &Field) -> i128 {
+            let bits = field.offset as usize..(field.offset + u64::from(field.bits)) as usize;
+            let raw = bits.fold(0i128, |raw, at| {
+                raw * 2 + i128::from(*self.0.get(at).unwrap_or(&false))
+            });
+            if field.signed && raw >= 1 << (field.bits - 1) {
+                raw - (1 << field.bits)
+            } else {
+                raw
+            }
+        }
+
+        fn set(&mut self, field: &Field, value: i128) {
+            let end = (field.offset + u64::from(field.bits)) as usize;
+            if self.0.len() < end {
+                self.0.resize(end, false);
+            }
+            let raw = value.rem_euclid(1 << field.bits);
+            for at in 0..field.bits as usize {
+                self.0[end - 1 - at] = (raw >> at) & 1 == 1;
+            }
+        }
+
+        /// What a value becomes in a field, or nothing if it fails to fit.
+        fn fit(field: &Field, value: i128, overflow: Overflow) -> Option<i128> {
+            let (min, max) = if field.signed {
+                (
+                    -(1i128 << (field.bits - 1)),
+                    (1i128 << (field.bits - 1)) - 1,
+                )
+            } else {
+                (0, (1i128 << field.bits) - 1)
+            };
+            if value >= min && value <= max {
+                return Some(value);
+            }
+            match overflow {
+                Overflow::Fail => None,
+                Overflow::Sat => Some(value.clamp(min, max)),
+                Overflow::Wrap => {
+                    let wrapped = value.rem_euclid(1 << field.bits);
+                    Some(if wrapped > max {
+                        wrapped - (1 << field.bits)
+                    } else {
+                        wrapped
+                    })
+                }
+            }
+        }
+
+        fn run(&mut self, op: &Op) -> Option<i64> {
+            match op {
+                Op::Get(field) => Some(self.get(field) as i64),
+                Op::Set(field, value, overflow) => {
+                    let value = if field.signed {
+                        i128::from(*value)
+                    } else {
+                        i128::from(*value as u64)
+                    };
+                    let old = self.get(field);
+                    let new = Model::fit(field, value, *overflow)?;
+                    self.set(field, new);
+                    Some(old as i64)
+                }
+                Op::Incrby(field, by, overflow) => {
+                    let new = Model::fit(field, self.get(field) + i128::from(*by), *overflow)?;
+                    self.set(field, new);
+                    Some(new as i64)
+                }
+            }
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn fields_behave_like_a_bit_vector(ops in ops()) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            let mut model = Model(Vec::new());
+            let replies = command(&mut store, b"bits", &ops)?;
+            let expected: Vec<Option<i64>> = ops.iter().map(|op| model.run(op)).collect();
+            prop_assert_eq!(replies, expected);
+            let bytes = match store.get(&b"bits"[..]) {
+                Some(Data::Primitive(p)) => p.to_bytes(),
+                _ => Vec::new(),
+            };
+            let written = bytes.iter().flat_map(|byte| (0..8).map(move |at| byte & (0x80 >> at) != 0));
+            for (at, bit) in written.enumerate() {
+                prop_assert_eq!(bit, *model.0.get(at).unwrap_or(&false));
+            }
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use super::super::domain::{Data, Primitive};
+use super::super::errors::ApplicationError;
+use super::utilities::get_string;
+use std::collections::HashMap;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Op {
+    And,
+    Or,
+    Xor,
+    Not,
+}
+
+/// The bitwise `op` of the strings at `keys`, where shorter and missing
+/// strings count as padded with zero bytes to the longest one.
+pub fn command(
+    store: &HashMap<Vec<u8>, Data>,
+    op: Op,
+    keys: &[Vec<u8>],
+) -> Result<Vec<u8>, ApplicationError> {
+    if op == Op::Not && keys.len() != 1 {
+        return Err(String::from("BITOP NOT must be called with a single source key").into());
+    }
+    let strings = keys
+        .iter()
+        .map(|key| Ok(get_string(store, key)?.map(Primitive::as_bytes)))
+        .collect::<Result<Vec<_>, ApplicationError>>()?;
+    let len = strings.iter().flatten().map(|s| s.len()).max().unwrap_or(0);
+    let mut result = vec![0; len];
+    for (index, string) in strings.iter().enumerate() {
+        let bytes: &[u8] = string.as_deref().unwrap_or(&[]);
+        if op == Op::Not {
+            for (target, byte) in result.iter_mut().zip(bytes) {
+                *target = !byte;
+            }
+        } else if index == 0 {
+            result[..bytes.len()].copy_from_slice(bytes);
+        } else {
+            let padded = bytes.iter().chain(std::iter::repeat(&0));
+            for (target, byte) in result.iter_mut().zip(padded) {
+                match op {
+                    Op::And => *target &= byte,
+                    Op::Or => *target |= byte,
+                    Op::Xor | Op::Not => *target ^= byte,
+                }
+            }
+        }
+    }
+    Ok(result)
+}
+
+/// BITOP, which stores the result at `destination`, deleting it if the
+/// result is empty, and returns its length.
+pub fn store_command(
+    store: &mut HashMap<Vec<u8>, Data>,
+    op: Op,
+    destination: &[u8],
+    keys: &[Vec<u8>],
+) -> Result<usize, ApplicationError> {
+    let result = command(store, op, keys)?;
+    let len = result.len();
+    if result.is_empty() {
+        store.remove(destination);
+    } else {
+        store.insert(
+            destination.to_vec(),
+            Data::Primitive(Primitive::from(result)),
+        );
+    }
+    Ok(len)
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::domain::Primitive;
+    use super::{command, store_command, Data, Op};
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+    use std::collections::HashMap;
+
+    proptest! {
+        #[test]
+        fn operations_work_byte_by_byte(
+            a in vec(any::<u8>(), 0..32),
+            b in vec(any::<u8>(), 0..32),
+        ) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            store.insert(b"a".to_vec(), Data::Primitive(Primitive::from(a.clone())));
+            store.insert(b"b".to_vec(), Data::Primitive(Primitive::from(b.clone())));
+            let keys = vec![b"a".to_vec(), b"b".to_vec(), b"missing".to_vec()];
+            let byte = |bytes: &[u8], at: usize| bytes.get(at).copied().unwrap_or(0);
+            let len = a.len().max(b.len());
+            let and = command(&store, Op::And, &keys)?;
+            let or = command(&store, Op::Or, &keys)?;
+            let xor = command(&store, Op::Xor, &keys)?;
+            prop_assert_eq!(and, vec![0; len]);
+            prop_assert_eq!(or, (0..len).map(|at| byte(&a, at) | byte(&b, at)).collect::<Vec<u8>>());
+            prop_assert_eq!(xor, (0..len).map(|at| byte(&a, at) ^ byte(&b, at)).collect::<Vec<u8>>());
+            let not = command(&store, Op::Not, &[b"a".to_vec()])?;
+            prop_assert_eq!(not, a.iter().map(|byte| !byte).collect::<Vec<u8>>());
+            let stored = store_command(&mut store, Op::Or, b"dest", &keys[1..])?;
+            prop_assert_eq!(stored, b.len());
+            prop_assert_eq!(store.contains_key(&b"dest"[..]), !b.is_empty());
+        }
+    }
+}
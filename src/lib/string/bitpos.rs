@@ -0,0 +1,51 @@
+use super::super::domain::Data;
+use super::super::errors::ApplicationError;
+use super::bits::{self, Range};
+use super::utilities::get_string;
+use std::collections::HashMap;
+
+/// BITPOS: where the first bit in `range` that is `bit` is, or -1. Without
+/// an end, the string counts as padded with clear bits, so looking for a
+/// clear bit in a run of set ones finds the bit just past the range.
+pub fn command(
+    store: &HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    bit: bool,
+    range: Range,
+) -> Result<i64, ApplicationError> {
+    let string = match get_string(store, key)? {
+        Some(string) => string.as_bytes(),
+        None => return Ok(if bit { -1 } else { 0 }),
+    };
+    let (first, last) = match range.bits(string.len()) {
+        Some(bits) => bits,
+        None => return Ok(-1),
+    };
+    Ok(match bits::position(&string, bit, first, last) {
+        Some(found) => found as i64,
+        None if !bit && range.end.is_none() => last as i64 + 1,
+        None => -1,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::domain::Primitive;
+    use super::{command, Data, Range};
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+    use std::collections::HashMap;
+
+    proptest! {
+        #[test]
+        fn finds_the_first_matching_bit(bytes in vec(any::<u8>(), 1..64), bit in any::<bool>()) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            let total = bytes.len() as i64 * 8;
+            let expected = (0..total)
+                .find(|at| (bytes[(*at / 8) as usize] & (0x80 >> (at % 8)) != 0) == bit)
+                .unwrap_or(if bit { -1 } else { total });
+            store.insert(b"bits".to_vec(), Data::Primitive(Primitive::from(bytes)));
+            assert_eq!(command(&store, b"bits", bit, Range::ALL)?, expected);
+        }
+    }
+}
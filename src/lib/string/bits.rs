@@ -0,0 +1,143 @@
+use std::convert::TryInto;
+
+/// Whether a bitmap range counts bytes or bits.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Unit {
+    Byte,
+    Bit,
+}
+
+/// A range of a bitmap as BITCOUNT and BITPOS take it: a start and an end,
+/// both included, where negative offsets count back from the end and no
+/// end means the end of the bitmap.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Range {
+    pub start: i64,
+    pub end: Option<i64>,
+    pub unit: Unit,
+}
+
+impl Range {
+    pub const ALL: Range = Range {
+        start: 0,
+        end: None,
+        unit: Unit::Byte,
+    };
+
+    /// The first and last bit the range covers in a bitmap of `len` bytes,
+    /// or nothing if it is empty. Offsets past either end stop at it.
+    pub fn bits(&self, len: usize) -> Option<(u64, u64)> {
+        let total = match self.unit {
+            Unit::Byte => len as i64,
+            Unit::Bit => len as i64 * 8,
+        };
+        let from_end = |offset: i64| if offset < 0 { total + offset } else { offset };
+        let start = from_end(self.start).max(0);
+        let end = from_end(self.end.unwrap_or(-1)).max(0).min(total - 1);
+        if start > end {
+            return None;
+        }
+        let (start, end) = (start as u64, end as u64);
+        Some(match self.unit {
+            Unit::Byte => (start * 8, end * 8 + 7),
+            Unit::Bit => (start, end),
+        })
+    }
+}
+
+/// The bit at `offset`, counting from the most significant bit of the first
+/// byte as Redis does. Bits past the end are clear.
+pub fn get(bytes: &[u8], offset: u64) -> bool {
+    bytes
+        .get((offset / 8) as usize)
+        .is_some_and(|byte| byte & (0x80 >> (offset % 8)) != 0)
+}
+
+/// The bits of the byte holding `first` and `last` that fall between them.
+fn mask(byte: u64, first: u64, last: u64) -> u8 {
+    let head = if first / 8 == byte {
+        0xff >> (first % 8)
+    } else {
+        0xff
+    };
+    let tail = if last / 8 == byte {
+        0xff << (7 - last % 8)
+    } else {
+        0xff
+    };
+    head & tail
+}
+
+/// How many bits are set from `first` to `last`, both included, a word at
+/// a time.
+pub fn count(bytes: &[u8], first: u64, last: u64) -> u64 {
+    let (head, tail) = ((first / 8) as usize, (last / 8) as usize);
+    let edges =
+        |byte: usize| u64::from((bytes[byte] & mask(byte as u64, first, last)).count_ones());
+    if head == tail {
+        return edges(head);
+    }
+    let middle = &bytes[head + 1..tail];
+    let words = middle.chunks_exact(8);
+    let rest: u64 = words
+        .remainder()
+        .iter()
+        .map(|byte| u64::from(byte.count_ones()))
+        .sum();
+    let full: u64 = words
+        .map(|word| {
+            let word: [u8; 8] = word.try_into().unwrap_or_default();
+            u64::from(u64::from_ne_bytes(word).count_ones())
+        })
+        .sum();
+    edges(head) + full + rest + edges(tail)
+}
+
+/// The first bit from `first` to `last` that is `bit`, if there is one.
+pub fn position(bytes: &[u8], bit: bool, first: u64, last: u64) -> Option<u64> {
+    (first / 8..=last / 8).find_map(|byte| {
+        let value = if bit {
+            bytes[byte as usize]
+        } else {
+            !bytes[byte as usize]
+        };
+        match value & mask(byte, first, last) {
+            0 => None,
+            found => Some(byte * 8 + u64::from(found.leading_zeros())),
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{count, get, position, Range, Unit};
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn counts_and_positions_agree_with_single_bits(
+            bytes in vec(any::<u8>(), 1..64),
+            start in -600i64..600,
+            end in -600i64..600,
+            bit in any::<bool>(),
+            unit in prop_oneof![Just(Unit::Byte), Just(Unit::Bit)],
+        ) {
+            let range = Range { start, end: Some(end), unit };
+            match range.bits(bytes.len()) {
+                Some((first, last)) => {
+                    assert!(first <= last && last < bytes.len() as u64 * 8);
+                    let bits: Vec<bool> = (first..=last).map(|offset| get(&bytes, offset)).collect();
+                    assert_eq!(count(&bytes, first, last), bits.iter().filter(|set| **set).count() as u64);
+                    let expected = bits.iter().position(|set| *set == bit).map(|at| first + at as u64);
+                    assert_eq!(position(&bytes, bit, first, last), expected)
+                }
+                None => {
+                    let total = bytes.len() as i64 * if unit == Unit::Byte { 1 } else { 8 };
+                    let from_end = |offset: i64| if offset < 0 { (total + offset).max(0) } else { offset };
+                    assert!(from_end(start) > from_end(end).min(total - 1))
+                }
+            }
+        }
+    }
+}
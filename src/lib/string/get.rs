@@ -0,0 +1,118 @@
+use super::super::db::Db;
+use super::super::domain::{Data, Primitive};
+use super::super::errors::ApplicationError;
+use super::super::expire::Lifetime;
+use super::utilities::get_string;
+use std::collections::HashMap;
+
+pub fn command(
+    store: &HashMap<Vec<u8>, Data>,
+    key: &[u8],
+) -> Result<Option<Primitive>, ApplicationError> {
+    Ok(get_string(store, key)?.cloned())
+}
+
+/// MGET, which has nothing for keys that don't hold strings.
+pub fn multi_command(store: &HashMap<Vec<u8>, Data>, keys: &[Vec<u8>]) -> Vec<Option<Primitive>> {
+    keys.iter()
+        .map(|key| get_string(store, key).ok().flatten().cloned())
+        .collect()
+}
+
+/// GETDEL, which deletes the string it gets.
+pub fn del_command(db: &mut Db, key: &[u8]) -> Result<Option<Primitive>, ApplicationError> {
+    get_string(db.store(), key)?;
+    let value = match db.store_mut().remove(key) {
+        Some(Data::Primitive(p)) => Some(p),
+        _ => None,
+    };
+    db.persist(key);
+    Ok(value)
+}
+
+/// GETEX, which gives the string it gets a new lifetime, where `Keep`
+/// leaves its deadline as it was.
+pub fn ex_command(
+    db: &mut Db,
+    key: &[u8],
+    lifetime: Lifetime,
+    now: u64,
+) -> Result<Option<Primitive>, ApplicationError> {
+    let lifetime = lifetime.resolve(now)?;
+    let value = get_string(db.store(), key)?.cloned();
+    if value.is_some() {
+        lifetime.apply(db, key, now)?;
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::expire::When;
+    use super::{command, del_command, ex_command, multi_command, Data, Db, Lifetime, Primitive};
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+    use std::collections::HashSet;
+
+    proptest! {
+        #[test]
+        fn getdel_gets_once(key in vec(any::<u8>(), 0..32), value in any::<Primitive>()) {
+            let mut db = Db::new();
+            db.store_mut().insert(key.clone(), value.clone().into());
+            db.set_deadline(&key, 10);
+            assert_eq!(del_command(&mut db, &key)?, Some(value));
+            assert_eq!(del_command(&mut db, &key)?, None);
+            assert_eq!(command(db.store(), &key)?, None);
+            assert_eq!(db.deadline(&key), None)
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn getex_only_touches_strings_that_are_there(
+            key in vec(any::<u8>(), 0..32),
+            value in any::<Primitive>(),
+            ttl in 1i64..1000,
+        ) {
+            let mut db = Db::new();
+            let expire = Lifetime::Expire(When::In(ttl));
+            assert_eq!(ex_command(&mut db, &key, expire, 0)?, None);
+            assert_eq!(db.deadline(&key), None);
+            db.store_mut().insert(key.clone(), value.clone().into());
+            assert_eq!(ex_command(&mut db, &key, expire, 0)?, Some(value.clone()));
+            assert_eq!(db.deadline(&key), Some(ttl as u64));
+            assert_eq!(ex_command(&mut db, &key, Lifetime::Keep, 0)?, Some(value.clone()));
+            assert_eq!(db.deadline(&key), Some(ttl as u64));
+            assert_eq!(ex_command(&mut db, &key, Lifetime::Persist, 0)?, Some(value));
+            assert_eq!(db.deadline(&key), None)
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn multi_agrees_with_single_gets(
+            pairs in vec((vec(any::<u8>(), 0..4), any::<Primitive>()), 0..8),
+            keys in vec(vec(any::<u8>(), 0..4), 1..8),
+        ) {
+            let mut db = Db::new();
+            pairs.into_iter().for_each(|(key, value)| {
+                db.store_mut().insert(key, value.into());
+            });
+            db.store_mut().insert(b"set".to_vec(), Data::from(HashSet::new()));
+            let keys: Vec<Vec<u8>> = keys.into_iter().chain(vec![b"set".to_vec()]).collect();
+            let expected: Vec<Option<Primitive>> = keys.iter().map(|key| command(db.store(), key).unwrap_or(None)).collect();
+            assert_eq!(multi_command(db.store(), &keys), expected)
+        }
+    }
+
+    #[test]
+    fn only_strings_can_be_got() {
+        let mut db = Db::new();
+        let set: HashSet<Primitive> = vec![Primitive::from(1)].into_iter().collect();
+        db.store_mut().insert(b"k".to_vec(), Data::from(set));
+        command(db.store(), b"k").unwrap_err();
+        del_command(&mut db, b"k").unwrap_err();
+        ex_command(&mut db, b"k", Lifetime::Persist, 0).unwrap_err();
+        assert!(db.store().contains_key(&b"k"[..]));
+    }
+}
@@ -0,0 +1,179 @@
+use super::super::domain::Data;
+use super::super::errors::ApplicationError;
+use super::utilities::get_string;
+use std::collections::HashMap;
+
+/// What LCS replies with. By default that's the subsequence itself, LEN
+/// asks for its length only, and IDX for the runs it is made of, leaving
+/// out those shorter than `min_match_len` and giving each one's length
+/// with `with_match_len`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Options {
+    pub len: bool,
+    pub idx: bool,
+    pub min_match_len: usize,
+    pub with_match_len: bool,
+}
+
+/// A run of bytes the two strings have in common, as the inclusive ranges
+/// it spans in each.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Match {
+    pub a: (usize, usize),
+    pub b: (usize, usize),
+}
+
+impl Match {
+    pub fn length(&self) -> usize {
+        self.a.1 - self.a.0 + 1
+    }
+}
+
+/// The longest common subsequence of two strings, along with the runs it
+/// is made of, from the last to the first.
+#[derive(Debug, PartialEq)]
+pub struct Lcs {
+    pub subsequence: Vec<u8>,
+    pub matches: Vec<Match>,
+}
+
+/// LCS, where missing strings count as empty ones.
+pub fn command(
+    store: &HashMap<Vec<u8>, Data>,
+    key1: &[u8],
+    key2: &[u8],
+) -> Result<Lcs, ApplicationError> {
+    let a = get_string(store, key1)?.map(|p| p.to_bytes());
+    let b = get_string(store, key2)?.map(|p| p.to_bytes());
+    lcs(&a.unwrap_or_default(), &b.unwrap_or_default())
+}
+
+/// Finds the longest common subsequence by dynamic programming, walking
+/// back through the table the way Redis does so that ties are settled as
+/// they are there.
+fn lcs(a: &[u8], b: &[u8]) -> Result<Lcs, ApplicationError> {
+    let width = b.len() + 1;
+    let cells = (a.len() + 1)
+        .checked_mul(width)
+        .filter(|cells| *cells <= super::MAX_LEN / 4)
+        .ok_or_else(|| String::from("Strings are too long to find their LCS"))?;
+    let mut table = vec![0u32; cells];
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            table[i * width + j] = if a[i - 1] == b[j - 1] {
+                table[(i - 1) * width + j - 1] + 1
+            } else {
+                table[(i - 1) * width + j].max(table[i * width + j - 1])
+            };
+        }
+    }
+    let mut subsequence = Vec::with_capacity(table[cells - 1] as usize);
+    let mut matches: Vec<Match> = Vec::new();
+    let mut current: Option<Match> = None;
+    let (mut i, mut j) = (a.len(), b.len());
+    while i > 0 && j > 0 {
+        if a[i - 1] == b[j - 1] {
+            subsequence.push(a[i - 1]);
+            current = Some(match current {
+                Some(run) => Match {
+                    a: (i - 1, run.a.1),
+                    b: (j - 1, run.b.1),
+                },
+                None => Match {
+                    a: (i - 1, i - 1),
+                    b: (j - 1, j - 1),
+                },
+            });
+            i -= 1;
+            j -= 1;
+        } else {
+            if table[(i - 1) * width + j] > table[i * width + j - 1] {
+                i -= 1;
+            } else {
+                j -= 1;
+            }
+            matches.extend(current.take());
+        }
+    }
+    matches.extend(current);
+    subsequence.reverse();
+    Ok(Lcs {
+        subsequence,
+        matches,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{command, lcs, Data, HashMap, Lcs, Match};
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    fn is_subsequence(sub: &[u8], of: &[u8]) -> bool {
+        let mut of = of.iter();
+        sub.iter().all(|byte| of.any(|other| other == byte))
+    }
+
+    /// The length of the LCS by plain recursion over a memo.
+    fn lcs_len(a: &[u8], b: &[u8], memo: &mut HashMap<(usize, usize), usize>) -> usize {
+        match (a.split_last(), b.split_last()) {
+            (Some((x, a_rest)), Some((y, b_rest))) => {
+                if let Some(len) = memo.get(&(a.len(), b.len())) {
+                    return *len;
+                }
+                let len = if x == y {
+                    lcs_len(a_rest, b_rest, memo) + 1
+                } else {
+                    lcs_len(a_rest, b, memo).max(lcs_len(a, b_rest, memo))
+                };
+                memo.insert((a.len(), b.len()), len);
+                len
+            }
+            _ => 0,
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn finds_a_longest_common_subsequence(a in vec(0u8..4, 0..40), b in vec(0u8..4, 0..40)) {
+            let Lcs { subsequence, matches } = lcs(&a, &b)?;
+            assert!(is_subsequence(&subsequence, &a) && is_subsequence(&subsequence, &b));
+            assert_eq!(subsequence.len(), lcs_len(&a, &b, &mut HashMap::new()));
+            assert_eq!(matches.iter().map(Match::length).sum::<usize>(), subsequence.len());
+            let mut runs: Vec<u8> = Vec::new();
+            for run in matches.iter().rev() {
+                assert_eq!(run.b.1 - run.b.0 + 1, run.length());
+                assert_eq!(a[run.a.0..=run.a.1], b[run.b.0..=run.b.1]);
+                runs.extend_from_slice(&a[run.a.0..=run.a.1]);
+            }
+            assert_eq!(runs, subsequence);
+            assert!(matches.windows(2).all(|pair| pair[0].a.0 > pair[1].a.1 && pair[0].b.0 > pair[1].b.1))
+        }
+    }
+
+    #[test]
+    fn matches_redis() {
+        let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+        store.insert(b"key1".to_vec(), b"ohmytext".to_vec().into());
+        store.insert(b"key2".to_vec(), b"mynewtext".to_vec().into());
+        let found = command(&store, b"key1", b"key2").unwrap();
+        assert_eq!(found.subsequence, b"mytext");
+        assert_eq!(
+            found.matches,
+            vec![
+                Match {
+                    a: (4, 7),
+                    b: (5, 8)
+                },
+                Match {
+                    a: (2, 3),
+                    b: (0, 1)
+                },
+            ]
+        );
+        assert_eq!(
+            command(&store, b"key1", b"nothing").unwrap().subsequence,
+            b""
+        );
+    }
+}
@@ -0,0 +1,17 @@
+pub mod append;
+pub mod bit;
+pub mod bitcount;
+pub mod bitfield;
+pub mod bitop;
+pub mod bitpos;
+pub mod bits;
+pub mod get;
+pub mod lcs;
+pub mod range;
+pub mod set;
+pub mod strlen;
+mod utilities;
+
+/// The longest a string can grow to, as with Redis's default
+/// `proto-max-bulk-len`.
+pub const MAX_LEN: usize = 512 * 1024 * 1024;
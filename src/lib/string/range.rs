@@ -0,0 +1,118 @@
+use super::super::domain::Data;
+use super::super::errors::ApplicationError;
+use super::utilities::{check_len, get_or_create_string, get_string};
+use std::collections::HashMap;
+
+/// GETRANGE: the bytes from `start` to `end`, both included, where negative
+/// offsets count back from the end. Offsets past either end stop at it.
+pub fn command(
+    store: &HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    start: i64,
+    end: i64,
+) -> Result<Vec<u8>, ApplicationError> {
+    Ok(match get_string(store, key)? {
+        Some(string) => slice(&string.as_bytes(), start, end).to_vec(),
+        None => Vec::new(),
+    })
+}
+
+fn slice(bytes: &[u8], start: i64, end: i64) -> &[u8] {
+    let len = bytes.len() as i64;
+    if start < 0 && end < 0 && start > end {
+        return &[];
+    }
+    let start = if start < 0 { len + start } else { start }.max(0);
+    let end = if end < 0 { len + end } else { end }.max(0).min(len - 1);
+    if start > end {
+        &[]
+    } else {
+        &bytes[start as usize..=end as usize]
+    }
+}
+
+/// SETRANGE, which overwrites the string from `offset` on, padding it with
+/// zero bytes if it is too short, and returns its new length. Writing
+/// nothing leaves a missing string missing.
+pub fn set_command(
+    store: &mut HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    offset: usize,
+    value: &[u8],
+) -> Result<usize, ApplicationError> {
+    let len = get_string(store, key)?.map_or(0, |p| p.len());
+    if value.is_empty() {
+        return Ok(len);
+    }
+    let end = offset.saturating_add(value.len());
+    check_len(end)?;
+    let string = get_or_create_string(store, key)?;
+    Ok(string.edit(|bytes| {
+        if bytes.len() < end {
+            bytes.resize(end, 0);
+        }
+        bytes[offset..end].copy_from_slice(value);
+        bytes.len()
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::get;
+    use super::{command, set_command, Data, HashMap};
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn negative_offsets_count_from_the_end(
+            value in vec(any::<u8>(), 1..32),
+            start in 0usize..40,
+            end in 0usize..40,
+        ) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            store.insert(b"k".to_vec(), value.clone().into());
+            let expected = if start <= end && start < value.len() {
+                value[start..=end.min(value.len() - 1)].to_vec()
+            } else {
+                Vec::new()
+            };
+            assert_eq!(command(&store, b"k", start as i64, end as i64)?, expected.clone());
+            let len = value.len() as i64;
+            if start < value.len() && end < value.len() {
+                assert_eq!(command(&store, b"k", start as i64 - len, end as i64 - len)?, expected);
+            }
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn ranges_are_written_over_zero_padding(
+            writes in vec((0usize..40, vec(any::<u8>(), 0..8)), 1..10),
+        ) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            let mut model: Vec<u8> = Vec::new();
+            for (offset, value) in writes {
+                if !value.is_empty() {
+                    if model.len() < offset + value.len() {
+                        model.resize(offset + value.len(), 0);
+                    }
+                    model[offset..offset + value.len()].copy_from_slice(&value);
+                }
+                assert_eq!(set_command(&mut store, b"k", offset, &value)?, model.len());
+            }
+            let value = get::command(&store, b"k")?.map(|p| p.into_bytes());
+            assert_eq!(value, Some(model).filter(|model| !model.is_empty()));
+            assert_eq!(command(&store, b"k", 0, -1)?, value.unwrap_or_default())
+        }
+    }
+
+    #[test]
+    fn strings_stay_within_the_limit() {
+        let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+        set_command(&mut store, b"k", 512 * 1024 * 1024, b"x").unwrap_err();
+        assert!(store.is_empty());
+        set_command(&mut store, b"k", usize::MAX, b"x").unwrap_err();
+        assert_eq!(set_command(&mut store, b"k", usize::MAX, b"").unwrap(), 0);
+    }
+}
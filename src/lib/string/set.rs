@@ -0,0 +1,106 @@
+use super::super::db::Db;
+use super::super::domain::Primitive;
+use super::super::errors::ApplicationError;
+use super::super::expire::Lifetime;
+use super::utilities::get_string;
+
+/// SET's options. NX only sets a key that isn't there and XX only one that
+/// is, GET has it reply with the string that was there, and the lifetime
+/// says what becomes of the key's deadline.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Options {
+    pub nx: bool,
+    pub xx: bool,
+    pub get: bool,
+    pub lifetime: Lifetime,
+}
+
+/// SET, which says whether it set the key, along with the string that was
+/// there if GET asked for it. Without GET any value is overwritten; with it
+/// only strings are.
+pub fn command(
+    db: &mut Db,
+    key: &[u8],
+    value: Primitive,
+    options: &Options,
+    now: u64,
+) -> Result<(bool, Option<Primitive>), ApplicationError> {
+    let lifetime = options.lifetime.resolve(now)?;
+    let old = if options.get {
+        get_string(db.store(), key)?.cloned()
+    } else {
+        None
+    };
+    let exists = db.store().contains_key(key);
+    if options.nx && exists || options.xx && !exists {
+        return Ok((false, old));
+    }
+    db.store_mut().insert(key.to_vec(), value.into());
+    lifetime.apply(db, key, now)?;
+    Ok((true, old))
+}
+
+/// MSET, which sets every key as SET would without options.
+pub fn multi_command(db: &mut Db, pairs: Vec<(Vec<u8>, Primitive)>) {
+    pairs.into_iter().for_each(|(key, value)| {
+        db.persist(&key);
+        db.store_mut().insert(key, value.into());
+    });
+}
+
+/// MSETNX, which sets every key or, if any of them is there, none. It says
+/// whether it set them.
+pub fn multi_nx_command(db: &mut Db, pairs: Vec<(Vec<u8>, Primitive)>) -> bool {
+    if pairs.iter().any(|(key, _)| db.store().contains_key(key)) {
+        return false;
+    }
+    multi_command(db, pairs);
+    true
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::get;
+    use super::{command, multi_nx_command, Db, Options, Primitive};
+    use proptest::collection::{hash_map, vec};
+    use proptest::option;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn conditions_decide_what_is_set(
+            existing in option::of(any::<Primitive>()),
+            value in any::<Primitive>(),
+            (nx, xx, get) in (any::<bool>(), any::<bool>(), any::<bool>()),
+        ) {
+            let mut db = Db::new();
+            if let Some(existing) = &existing {
+                db.store_mut().insert(b"k".to_vec(), existing.clone().into());
+            }
+            let options = Options { nx, xx, get, ..Options::default() };
+            let set = if existing.is_some() { !nx } else { !xx };
+            let old = existing.clone().filter(|_| get);
+            assert_eq!(command(&mut db, b"k", value.clone(), &options, 0)?, (set, old));
+            let expected = if set { Some(value) } else { existing };
+            assert_eq!(get::command(db.store(), b"k")?, expected)
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn msetnx_sets_all_or_nothing(
+            pairs in hash_map(vec(any::<u8>(), 0..4), any::<Primitive>(), 1..8),
+            taken in vec(any::<u8>(), 0..4),
+        ) {
+            let mut db = Db::new();
+            db.store_mut().insert(taken.clone(), Primitive::from("taken").into());
+            let clash = pairs.contains_key(&taken);
+            assert_eq!(multi_nx_command(&mut db, pairs.clone().into_iter().collect()), !clash);
+            pairs.into_iter().filter(|(key, _)| *key != taken).try_for_each(|(key, value)| {
+                let expected = if clash { None } else { Some(value) };
+                prop_assert_eq!(get::command(db.store(), &key)?, expected);
+                Ok(())
+            })?;
+        }
+    }
+}
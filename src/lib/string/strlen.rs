@@ -0,0 +1,8 @@
+use super::super::domain::Data;
+use super::super::errors::ApplicationError;
+use super::utilities::get_string;
+use std::collections::HashMap;
+
+pub fn command(store: &HashMap<Vec<u8>, Data>, key: &[u8]) -> Result<usize, ApplicationError> {
+    Ok(get_string(store, key)?.map_or(0, |p| p.len()))
+}
@@ -0,0 +1,38 @@
+use super::super::domain::{Data, Escaped, Primitive};
+use super::super::errors::ApplicationError;
+use super::MAX_LEN;
+use std::collections::HashMap;
+
+pub fn get_string<'a>(
+    store: &'a HashMap<Vec<u8>, Data>,
+    key: &[u8],
+) -> Result<Option<&'a Primitive>, ApplicationError> {
+    match store.get(key) {
+        Some(Data::Primitive(p)) => Ok(Some(p)),
+        Some(_) => Err(format!("Value at {} is not a primitive", Escaped(key)).into()),
+        None => Ok(None),
+    }
+}
+
+/// Gets the string at `key` for writing, creating an empty one if need be.
+pub fn get_or_create_string<'a>(
+    store: &'a mut HashMap<Vec<u8>, Data>,
+    key: &[u8],
+) -> Result<&'a mut Primitive, ApplicationError> {
+    match store
+        .entry(key.to_vec())
+        .or_insert_with(|| Data::Primitive(Primitive::from("")))
+    {
+        Data::Primitive(p) => Ok(p),
+        _ => Err(format!("Value at {} is not a primitive", Escaped(key)).into()),
+    }
+}
+
+/// Fails unless a string of `len` bytes would be within `MAX_LEN`.
+pub fn check_len(len: usize) -> Result<(), ApplicationError> {
+    if len > MAX_LEN {
+        Err(String::from("String would exceed the maximum allowed size of 512MB").into())
+    } else {
+        Ok(())
+    }
+}
@@ -0,0 +1,205 @@
+use super::super::domain::Data;
+use super::super::errors::ApplicationError;
+use super::utilities::{get_or_create_sorted_set, remove_if_empty};
+use super::SortedSet;
+use std::collections::HashMap;
+
+/// The flags ZADD takes: NX only adds new members and XX only updates
+/// existing ones, GT and LT only update a score that would go up or down
+/// respectively, and CH counts updated members as well as added ones.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct Options {
+    pub nx: bool,
+    pub xx: bool,
+    pub gt: bool,
+    pub lt: bool,
+    pub ch: bool,
+}
+
+#[derive(Debug, PartialEq)]
+enum Outcome {
+    Added,
+    Updated,
+    Unchanged,
+    Skipped,
+}
+
+/// Sets or, with `incr`, increments the score of one member as the flags
+/// allow, returning what happened and the score it ended up with.
+fn add(
+    set: &mut SortedSet,
+    options: Options,
+    score: f64,
+    member: Vec<u8>,
+    incr: bool,
+) -> Result<(Outcome, Option<f64>), ApplicationError> {
+    match set.score(&member) {
+        Some(_) if options.nx => Ok((Outcome::Skipped, None)),
+        Some(current) => {
+            let score = if incr { current + score } else { score };
+            if score.is_nan() {
+                return Err(String::from("Resulting score is not a number (NaN)").into());
+            }
+            if (options.gt && score <= current) || (options.lt && score >= current) {
+                Ok((Outcome::Skipped, None))
+            } else if score == current {
+                Ok((Outcome::Unchanged, Some(score)))
+            } else {
+                set.insert(member, score);
+                Ok((Outcome::Updated, Some(score)))
+            }
+        }
+        None if options.xx => Ok((Outcome::Skipped, None)),
+        None => {
+            set.insert(member, score);
+            Ok((Outcome::Added, Some(score)))
+        }
+    }
+}
+
+/// Returns how many members were added or, with CH, changed.
+pub fn command(
+    store: &mut HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    options: Options,
+    pairs: Vec<(f64, Vec<u8>)>,
+) -> Result<usize, ApplicationError> {
+    let set = get_or_create_sorted_set(store, key)?;
+    let counted = pairs.into_iter().try_fold(0, |counted, (score, member)| {
+        Ok(match add(set, options, score, member, false)?.0 {
+            Outcome::Added => counted + 1,
+            Outcome::Updated if options.ch => counted + 1,
+            _ => counted,
+        })
+    });
+    remove_if_empty(store, key);
+    counted
+}
+
+/// Increments a member's score, returning the new one, or `None` when the
+/// flags ruled the increment out.
+pub fn incr_command(
+    store: &mut HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    options: Options,
+    by: f64,
+    member: Vec<u8>,
+) -> Result<Option<f64>, ApplicationError> {
+    let set = get_or_create_sorted_set(store, key)?;
+    let added = add(set, options, by, member, true);
+    remove_if_empty(store, key);
+    added.map(|(_, score)| score)
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::score;
+    use super::{command, incr_command, Data, HashMap, Options};
+    use proptest::collection::{hash_map, vec};
+    use proptest::prelude::*;
+
+    fn members() -> impl Strategy<Value = HashMap<Vec<u8>, i32>> {
+        hash_map(vec(any::<u8>(), 0..4), -1000i32..1000, 1..50)
+    }
+
+    fn pairs(members: &HashMap<Vec<u8>, i32>) -> Vec<(f64, Vec<u8>)> {
+        members
+            .iter()
+            .map(|(m, s)| (f64::from(*s), m.clone()))
+            .collect()
+    }
+
+    proptest! {
+        #[test]
+        fn returns_n_added(key in vec(any::<u8>(), 0..32), first in members(), second in members()) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            let new = second.keys().filter(|m| !first.contains_key(*m)).count();
+            assert_eq!(command(&mut store, &key, Options::default(), pairs(&first))?, first.len());
+            assert_eq!(command(&mut store, &key, Options::default(), pairs(&second))?, new);
+            second.iter().try_for_each(|(m, s)| {
+                prop_assert_eq!(score::command(&store, &key, m)?, Some(f64::from(*s)));
+                Ok(())
+            })?
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn flags_decide_which_scores_change(
+            key in vec(any::<u8>(), 0..32),
+            first in members(),
+            second in members(),
+            flags in (any::<bool>(), any::<bool>(), 0..3)
+        ) {
+            let (nx, ch, comparison) = flags;
+            let (gt, lt) = (comparison == 1, comparison == 2);
+            let options = Options { nx, xx: !nx && !gt && !lt, gt: gt && !nx, lt: lt && !nx, ch };
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            command(&mut store, &key, Options::default(), pairs(&first))?;
+            let mut counted = 0;
+            let members: std::collections::HashSet<&Vec<u8>> = first.keys().chain(second.keys()).collect();
+            let expected: HashMap<Vec<u8>, i32> = members.into_iter().map(|m| {
+                let score = match (first.get(m), second.get(m)) {
+                    (Some(old), Some(_)) if options.nx => Some(*old),
+                    (Some(old), Some(new)) if (options.gt && new <= old) || (options.lt && new >= old) => Some(*old),
+                    (Some(old), Some(new)) => {
+                        if options.ch && old != new { counted += 1; }
+                        Some(*new)
+                    }
+                    (Some(old), None) => Some(*old),
+                    (None, Some(_)) if options.xx => None,
+                    (None, Some(new)) => { counted += 1; Some(*new) }
+                    (None, None) => None,
+                };
+                (m.clone(), score)
+            }).filter_map(|(m, s)| s.map(|s| (m, s))).collect();
+            assert_eq!(command(&mut store, &key, options, pairs(&second))?, counted);
+            expected.iter().try_for_each(|(m, s)| {
+                prop_assert_eq!(score::command(&store, &key, m)?, Some(f64::from(*s)));
+                Ok(())
+            })?
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn increments_accumulate(key in vec(any::<u8>(), 0..32), member in vec(any::<u8>(), 0..4), by in vec(-1000i32..1000, 1..20)) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            let sum: i32 = by.iter().sum();
+            by.into_iter().try_for_each(|by| {
+                incr_command(&mut store, &key, Options::default(), f64::from(by), member.clone()).map(|_| ())
+            })?;
+            assert_eq!(score::command(&store, &key, &member)?, Some(f64::from(sum)))
+        }
+    }
+
+    #[test]
+    fn skipped_increments_reply_nothing() {
+        let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+        let xx = Options {
+            xx: true,
+            ..Options::default()
+        };
+        assert_eq!(
+            incr_command(&mut store, b"z", xx, 1.0, b"m".to_vec()).unwrap(),
+            None
+        );
+        assert!(store.is_empty());
+        incr_command(
+            &mut store,
+            b"z",
+            Options::default(),
+            f64::INFINITY,
+            b"m".to_vec(),
+        )
+        .unwrap();
+        incr_command(
+            &mut store,
+            b"z",
+            Options::default(),
+            f64::NEG_INFINITY,
+            b"m".to_vec(),
+        )
+        .unwrap_err();
+    }
+}
@@ -0,0 +1,98 @@
+use super::super::domain::Data;
+use super::super::errors::ApplicationError;
+use super::utilities::{get_scores, store_result};
+use super::SortedSet;
+use std::collections::HashMap;
+
+/// The members of the first input that are in none of the others, with
+/// the scores they have in the first.
+pub fn command(
+    store: &HashMap<Vec<u8>, Data>,
+    keys: &[Vec<u8>],
+) -> Result<SortedSet, ApplicationError> {
+    let mut result = SortedSet::new();
+    let (first, others) = match keys.split_first() {
+        Some(split) => split,
+        None => return Ok(result),
+    };
+    let base = get_scores(store, first)?;
+    let others = others
+        .iter()
+        .map(|key| get_scores(store, key))
+        .collect::<Result<Vec<HashMap<Vec<u8>, f64>>, ApplicationError>>()?;
+    base.into_iter()
+        .filter(|(member, _)| !others.iter().any(|other| other.contains_key(member)))
+        .for_each(|(member, score)| {
+            result.insert(member, score);
+        });
+    Ok(result)
+}
+
+pub fn store_command(
+    store: &mut HashMap<Vec<u8>, Data>,
+    destination: &[u8],
+    keys: &[Vec<u8>],
+) -> Result<usize, ApplicationError> {
+    let difference = command(store, keys)?;
+    Ok(store_result(store, destination, difference))
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::add::{self, Options};
+    use super::super::{inter, union, Aggregate};
+    use super::{store_command, Data};
+    use proptest::collection::{hash_map, vec};
+    use proptest::prelude::*;
+    use std::collections::HashMap;
+
+    fn valid_keys() -> impl Strategy<Value = Vec<u8>> {
+        vec(any::<u8>(), 1..16)
+    }
+
+    fn scores() -> impl Strategy<Value = HashMap<Vec<u8>, i32>> {
+        hash_map(vec(any::<u8>(), 0..2), -1000i32..1000, 1..100)
+    }
+
+    fn insert(
+        store: &mut HashMap<Vec<u8>, Data>,
+        key: &[u8],
+        scores: &HashMap<Vec<u8>, i32>,
+    ) -> Result<(), TestCaseError> {
+        let pairs = scores
+            .iter()
+            .map(|(m, s)| (f64::from(*s), m.clone()))
+            .collect();
+        add::command(store, key, Options::default(), pairs)?;
+        Ok(())
+    }
+
+    proptest! {
+        #[test]
+        fn difference_and_intersection_partition_the_base(
+            a in (valid_keys(), scores()),
+            b in (valid_keys(), scores())
+        ) {
+            prop_assume!(a.0 != b.0);
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            insert(&mut store, &a.0, &a.1)?;
+            insert(&mut store, &b.0, &b.1)?;
+            let keys = [a.0.clone(), b.0];
+            store_command(&mut store, b"diff", &keys)?;
+            inter::store_command(&mut store, b"inter", &keys, &[1.0, 0.0], Aggregate::Sum)?;
+            let size = union::store_command(&mut store, b"union", &[b"diff".to_vec(), b"inter".to_vec()], &[1.0, 1.0], Aggregate::Sum)?;
+            assert_eq!(size, a.1.len());
+            assert_eq!(store.get(b"union".as_slice()), store.get(&a.0))
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn self_difference_is_empty(dest in valid_keys(), a in (valid_keys(), scores())) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            insert(&mut store, &a.0, &a.1)?;
+            assert_eq!(store_command(&mut store, &dest, &[a.0.clone(), a.0])?, 0);
+            assert_eq!(store.get(&dest), None)
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use super::super::domain::Data;
+use super::super::errors::ApplicationError;
+use super::utilities::{get_scores, store_result, weigh};
+use super::{Aggregate, SortedSet};
+use std::collections::HashMap;
+
+pub fn command(
+    store: &HashMap<Vec<u8>, Data>,
+    keys: &[Vec<u8>],
+    weights: &[f64],
+    aggregate: Aggregate,
+) -> Result<SortedSet, ApplicationError> {
+    let inputs = keys
+        .iter()
+        .map(|key| get_scores(store, key))
+        .collect::<Result<Vec<HashMap<Vec<u8>, f64>>, ApplicationError>>()?;
+    let mut result = SortedSet::new();
+    let minimal = match inputs.iter().min_by_key(|input| input.len()) {
+        Some(input) => input,
+        None => return Ok(result),
+    };
+    minimal
+        .keys()
+        .filter_map(|member| {
+            let mut scores = inputs
+                .iter()
+                .zip(weights)
+                .map(|(input, weight)| Some(weigh(*input.get(member)?, *weight)));
+            let first = scores.next()??;
+            let score =
+                scores.try_fold(first, |total, score| Some(aggregate.combine(total, score?)))?;
+            Some((member.clone(), score))
+        })
+        .for_each(|(member, score)| {
+            result.insert(member, score);
+        });
+    Ok(result)
+}
+
+pub fn store_command(
+    store: &mut HashMap<Vec<u8>, Data>,
+    destination: &[u8],
+    keys: &[Vec<u8>],
+    weights: &[f64],
+    aggregate: Aggregate,
+) -> Result<usize, ApplicationError> {
+    let intersection = command(store, keys, weights, aggregate)?;
+    Ok(store_result(store, destination, intersection))
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::domain::{Data, Primitive};
+    use super::super::add::{self, Options};
+    use super::super::{score, Aggregate};
+    use super::store_command;
+    use proptest::collection::{hash_map, hash_set, vec};
+    use proptest::prelude::*;
+    use std::collections::HashMap;
+
+    fn valid_keys() -> impl Strategy<Value = Vec<u8>> {
+        vec(any::<u8>(), 1..16)
+    }
+
+    fn scores() -> impl Strategy<Value = HashMap<Vec<u8>, i32>> {
+        hash_map(vec(any::<u8>(), 0..2), -1000i32..1000, 1..100)
+    }
+
+    fn insert(
+        store: &mut HashMap<Vec<u8>, Data>,
+        key: &[u8],
+        scores: &HashMap<Vec<u8>, i32>,
+    ) -> Result<(), TestCaseError> {
+        let pairs = scores
+            .iter()
+            .map(|(m, s)| (f64::from(*s), m.clone()))
+            .collect();
+        add::command(store, key, Options::default(), pairs)?;
+        Ok(())
+    }
+
+    proptest! {
+        #[test]
+        fn intersection_is_idempotent(
+            dest in valid_keys(),
+            snd_dest in valid_keys(),
+            a in (valid_keys(), scores()),
+            b in (valid_keys(), scores()),
+            aggregate in prop_oneof![Just(Aggregate::Min), Just(Aggregate::Max)]
+        ) {
+            prop_assume!(dest != snd_dest && dest != b.0);
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            insert(&mut store, &a.0, &a.1)?;
+            insert(&mut store, &b.0, &b.1)?;
+            store_command(&mut store, &dest, &[a.0, b.0.clone()], &[1.0, 1.0], aggregate)?;
+            store_command(&mut store, &snd_dest, &[dest.clone(), b.0], &[1.0, 1.0], aggregate)?;
+            assert_eq!(store.get(&dest), store.get(&snd_dest))
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn aggregates_scores_of_common_members(
+            dest in valid_keys(),
+            a in (valid_keys(), scores()),
+            b in (valid_keys(), scores()),
+            aggregate in prop_oneof![Just(Aggregate::Sum), Just(Aggregate::Min), Just(Aggregate::Max)]
+        ) {
+            prop_assume!(a.0 != b.0);
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            insert(&mut store, &a.0, &a.1)?;
+            insert(&mut store, &b.0, &b.1)?;
+            let size = store_command(&mut store, &dest, &[a.0.clone(), b.0.clone()], &[1.0, 2.0], aggregate)?;
+            let common: Vec<&Vec<u8>> = a.1.keys().filter(|m| b.1.contains_key(*m)).collect();
+            assert_eq!(size, common.len());
+            assert_eq!(store.contains_key(&dest), !common.is_empty());
+            common.into_iter().try_for_each(|m| {
+                let expected = aggregate.combine(f64::from(a.1[m]), 2.0 * f64::from(b.1[m]));
+                prop_assert_eq!(score::command(&store, &dest, m)?, Some(expected));
+                Ok(())
+            })?
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn missing_inputs_empty_the_intersection(
+            dest in valid_keys(),
+            a in (valid_keys(), hash_set(any::<Primitive>(), 1..100))
+        ) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            let (a_key, a_set) = a;
+            prop_assume!(a_key != b"missing".to_vec());
+            store.insert(a_key.clone(), a_set.into());
+            store.insert(dest.clone(), Data::from(1));
+            let keys = [a_key, b"missing".to_vec()];
+            assert_eq!(store_command(&mut store, &dest, &keys, &[1.0, 1.0], Aggregate::Sum)?, 0);
+            assert_eq!(store.get(&dest), None)
+        }
+    }
+}
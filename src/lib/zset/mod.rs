@@ -0,0 +1,34 @@
+pub mod add;
+pub mod diff;
+pub mod inter;
+pub mod range;
+pub mod rank;
+pub mod rem;
+pub mod score;
+mod skiplist;
+mod sorted_set;
+pub mod union;
+pub(crate) mod utilities;
+
+pub use sorted_set::{LexBound, ScoreBound, SortedSet};
+
+/// How ZUNIONSTORE and ZINTERSTORE combine the scores a member has in
+/// each of their inputs.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Aggregate {
+    Sum,
+    Min,
+    Max,
+}
+
+impl Aggregate {
+    pub fn combine(self, a: f64, b: f64) -> f64 {
+        match self {
+            // Infinities of opposite signs sum to 0 rather than NaN.
+            Aggregate::Sum if (a + b).is_nan() => 0.0,
+            Aggregate::Sum => a + b,
+            Aggregate::Min => a.min(b),
+            Aggregate::Max => a.max(b),
+        }
+    }
+}
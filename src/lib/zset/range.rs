@@ -0,0 +1,149 @@
+use super::super::domain::Data;
+use super::super::errors::ApplicationError;
+use super::super::list::utilities::range;
+use super::utilities::get_sorted_set;
+use super::{LexBound, ScoreBound};
+use std::collections::HashMap;
+
+/// How ZRANGE picks members: by rank, with negative ranks counting back
+/// from the end, by score or by member.
+#[derive(Debug, PartialEq, Clone)]
+pub enum By {
+    Rank(i64, i64),
+    Score(ScoreBound, ScoreBound),
+    Lex(LexBound, LexBound),
+}
+
+/// A ZRANGE query. With `reverse` members come from the highest score down
+/// and ranks count from that end too. `limit` is an offset and a count,
+/// where a negative count means no limit.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Range {
+    pub by: By,
+    pub reverse: bool,
+    pub limit: Option<(i64, i64)>,
+}
+
+pub fn command(
+    store: &HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    query: &Range,
+) -> Result<Vec<(Vec<u8>, f64)>, ApplicationError> {
+    let set = match get_sorted_set(store, key)? {
+        Some(set) => set,
+        None => return Ok(Vec::new()),
+    };
+    let window = match &query.by {
+        By::Rank(start, stop) => range(*start, *stop, set.len()).map(|(first, last)| {
+            if query.reverse {
+                (set.len() - 1 - last, set.len() - 1 - first)
+            } else {
+                (first, last)
+            }
+        }),
+        By::Score(min, max) => set.score_range(*min, *max),
+        By::Lex(min, max) => set.lex_range(min, max),
+    };
+    let (first, last) = match window {
+        Some(window) => window,
+        None => return Ok(Vec::new()),
+    };
+    let (offset, count) = match query.limit {
+        Some((offset, _)) if offset < 0 => return Ok(Vec::new()),
+        Some((offset, count)) if count >= 0 => (offset as usize, count as usize),
+        Some((offset, _)) => (offset as usize, usize::MAX),
+        None => (0, usize::MAX),
+    };
+    let len = last - first + 1;
+    if offset >= len {
+        return Ok(Vec::new());
+    }
+    let start = if query.reverse {
+        last - offset
+    } else {
+        first + offset
+    };
+    Ok(set
+        .iter_from(start, query.reverse)
+        .take(count.min(len - offset))
+        .map(|(member, score)| (member.to_vec(), score))
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::add::{self, Options};
+    use super::super::ScoreBound;
+    use super::{command, By, Data, HashMap, Range};
+    use proptest::collection::{hash_map, vec};
+    use proptest::prelude::*;
+
+    fn sorted(members: &HashMap<Vec<u8>, i32>) -> Vec<(Vec<u8>, f64)> {
+        let mut sorted: Vec<(i32, Vec<u8>)> =
+            members.iter().map(|(m, s)| (*s, m.clone())).collect();
+        sorted.sort();
+        sorted.into_iter().map(|(s, m)| (m, f64::from(s))).collect()
+    }
+
+    fn store_of(
+        key: &[u8],
+        members: &HashMap<Vec<u8>, i32>,
+    ) -> Result<HashMap<Vec<u8>, Data>, TestCaseError> {
+        let mut store = HashMap::new();
+        let pairs = members
+            .iter()
+            .map(|(m, s)| (f64::from(*s), m.clone()))
+            .collect();
+        add::command(&mut store, key, Options::default(), pairs)?;
+        Ok(store)
+    }
+
+    proptest! {
+        #[test]
+        fn rank_ranges_slice_the_sorted_members(
+            key in vec(any::<u8>(), 0..32),
+            members in hash_map(vec(any::<u8>(), 0..4), -20i32..20, 1..50),
+            start in -60i64..60,
+            stop in -60i64..60,
+            reverse in any::<bool>()
+        ) {
+            let store = store_of(&key, &members)?;
+            let mut all = sorted(&members);
+            if reverse {
+                all.reverse();
+            }
+            let len = all.len() as i64;
+            let from = if start < 0 { (len + start).max(0) } else { start.min(len) };
+            let to = if stop < 0 { len + stop + 1 } else { (stop + 1).min(len) };
+            let expected = if from < to { all[from as usize..to as usize].to_vec() } else { vec![] };
+            let query = Range { by: By::Rank(start, stop), reverse, limit: None };
+            assert_eq!(command(&store, &key, &query)?, expected)
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn limits_page_through_score_ranges(
+            key in vec(any::<u8>(), 0..32),
+            members in hash_map(vec(any::<u8>(), 0..4), -20i32..20, 1..50),
+            min in -25i32..25,
+            max in -25i32..25,
+            offset in 0i64..60,
+            count in -1i64..60,
+            reverse in any::<bool>()
+        ) {
+            let store = store_of(&key, &members)?;
+            let mut expected: Vec<(Vec<u8>, f64)> = sorted(&members).into_iter()
+                .filter(|(_, s)| *s >= f64::from(min) && *s <= f64::from(max))
+                .collect();
+            if reverse {
+                expected.reverse();
+            }
+            let count = if count < 0 { usize::MAX } else { count as usize };
+            let expected: Vec<_> = expected.into_iter().skip(offset as usize).take(count).collect();
+            let by = By::Score(ScoreBound::Inclusive(f64::from(min)), ScoreBound::Inclusive(f64::from(max)));
+            let query = Range { by, reverse, limit: Some((offset, count as i64)) };
+            assert_eq!(command(&store, &key, &query)?, expected)
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use super::super::domain::Data;
+use super::super::errors::ApplicationError;
+use super::utilities::get_sorted_set;
+use std::collections::HashMap;
+
+pub fn command(
+    store: &HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    member: &[u8],
+) -> Result<Option<usize>, ApplicationError> {
+    Ok(get_sorted_set(store, key)?.and_then(|set| set.rank(member)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::add::{self, Options};
+    use super::{command, Data, HashMap};
+    use proptest::collection::{hash_map, vec};
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn ranks_count_lower_scores(
+            key in vec(any::<u8>(), 0..32),
+            scores in hash_map(vec(any::<u8>(), 0..4), -1000i32..1000, 1..100)
+        ) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            let pairs = scores.iter().map(|(m, s)| (f64::from(*s), m.clone())).collect();
+            add::command(&mut store, &key, Options::default(), pairs)?;
+            scores.iter().try_for_each(|(member, score)| {
+                let below = scores.iter().filter(|(m, s)| (*s, *m) < (score, member)).count();
+                prop_assert_eq!(command(&store, &key, member)?, Some(below));
+                Ok(())
+            })?;
+            assert_eq!(command(&store, &key, b"not a member at all")?, None)
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use super::super::domain::Data;
+use super::super::errors::ApplicationError;
+use super::utilities::{get_sorted_set_mut, remove_if_empty};
+use std::collections::HashMap;
+
+pub fn command(
+    store: &mut HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    members: &[Vec<u8>],
+) -> Result<usize, ApplicationError> {
+    let set = match get_sorted_set_mut(store, key)? {
+        Some(set) => set,
+        None => return Ok(0),
+    };
+    let removed = members
+        .iter()
+        .filter(|member| set.remove(member).is_some())
+        .count();
+    remove_if_empty(store, key);
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::add::{self, Options};
+    use super::super::rank;
+    use super::{command, Data, HashMap};
+    use proptest::collection::{hash_map, vec};
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn removed_members_leave_the_ranks(
+            key in vec(any::<u8>(), 0..32),
+            scores in hash_map(vec(any::<u8>(), 0..3), -100i32..100, 1..100),
+            removed in vec(vec(any::<u8>(), 0..3), 1..50)
+        ) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            let pairs = scores.iter().map(|(m, s)| (f64::from(*s), m.clone())).collect();
+            add::command(&mut store, &key, Options::default(), pairs)?;
+            let present = removed.iter().collect::<std::collections::HashSet<_>>()
+                .into_iter()
+                .filter(|m| scores.contains_key(*m))
+                .count();
+            assert_eq!(command(&mut store, &key, &removed)?, present);
+            let mut kept: Vec<(i32, &Vec<u8>)> = scores.iter()
+                .filter(|(m, _)| !removed.contains(m))
+                .map(|(m, s)| (*s, m))
+                .collect();
+            kept.sort();
+            kept.iter().enumerate().try_for_each(|(i, (_, m))| {
+                prop_assert_eq!(rank::command(&store, &key, m)?, Some(i));
+                Ok(())
+            })?;
+            assert_eq!(store.is_empty(), kept.is_empty())
+        }
+    }
+}
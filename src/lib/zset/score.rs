@@ -0,0 +1,29 @@
+use super::super::domain::Data;
+use super::super::errors::ApplicationError;
+use super::utilities::get_sorted_set;
+use std::collections::HashMap;
+
+pub fn command(
+    store: &HashMap<Vec<u8>, Data>,
+    key: &[u8],
+    member: &[u8],
+) -> Result<Option<f64>, ApplicationError> {
+    Ok(get_sorted_set(store, key)?.and_then(|set| set.score(member)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{command, Data, HashMap};
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn needs_a_sorted_set(key in vec(any::<u8>(), 0..32), member in vec(any::<u8>(), 0..4)) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            assert_eq!(command(&store, &key, &member)?, None);
+            store.insert(key.clone(), Data::from(1));
+            command(&store, &key, &member).unwrap_err();
+        }
+    }
+}
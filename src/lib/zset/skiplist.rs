@@ -0,0 +1,316 @@
+use super::super::random::Random;
+
+/// The most levels a node can have, plenty for 2^64 elements at P = 1/4.
+const MAX_LEVEL: usize = 32;
+
+/// A link to the next node on one level, along with how many nodes it
+/// skips over, which is what lets the list find ranks in O(log n).
+#[derive(Debug, Clone, Copy)]
+struct Link {
+    next: Option<usize>,
+    span: usize,
+}
+
+const EMPTY: Link = Link {
+    next: None,
+    span: 0,
+};
+
+#[derive(Debug, Clone)]
+struct Node {
+    member: Vec<u8>,
+    score: f64,
+    links: Vec<Link>,
+    prev: Option<usize>,
+}
+
+/// The skiplist Redis orders sorted sets with, ordered by score and then by
+/// member, with every link knowing its span. Nodes live in an arena and
+/// refer to each other by index; `None` stands for the head.
+#[derive(Debug, Clone)]
+pub struct SkipList {
+    head: Vec<Link>,
+    level: usize,
+    nodes: Vec<Node>,
+    free: Vec<usize>,
+    len: usize,
+    random: Random,
+}
+
+fn precedes(node: &Node, score: f64, member: &[u8]) -> bool {
+    node.score < score || (node.score == score && &node.member[..] < member)
+}
+
+impl SkipList {
+    pub fn new() -> Self {
+        SkipList {
+            head: vec![EMPTY; MAX_LEVEL],
+            level: 1,
+            nodes: Vec::new(),
+            free: Vec::new(),
+            len: 0,
+            random: Random::with_seed(0x2545_f491_4f6c_dd1d),
+        }
+    }
+
+    fn link(&self, at: Option<usize>, level: usize) -> Link {
+        match at {
+            Some(node) => self.nodes[node].links[level],
+            None => self.head[level],
+        }
+    }
+
+    fn link_mut(&mut self, at: Option<usize>, level: usize) -> &mut Link {
+        match at {
+            Some(node) => &mut self.nodes[node].links[level],
+            None => &mut self.head[level],
+        }
+    }
+
+    /// Each level is a quarter as likely as the one below it.
+    fn random_level(&mut self) -> usize {
+        let mut level = 1;
+        loop {
+            if level == MAX_LEVEL || self.random.next_u64() & 3 != 0 {
+                return level;
+            }
+            level += 1;
+        }
+    }
+
+    /// Walks down to the last node `before` holds for, recording where the
+    /// walk left each level and the rank it had reached there.
+    fn descend<F>(&self, before: F) -> ([Option<usize>; MAX_LEVEL], [usize; MAX_LEVEL])
+    where
+        F: Fn(&Node) -> bool,
+    {
+        let mut update = [None; MAX_LEVEL];
+        let mut rank = [0; MAX_LEVEL];
+        let mut at = None;
+        for i in (0..self.level).rev() {
+            rank[i] = if i + 1 == self.level { 0 } else { rank[i + 1] };
+            while let Some(next) = self.link(at, i).next {
+                if !before(&self.nodes[next]) {
+                    break;
+                }
+                rank[i] += self.link(at, i).span;
+                at = Some(next);
+            }
+            update[i] = at;
+        }
+        (update, rank)
+    }
+
+    /// Inserts a member that isn't in the list yet.
+    pub fn insert(&mut self, score: f64, member: Vec<u8>) {
+        let (mut update, mut rank) = self.descend(|node| precedes(node, score, &member));
+        let level = self.random_level();
+        if level > self.level {
+            for i in self.level..level {
+                rank[i] = 0;
+                update[i] = None;
+                self.head[i].span = self.len;
+            }
+            self.level = level;
+        }
+        let node = Node {
+            member,
+            score,
+            links: vec![EMPTY; level],
+            prev: update[0],
+        };
+        let id = match self.free.pop() {
+            Some(id) => {
+                self.nodes[id] = node;
+                id
+            }
+            None => {
+                self.nodes.push(node);
+                self.nodes.len() - 1
+            }
+        };
+        for i in 0..level {
+            let before = self.link(update[i], i);
+            self.nodes[id].links[i] = Link {
+                next: before.next,
+                span: before.span - (rank[0] - rank[i]),
+            };
+            *self.link_mut(update[i], i) = Link {
+                next: Some(id),
+                span: rank[0] - rank[i] + 1,
+            };
+        }
+        for (i, at) in update.iter().enumerate().take(self.level).skip(level) {
+            self.link_mut(*at, i).span += 1;
+        }
+        if let Some(next) = self.nodes[id].links[0].next {
+            self.nodes[next].prev = Some(id);
+        }
+        self.len += 1;
+    }
+
+    /// Removes a member, returning whether it was there with that score.
+    pub fn remove(&mut self, score: f64, member: &[u8]) -> bool {
+        let (update, _) = self.descend(|node| precedes(node, score, member));
+        let id = match self.link(update[0], 0).next {
+            Some(id) if self.nodes[id].score == score && self.nodes[id].member == member => id,
+            _ => return false,
+        };
+        for (i, at) in update.iter().enumerate().take(self.level) {
+            let removed = self.nodes[id].links.get(i).copied();
+            let before = self.link_mut(*at, i);
+            match removed {
+                Some(removed) if before.next == Some(id) => {
+                    before.span += removed.span;
+                    before.span -= 1;
+                    before.next = removed.next;
+                }
+                _ => before.span -= 1,
+            }
+        }
+        if let Some(next) = self.nodes[id].links[0].next {
+            self.nodes[next].prev = self.nodes[id].prev;
+        }
+        while self.level > 1 && self.head[self.level - 1].next.is_none() {
+            self.level -= 1;
+        }
+        self.nodes[id].member = Vec::new();
+        self.nodes[id].links = Vec::new();
+        self.free.push(id);
+        self.len -= 1;
+        true
+    }
+
+    /// The 0-based rank of a member with the given score.
+    pub fn rank(&self, score: f64, member: &[u8]) -> Option<usize> {
+        let (update, rank) = self.descend(|node| precedes(node, score, member));
+        let at = self.link(update[0], 0).next?;
+        if self.nodes[at].score == score && self.nodes[at].member == member {
+            Some(rank[0])
+        } else {
+            None
+        }
+    }
+
+    /// How many members `before` holds for, provided it holds for a prefix
+    /// of the list.
+    pub fn count_while<F>(&self, before: F) -> usize
+    where
+        F: Fn(f64, &[u8]) -> bool,
+    {
+        self.descend(|node| before(node.score, &node.member)).1[0]
+    }
+
+    fn node_at(&self, rank: usize) -> Option<usize> {
+        let mut traversed = 0;
+        let mut at = None;
+        for i in (0..self.level).rev() {
+            while let Some(next) = self.link(at, i).next {
+                if traversed + self.link(at, i).span > rank + 1 {
+                    break;
+                }
+                traversed += self.link(at, i).span;
+                at = Some(next);
+            }
+            if traversed == rank + 1 {
+                return at;
+            }
+        }
+        None
+    }
+
+    /// The members from the given rank on, towards the tail or, when
+    /// `reverse` is set, towards the head.
+    pub fn iter_from(&self, rank: usize, reverse: bool) -> Iter<'_> {
+        Iter {
+            list: self,
+            at: self.node_at(rank),
+            reverse,
+        }
+    }
+}
+
+impl Default for SkipList {
+    fn default() -> Self {
+        SkipList::new()
+    }
+}
+
+pub struct Iter<'a> {
+    list: &'a SkipList,
+    at: Option<usize>,
+    reverse: bool,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = (&'a [u8], f64);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = &self.list.nodes[self.at?];
+        self.at = if self.reverse {
+            node.prev
+        } else {
+            node.links[0].next
+        };
+        Some((&node.member, node.score))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::SkipList;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+    use std::collections::BTreeMap;
+
+    /// Scores in a small range, so that ties on score are common.
+    fn entries() -> impl Strategy<Value = Vec<(i8, Vec<u8>, bool)>> {
+        vec((-8i8..8, vec(any::<u8>(), 0..3), any::<bool>()), 0..300)
+    }
+
+    proptest! {
+        #[test]
+        fn agrees_with_a_sorted_model(entries in entries()) {
+            let mut list = SkipList::new();
+            let mut model: BTreeMap<(i8, Vec<u8>), ()> = BTreeMap::new();
+            let mut scores: BTreeMap<Vec<u8>, i8> = BTreeMap::new();
+            for (score, member, remove) in entries {
+                if let Some(old) = scores.remove(&member) {
+                    assert!(list.remove(f64::from(old), &member));
+                    model.remove(&(old, member.clone()));
+                }
+                if !remove {
+                    list.insert(f64::from(score), member.clone());
+                    model.insert((score, member.clone()), ());
+                    scores.insert(member, score);
+                }
+            }
+            assert_eq!(list.len, model.len());
+            let forward: Vec<(i8, Vec<u8>)> = list.iter_from(0, false)
+                .map(|(member, score)| (score as i8, member.to_vec()))
+                .collect();
+            assert_eq!(&forward, &model.keys().cloned().collect::<Vec<_>>());
+            forward.iter().enumerate().try_for_each(|(rank, (score, member))| {
+                prop_assert_eq!(list.rank(f64::from(*score), member), Some(rank));
+                let at: Vec<(i8, Vec<u8>)> = list.iter_from(rank, true)
+                    .map(|(member, score)| (score as i8, member.to_vec()))
+                    .collect();
+                let mut expected = forward[..=rank].to_vec();
+                expected.reverse();
+                prop_assert_eq!(at, expected);
+                Ok(())
+            })?;
+            assert_eq!(list.iter_from(model.len(), false).count(), 0)
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn counts_prefixes(scores in vec(-100i32..100, 0..200), bound in -100i32..100) {
+            let mut list = SkipList::new();
+            scores.iter().enumerate().for_each(|(i, score)| list.insert(f64::from(*score), i.to_be_bytes().to_vec()));
+            let expected = scores.iter().filter(|score| **score < bound).count();
+            assert_eq!(list.count_while(|score, _| score < f64::from(bound)), expected)
+        }
+    }
+}
@@ -0,0 +1,218 @@
+use super::skiplist::{Iter, SkipList};
+use std::collections::HashMap;
+
+/// One end of a range of scores.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ScoreBound {
+    Inclusive(f64),
+    Exclusive(f64),
+}
+
+/// One end of a range of members, for sorted sets whose members all have
+/// the same score and so are ordered by their bytes.
+#[derive(Debug, PartialEq, Clone)]
+pub enum LexBound {
+    Min,
+    Max,
+    Inclusive(Vec<u8>),
+    Exclusive(Vec<u8>),
+}
+
+/// Members ordered by score, with ties broken by comparing their bytes. As
+/// in Redis, a table finds a member's score and a skiplist keeps the order.
+#[derive(Debug, Clone)]
+pub struct SortedSet {
+    scores: HashMap<Vec<u8>, f64>,
+    order: SkipList,
+}
+
+impl SortedSet {
+    pub fn new() -> Self {
+        SortedSet {
+            scores: HashMap::new(),
+            order: SkipList::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.scores.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scores.is_empty()
+    }
+
+    pub fn score(&self, member: &[u8]) -> Option<f64> {
+        self.scores.get(member).copied()
+    }
+
+    /// Adds a member or moves it to a new score, returning its old score.
+    pub fn insert(&mut self, member: Vec<u8>, score: f64) -> Option<f64> {
+        let old = self.scores.insert(member.clone(), score);
+        if let Some(old) = old {
+            self.order.remove(old, &member);
+        }
+        self.order.insert(score, member);
+        old
+    }
+
+    pub fn remove(&mut self, member: &[u8]) -> Option<f64> {
+        let score = self.scores.remove(member)?;
+        self.order.remove(score, member);
+        Some(score)
+    }
+
+    /// The 0-based rank of a member, counting from the lowest score.
+    pub fn rank(&self, member: &[u8]) -> Option<usize> {
+        let score = self.score(member)?;
+        self.order.rank(score, member)
+    }
+
+    /// The ranks of the first and last members scored within the bounds.
+    pub fn score_range(&self, min: ScoreBound, max: ScoreBound) -> Option<(usize, usize)> {
+        let first = self.order.count_while(|score, _| match min {
+            ScoreBound::Inclusive(min) => score < min,
+            ScoreBound::Exclusive(min) => score <= min,
+        });
+        let end = self.order.count_while(|score, _| match max {
+            ScoreBound::Inclusive(max) => score <= max,
+            ScoreBound::Exclusive(max) => score < max,
+        });
+        if first < end {
+            Some((first, end - 1))
+        } else {
+            None
+        }
+    }
+
+    /// The ranks of the first and last members within the bounds, assuming
+    /// all members have the same score.
+    pub fn lex_range(&self, min: &LexBound, max: &LexBound) -> Option<(usize, usize)> {
+        let count_below = |bytes: &[u8]| self.order.count_while(|_, member| member < bytes);
+        let count_up_to = |bytes: &[u8]| self.order.count_while(|_, member| member <= bytes);
+        let first = match min {
+            LexBound::Min => 0,
+            LexBound::Max => self.len(),
+            LexBound::Inclusive(min) => count_below(min),
+            LexBound::Exclusive(min) => count_up_to(min),
+        };
+        let end = match max {
+            LexBound::Min => 0,
+            LexBound::Max => self.len(),
+            LexBound::Inclusive(max) => count_up_to(max),
+            LexBound::Exclusive(max) => count_below(max),
+        };
+        if first < end {
+            Some((first, end - 1))
+        } else {
+            None
+        }
+    }
+
+    /// The members and scores from the given rank on, towards the highest
+    /// score or, when `reverse` is set, towards the lowest.
+    pub fn iter_from(&self, rank: usize, reverse: bool) -> Iter<'_> {
+        self.order.iter_from(rank, reverse)
+    }
+
+    pub fn iter(&self) -> Iter<'_> {
+        self.iter_from(0, false)
+    }
+}
+
+impl Default for SortedSet {
+    fn default() -> Self {
+        SortedSet::new()
+    }
+}
+
+impl PartialEq for SortedSet {
+    fn eq(&self, other: &Self) -> bool {
+        self.scores == other.scores
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{LexBound, ScoreBound, SortedSet};
+    use proptest::collection::{hash_map, vec};
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn score_ranges_hold_the_scores_within(
+            members in hash_map(vec(any::<u8>(), 0..4), -20i32..20, 0..100),
+            min in -25i32..25,
+            max in -25i32..25,
+            min_exclusive in any::<bool>(),
+            max_exclusive in any::<bool>()
+        ) {
+            let mut set = SortedSet::new();
+            members.iter().for_each(|(member, score)| { set.insert(member.clone(), f64::from(*score)); });
+            let (min, max) = (f64::from(min), f64::from(max));
+            let within = |score: f64| {
+                (if min_exclusive { score > min } else { score >= min })
+                    && (if max_exclusive { score < max } else { score <= max })
+            };
+            let bound = |n, exclusive| if exclusive { ScoreBound::Exclusive(n) } else { ScoreBound::Inclusive(n) };
+            let expected: Vec<f64> = set.iter().map(|(_, score)| score).filter(|s| within(*s)).collect();
+            let found: Vec<f64> = match set.score_range(bound(min, min_exclusive), bound(max, max_exclusive)) {
+                Some((first, last)) => set.iter_from(first, false).take(last - first + 1).map(|(_, s)| s).collect(),
+                None => vec![],
+            };
+            assert_eq!(found, expected)
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn lex_ranges_hold_the_members_within(
+            members in vec(vec(any::<u8>(), 0..3), 0..100),
+            min in vec(any::<u8>(), 0..3),
+            max in vec(any::<u8>(), 0..3),
+            kinds in (0..4, 0..4)
+        ) {
+            let mut set = SortedSet::new();
+            members.into_iter().for_each(|member| { set.insert(member, 0.0); });
+            let bound = |bytes: &Vec<u8>, kind| match kind {
+                0 => LexBound::Min,
+                1 => LexBound::Max,
+                2 => LexBound::Inclusive(bytes.clone()),
+                _ => LexBound::Exclusive(bytes.clone()),
+            };
+            let (min, max) = (bound(&min, kinds.0), bound(&max, kinds.1));
+            let above = |member: &[u8]| match &min {
+                LexBound::Min => true,
+                LexBound::Max => false,
+                LexBound::Inclusive(b) => member >= &b[..],
+                LexBound::Exclusive(b) => member > &b[..],
+            };
+            let below = |member: &[u8]| match &max {
+                LexBound::Min => false,
+                LexBound::Max => true,
+                LexBound::Inclusive(b) => member <= &b[..],
+                LexBound::Exclusive(b) => member < &b[..],
+            };
+            let expected: Vec<&[u8]> = set.iter().map(|(m, _)| m).filter(|m| above(m) && below(m)).collect();
+            let found: Vec<&[u8]> = match set.lex_range(&min, &max) {
+                Some((first, last)) => set.iter_from(first, false).take(last - first + 1).map(|(m, _)| m).collect(),
+                None => vec![],
+            };
+            assert_eq!(found, expected)
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn ranks_follow_scores(members in hash_map(vec(any::<u8>(), 0..4), any::<i32>(), 1..100)) {
+            let mut set = SortedSet::new();
+            members.iter().for_each(|(member, score)| { set.insert(member.clone(), f64::from(*score)); });
+            let mut sorted: Vec<(i32, &Vec<u8>)> = members.iter().map(|(m, s)| (*s, m)).collect();
+            sorted.sort();
+            sorted.iter().enumerate().try_for_each(|(rank, (_, member))| {
+                prop_assert_eq!(set.rank(member), Some(rank));
+                Ok(())
+            })?
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use super::super::domain::Data;
+use super::super::errors::ApplicationError;
+use super::utilities::{get_scores, store_result, weigh};
+use super::{Aggregate, SortedSet};
+use std::collections::HashMap;
+
+pub fn command(
+    store: &HashMap<Vec<u8>, Data>,
+    keys: &[Vec<u8>],
+    weights: &[f64],
+    aggregate: Aggregate,
+) -> Result<SortedSet, ApplicationError> {
+    let mut union: HashMap<Vec<u8>, f64> = HashMap::new();
+    for (key, weight) in keys.iter().zip(weights) {
+        get_scores(store, key)?
+            .into_iter()
+            .for_each(|(member, score)| {
+                let score = weigh(score, *weight);
+                union
+                    .entry(member)
+                    .and_modify(|total| *total = aggregate.combine(*total, score))
+                    .or_insert(score);
+            });
+    }
+    let mut result = SortedSet::new();
+    union.into_iter().for_each(|(member, score)| {
+        result.insert(member, score);
+    });
+    Ok(result)
+}
+
+pub fn store_command(
+    store: &mut HashMap<Vec<u8>, Data>,
+    destination: &[u8],
+    keys: &[Vec<u8>],
+    weights: &[f64],
+    aggregate: Aggregate,
+) -> Result<usize, ApplicationError> {
+    let union = command(store, keys, weights, aggregate)?;
+    Ok(store_result(store, destination, union))
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::domain::{Data, Primitive};
+    use super::super::add::{self, Options};
+    use super::super::{score, Aggregate};
+    use super::store_command;
+    use proptest::collection::{hash_map, hash_set, vec};
+    use proptest::prelude::*;
+    use std::collections::HashMap;
+
+    fn valid_keys() -> impl Strategy<Value = Vec<u8>> {
+        vec(any::<u8>(), 1..16)
+    }
+
+    fn scores() -> impl Strategy<Value = HashMap<Vec<u8>, i32>> {
+        hash_map(vec(any::<u8>(), 0..3), -1000i32..1000, 1..100)
+    }
+
+    fn insert(
+        store: &mut HashMap<Vec<u8>, Data>,
+        key: &[u8],
+        scores: &HashMap<Vec<u8>, i32>,
+    ) -> Result<(), TestCaseError> {
+        let pairs = scores
+            .iter()
+            .map(|(m, s)| (f64::from(*s), m.clone()))
+            .collect();
+        add::command(store, key, Options::default(), pairs)?;
+        Ok(())
+    }
+
+    proptest! {
+        #[test]
+        fn union_is_idempotent(
+            dest in valid_keys(),
+            snd_dest in valid_keys(),
+            a in (valid_keys(), scores()),
+            b in (valid_keys(), scores()),
+            aggregate in prop_oneof![Just(Aggregate::Min), Just(Aggregate::Max)]
+        ) {
+            prop_assume!(dest != snd_dest && dest != b.0);
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            insert(&mut store, &a.0, &a.1)?;
+            insert(&mut store, &b.0, &b.1)?;
+            store_command(&mut store, &dest, &[a.0, b.0.clone()], &[1.0, 1.0], aggregate)?;
+            store_command(&mut store, &snd_dest, &[dest.clone(), b.0], &[1.0, 1.0], aggregate)?;
+            assert_eq!(store.get(&dest), store.get(&snd_dest))
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn sums_weighted_scores(
+            dest in valid_keys(),
+            a in (valid_keys(), scores()),
+            b in (valid_keys(), scores()),
+            weights in (-10i32..10, -10i32..10)
+        ) {
+            prop_assume!(a.0 != b.0);
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            insert(&mut store, &a.0, &a.1)?;
+            insert(&mut store, &b.0, &b.1)?;
+            let weights = [f64::from(weights.0), f64::from(weights.1)];
+            let size = store_command(&mut store, &dest, &[a.0.clone(), b.0.clone()], &weights, Aggregate::Sum)?;
+            let members: std::collections::HashSet<&Vec<u8>> = a.1.keys().chain(b.1.keys()).collect();
+            assert_eq!(size, members.len());
+            members.into_iter().try_for_each(|m| {
+                let expected = f64::from(*a.1.get(m).unwrap_or(&0)) * weights[0]
+                    + f64::from(*b.1.get(m).unwrap_or(&0)) * weights[1];
+                prop_assert_eq!(score::command(&store, &dest, m)?, Some(expected));
+                Ok(())
+            })?
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn plain_sets_score_one(
+            dest in valid_keys(),
+            a in (valid_keys(), hash_set(any::<Primitive>(), 1..100)),
+            weight in -10i32..10
+        ) {
+            let mut store: HashMap<Vec<u8>, Data> = HashMap::new();
+            let (a_key, a_set) = a;
+            store.insert(a_key.clone(), a_set.clone().into());
+            store_command(&mut store, &dest, &[a_key, b"missing".to_vec()], &[f64::from(weight), 1.0], Aggregate::Sum)?;
+            a_set.into_iter().try_for_each(|m| {
+                prop_assert_eq!(score::command(&store, &dest, &m.to_bytes())?, Some(f64::from(weight)));
+                Ok(())
+            })?
+        }
+    }
+}
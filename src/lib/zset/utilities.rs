@@ -0,0 +1,94 @@
+use super::super::domain::{Data, Escaped};
+use super::super::errors::ApplicationError;
+use super::SortedSet;
+use std::collections::HashMap;
+
+pub fn get_sorted_set<'a>(
+    store: &'a HashMap<Vec<u8>, Data>,
+    key: &[u8],
+) -> Result<Option<&'a SortedSet>, ApplicationError> {
+    match store.get(key) {
+        Some(Data::SortedSet(set)) => Ok(Some(set)),
+        Some(_) => Err(format!("Value at {} is not a sorted set", Escaped(key)).into()),
+        None => Ok(None),
+    }
+}
+
+pub fn get_sorted_set_mut<'a>(
+    store: &'a mut HashMap<Vec<u8>, Data>,
+    key: &[u8],
+) -> Result<Option<&'a mut SortedSet>, ApplicationError> {
+    match store.get_mut(key) {
+        Some(Data::SortedSet(set)) => Ok(Some(set)),
+        Some(_) => Err(format!("Value at {} is not a sorted set", Escaped(key)).into()),
+        None => Ok(None),
+    }
+}
+
+/// Gets the sorted set at `key` for writing, creating an empty one if need
+/// be. Callers remove it again if they leave it empty.
+pub fn get_or_create_sorted_set<'a>(
+    store: &'a mut HashMap<Vec<u8>, Data>,
+    key: &[u8],
+) -> Result<&'a mut SortedSet, ApplicationError> {
+    match store
+        .entry(key.to_vec())
+        .or_insert_with(|| Data::SortedSet(SortedSet::new()))
+    {
+        Data::SortedSet(set) => Ok(set),
+        _ => Err(format!("Value at {} is not a sorted set", Escaped(key)).into()),
+    }
+}
+
+/// Redis never keeps an empty sorted set around.
+pub fn remove_if_empty(store: &mut HashMap<Vec<u8>, Data>, key: &[u8]) {
+    if let Some(Data::SortedSet(set)) = store.get(key) {
+        if set.is_empty() {
+            store.remove(key);
+        }
+    }
+}
+
+/// The members and scores of an input to sorted set algebra. Plain sets
+/// count as sorted sets whose members all score 1, and missing keys as
+/// empty sets.
+pub fn get_scores(
+    store: &HashMap<Vec<u8>, Data>,
+    key: &[u8],
+) -> Result<HashMap<Vec<u8>, f64>, ApplicationError> {
+    match store.get(key) {
+        Some(Data::SortedSet(set)) => Ok(set
+            .iter()
+            .map(|(member, score)| (member.to_vec(), score))
+            .collect()),
+        Some(Data::Set(set)) => Ok(set.iter().map(|member| (member.to_bytes(), 1.0)).collect()),
+        Some(_) => Err(format!("Value at {} is not a sorted set", Escaped(key)).into()),
+        None => Ok(HashMap::new()),
+    }
+}
+
+/// A score scaled by its input's weight, where infinity times 0 is 0.
+pub fn weigh(score: f64, weight: f64) -> f64 {
+    let weighted = score * weight;
+    if weighted.is_nan() {
+        0.0
+    } else {
+        weighted
+    }
+}
+
+/// Stores the result of sorted set algebra, or deletes the destination if
+/// the result is empty, returning its size.
+pub fn store_result(
+    store: &mut HashMap<Vec<u8>, Data>,
+    destination: &[u8],
+    result: SortedSet,
+) -> usize {
+    let size = result.len();
+    if size == 0 {
+        store.remove(destination);
+    } else {
+        store.insert(destination.to_vec(), Data::SortedSet(result));
+    }
+    size
+}